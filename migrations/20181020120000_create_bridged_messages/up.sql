@@ -0,0 +1,11 @@
+CREATE TABLE bridged_messages (
+  matrix_event_id VARCHAR NOT NULL,
+  matrix_room_id VARCHAR NOT NULL,
+  rocketchat_message_id VARCHAR NOT NULL,
+  rocketchat_room_id VARCHAR NOT NULL,
+  rocketchat_server_id VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT bridged_messages_pk PRIMARY KEY (matrix_event_id)
+  UNIQUE (rocketchat_server_id, rocketchat_message_id)
+)
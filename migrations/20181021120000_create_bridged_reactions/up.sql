@@ -0,0 +1,12 @@
+CREATE TABLE bridged_reactions (
+  matrix_event_id VARCHAR NOT NULL,
+  matrix_room_id VARCHAR NOT NULL,
+  rocketchat_message_id VARCHAR NOT NULL,
+  rocketchat_server_id VARCHAR NOT NULL,
+  rocketchat_user_id VARCHAR NOT NULL,
+  emoji VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT bridged_reactions_pk PRIMARY KEY (matrix_event_id)
+  UNIQUE (rocketchat_server_id, rocketchat_message_id, rocketchat_user_id, emoji)
+)
@@ -0,0 +1,16 @@
+CREATE TABLE bridged_messages_without_thread_id (
+  matrix_event_id VARCHAR NOT NULL,
+  matrix_room_id VARCHAR NOT NULL,
+  rocketchat_message_id VARCHAR NOT NULL,
+  rocketchat_room_id VARCHAR NOT NULL,
+  rocketchat_server_id VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT bridged_messages_pk PRIMARY KEY (matrix_event_id)
+  UNIQUE (rocketchat_server_id, rocketchat_message_id)
+);
+INSERT INTO bridged_messages_without_thread_id
+  SELECT matrix_event_id, matrix_room_id, rocketchat_message_id, rocketchat_room_id, rocketchat_server_id, created_at, updated_at
+  FROM bridged_messages;
+DROP TABLE bridged_messages;
+ALTER TABLE bridged_messages_without_thread_id RENAME TO bridged_messages;
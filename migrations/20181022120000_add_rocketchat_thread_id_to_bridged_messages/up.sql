@@ -0,0 +1 @@
+ALTER TABLE bridged_messages ADD COLUMN rocketchat_thread_id VARCHAR;
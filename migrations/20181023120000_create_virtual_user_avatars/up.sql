@@ -0,0 +1,9 @@
+CREATE TABLE virtual_user_avatars (
+  matrix_user_id VARCHAR NOT NULL,
+  rocketchat_server_id VARCHAR NOT NULL,
+  avatar_etag VARCHAR NOT NULL,
+  avatar_url VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT virtual_user_avatars_pk PRIMARY KEY (matrix_user_id)
+)
@@ -0,0 +1 @@
+DROP TABLE bridged_rooms;
@@ -0,0 +1,10 @@
+CREATE TABLE bridged_rooms (
+  matrix_room_id VARCHAR NOT NULL,
+  rocketchat_server_id VARCHAR NOT NULL,
+  rocketchat_channel_id VARCHAR NOT NULL,
+  kind VARCHAR,
+  created_by VARCHAR,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT bridged_rooms_pk PRIMARY KEY (matrix_room_id)
+  UNIQUE (rocketchat_server_id, rocketchat_channel_id)
+)
@@ -0,0 +1,7 @@
+CREATE TABLE admin_rooms (
+  matrix_room_id VARCHAR NOT NULL,
+  matrix_user_id VARCHAR NOT NULL,
+  rocketchat_server_id VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT admin_rooms_pk PRIMARY KEY (matrix_room_id)
+)
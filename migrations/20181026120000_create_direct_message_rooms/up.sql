@@ -0,0 +1,8 @@
+CREATE TABLE direct_message_rooms (
+  matrix_room_id VARCHAR NOT NULL,
+  rocketchat_server_id VARCHAR NOT NULL,
+  rocketchat_channel_id VARCHAR NOT NULL,
+  matrix_query_user_id VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT direct_message_rooms_pk PRIMARY KEY (matrix_room_id)
+)
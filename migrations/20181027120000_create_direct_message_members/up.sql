@@ -0,0 +1,8 @@
+CREATE TABLE direct_message_members (
+  rocketchat_server_id VARCHAR NOT NULL,
+  rocketchat_channel_id VARCHAR NOT NULL,
+  rocketchat_user_id VARCHAR NOT NULL,
+  rocketchat_username VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT direct_message_members_pk PRIMARY KEY (rocketchat_server_id, rocketchat_channel_id, rocketchat_user_id)
+)
@@ -0,0 +1,8 @@
+CREATE TABLE direct_message_receivers (
+  rocketchat_server_id VARCHAR NOT NULL,
+  rocketchat_channel_id VARCHAR NOT NULL,
+  matrix_user_id VARCHAR NOT NULL,
+  matrix_room_id VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT direct_message_receivers_pk PRIMARY KEY (rocketchat_server_id, rocketchat_channel_id, matrix_user_id)
+)
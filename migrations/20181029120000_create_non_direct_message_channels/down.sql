@@ -0,0 +1 @@
+DROP TABLE non_direct_message_channels;
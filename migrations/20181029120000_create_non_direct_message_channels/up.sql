@@ -0,0 +1,6 @@
+CREATE TABLE non_direct_message_channels (
+  rocketchat_server_id VARCHAR NOT NULL,
+  rocketchat_channel_id VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT non_direct_message_channels_pk PRIMARY KEY (rocketchat_server_id, rocketchat_channel_id)
+)
@@ -0,0 +1,5 @@
+CREATE TABLE completed_backfills (
+  name VARCHAR NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  CONSTRAINT completed_backfills_pk PRIMARY KEY (name)
+)
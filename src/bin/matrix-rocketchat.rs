@@ -33,19 +33,32 @@ fn main() {
     }
 }
 
-fn run() -> Result<Listening> {
+fn run() -> Result<Option<Listening>> {
     let matches = App::new("matrix-rocketchat")
         .version("0.1")
         .author("Andreas Studer <foss@exul.org>")
         .about("An application service to bridge Matrix and Rocket.Chat.")
         .arg(Arg::with_name("config").short("c").long("config").help("Path to config file").takes_value(true))
+        .arg(
+            Arg::with_name("remove-server")
+                .long("remove-server")
+                .help("Removes a Rocket.Chat server that is no longer used and exits")
+                .value_name("ROCKETCHAT_SERVER_ID")
+                .takes_value(true),
+        )
         .get_matches();
 
     let config_path = matches.value_of("config").unwrap_or("config.yaml").to_string();
     let config = Config::read_from_file(&config_path).chain_err(|| ErrorKind::ReadFileError(config_path))?;
     let log = build_logger(&config);
+
+    if let Some(rocketchat_server_id) = matches.value_of("remove-server") {
+        Server::new(&config, log).remove_rocketchat_server(rocketchat_server_id)?;
+        return Ok(None);
+    }
+
     let threads = num_cpus::get() * 8;
-    Server::new(&config, log).run(threads)
+    Server::new(&config, log).run(threads).map(Some)
 }
 
 fn build_logger(config: &Config) -> slog::Logger {
@@ -5,9 +5,10 @@ use ruma_client_api::unversioned::get_supported_versions::{
     Endpoint as GetSupportedVersionsEndpoint, Response as GetSupportedVersionsResponse,
 };
 use ruma_client_api::Endpoint;
+use ruma_events::room::join_rules::JoinRule;
 use ruma_events::room::member::MemberEvent;
 use ruma_events::room::message::MessageType;
-use ruma_identifiers::{RoomAliasId, RoomId, UserId};
+use ruma_identifiers::{EventId, RoomAliasId, RoomId, UserId};
 use serde_json;
 use slog::Logger;
 
@@ -18,6 +19,50 @@ use errors::*;
 /// Matrix REST API v0
 pub mod r0;
 
+/// The relation of a message that is sent to a room to an existing event
+#[derive(Clone, Debug)]
+pub enum MessageRelation {
+    /// The message is a reply to the event
+    Reply(EventId),
+    /// The message is part of the thread that was started by the `root` event. Clients that don't
+    /// support threads display the message as reply to the `in_reply_to` event.
+    Thread {
+        /// The first event of the thread
+        root: EventId,
+        /// The event that is used as reply fallback
+        in_reply_to: EventId,
+    },
+}
+
+/// The level that is required to redact events of other users if a room does not specify it
+pub const DEFAULT_REDACT_POWER_LEVEL: i64 = 50;
+
+/// The power levels of a room, only contains the levels that are used by the application service.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PowerLevels {
+    /// The level that is required to redact events of other users
+    #[serde(default = "default_redact_power_level")]
+    pub redact: i64,
+    /// The levels of specific users
+    #[serde(default)]
+    pub users: HashMap<String, i64>,
+    /// The level of users that are not listed in `users`
+    #[serde(default)]
+    pub users_default: i64,
+}
+
+impl PowerLevels {
+    /// Check if a user is allowed to redact events of other users.
+    pub fn can_redact(&self, user_id: &UserId) -> bool {
+        let user_level = self.users.get(&user_id.to_string()).cloned().unwrap_or(self.users_default);
+        user_level >= self.redact
+    }
+}
+
+fn default_redact_power_level() -> i64 {
+    DEFAULT_REDACT_POWER_LEVEL
+}
+
 /// Matrix REST API
 pub trait MatrixApi: Send + Sync + MatrixApiClone {
     /// Create a room.
@@ -42,6 +87,10 @@ pub trait MatrixApi: Send + Sync + MatrixApiClone {
     fn get_room_creator(&self, room_id: RoomId) -> Result<UserId>;
     /// Get the list of members for this room.
     fn get_room_members(&self, room_id: RoomId, sender_id: Option<UserId>) -> Result<Vec<MemberEvent>>;
+    /// Get the name of a room.
+    fn get_room_name(&self, room_id: RoomId) -> Result<Option<String>>;
+    /// Get the power levels of a room. Returns `None` if the room has no power levels.
+    fn get_room_power_levels(&self, room_id: RoomId) -> Result<Option<PowerLevels>>;
     /// Get the topic for a room.
     fn get_room_topic(&self, room_id: RoomId) -> Result<Option<String>>;
     /// Invite a user to a room.
@@ -54,21 +103,43 @@ pub trait MatrixApi: Send + Sync + MatrixApiClone {
     fn leave_room(&self, room_id: RoomId, user_id: UserId) -> Result<()>;
     /// Set the canonical alias for a room.
     fn put_canonical_room_alias(&self, room_id: RoomId, matrix_room_alias_id: Option<RoomAliasId>) -> Result<()>;
+    /// Add an alias to a room.
+    fn put_room_alias(&self, room_id: RoomId, matrix_room_alias_id: RoomAliasId) -> Result<()>;
+    /// Redact an event in a room.
+    fn redact_event(&self, room_id: RoomId, event_id: EventId, user_id: UserId) -> Result<()>;
     /// Register a user.
     fn register(&self, user_id_local_part: String) -> Result<()>;
-    /// Send a text message to a room.
-    fn send_text_message(&self, room_id: RoomId, user_id: UserId, body: String) -> Result<()>;
+    /// Send a text message with an HTML formatted body to a room, returns the ID of the event if
+    /// the homeserver sent it back. The message can optionally reply to another event or be part
+    /// of a thread.
+    fn send_formatted_message(
+        &self,
+        room_id: RoomId,
+        user_id: UserId,
+        body: String,
+        formatted_body: String,
+        relation: Option<MessageRelation>,
+    ) -> Result<Option<EventId>>;
+    /// Send a text message to a room, returns the ID of the event if the homeserver sent it back.
+    fn send_text_message(&self, room_id: RoomId, user_id: UserId, body: String) -> Result<Option<EventId>>;
     /// Send an data message (audio, file, image, video) to a room.
     fn send_data_message(&self, room_id: RoomId, user_id: UserId, body: String, url: String, mtype: MessageType) -> Result<()>;
+    /// Send a reaction (annotation) to an event, returns the ID of the reaction event if the
+    /// homeserver sent it back.
+    fn send_reaction(&self, room_id: RoomId, user_id: UserId, event_id: EventId, key: String) -> Result<Option<EventId>>;
     /// Set the default power levels for a room. Only the bot will be able to control the room.
     /// The power levels for invite, kick, ban, and redact are all set to 50.
     fn set_default_powerlevels(&self, room_id: RoomId, room_creator_user_id: UserId) -> Result<()>;
+    /// Set the avatar for a user, the URL has to be an MXC URL of uploaded content.
+    fn set_avatar_url(&self, user_id: UserId, avatar_url: String) -> Result<()>;
     /// Set the display name for a user
     fn set_display_name(&self, user_id: UserId, name: String) -> Result<()>;
+    /// Set the join rule for a room, it determines who is allowed to join the room.
+    fn set_room_join_rule(&self, room_id: RoomId, user_id: UserId, join_rule: JoinRule) -> Result<()>;
     /// Set the name for a room
-    fn set_room_name(&self, room_id: RoomId, name: String) -> Result<()>;
+    fn set_room_name(&self, room_id: RoomId, user_id: UserId, name: String) -> Result<()>;
     /// Set the topic for a room.
-    fn set_room_topic(&self, room_id: RoomId, topic: String) -> Result<()>;
+    fn set_room_topic(&self, room_id: RoomId, user_id: UserId, topic: String) -> Result<()>;
     /// Upload a file to the media storage
     fn upload(&self, data: Vec<u8>, content_type: HeaderValue) -> Result<String>;
 }
@@ -6,6 +6,7 @@ use http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use http::{Method, StatusCode};
 use pulldown_cmark::{html, Options, Parser};
 use ruma_client_api::r0::account::register::{self, Endpoint as RegisterEndpoint};
+use ruma_client_api::r0::alias::create_alias::{self, Endpoint as CreateAliasEndpoint};
 use ruma_client_api::r0::alias::delete_alias::Endpoint as DeleteAliasEndpoint;
 use ruma_client_api::r0::alias::get_alias::{self, Endpoint as GetAliasEndpoint};
 use ruma_client_api::r0::media::create_content::{self, Endpoint as CreateContentEndpoint};
@@ -15,6 +16,7 @@ use ruma_client_api::r0::membership::invite_user::{self, Endpoint as InviteUserE
 use ruma_client_api::r0::membership::join_room_by_id::{self, Endpoint as JoinRoomByIdEndpoint};
 use ruma_client_api::r0::membership::leave_room::{self, Endpoint as LeaveRoomEndpoint};
 use ruma_client_api::r0::profile::get_display_name::{self, Endpoint as GetDisplayNameEndpoint};
+use ruma_client_api::r0::profile::set_avatar_url::{self, Endpoint as SetAvatarUrlEndpoint};
 use ruma_client_api::r0::profile::set_display_name::{self, Endpoint as SetDisplayNameEndpoint};
 use ruma_client_api::r0::room::create_room::{self, Endpoint as CreateRoomEndpoint, RoomPreset};
 use ruma_client_api::r0::send::send_message_event::{self, Endpoint as SendMessageEventEndpoint};
@@ -25,6 +27,7 @@ use ruma_client_api::r0::sync::get_state_events_for_empty_key::{self, Endpoint a
 use ruma_client_api::r0::sync::sync_events::Endpoint as SyncEventsEndpoint;
 use ruma_client_api::Endpoint;
 use ruma_events::collections::all::Event;
+use ruma_events::room::join_rules::JoinRule;
 use ruma_events::room::member::MemberEvent;
 use ruma_events::room::message::MessageType;
 use ruma_events::EventType;
@@ -33,6 +36,7 @@ use serde_json::{self, Map, Value};
 use slog::Logger;
 use url;
 
+use api::matrix::{MessageRelation, PowerLevels};
 use api::{RequestData, RestApi};
 use config::Config;
 use errors::*;
@@ -318,6 +322,55 @@ impl super::MatrixApi for MatrixApi {
         Ok(room_member_events.chunk)
     }
 
+    fn get_room_name(&self, room_id: RoomId) -> Result<Option<String>> {
+        let path_params = get_state_events_for_empty_key::PathParams { room_id, event_type: EventType::RoomName.to_string() };
+        let endpoint = self.base_url.clone() + &GetStateEventsForEmptyKeyEndpoint::request_path(path_params);
+        let params = self.params_hash();
+
+        let (body, status_code) = RestApi::call_matrix(&GetStateEventsForEmptyKeyEndpoint::method(), &endpoint, "{}", &params)?;
+        if status_code == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !status_code.is_success() {
+            return Err(build_error(&endpoint, &body, &status_code));
+        }
+
+        let room_name_response: Value = serde_json::from_str(&body).chain_err(|| {
+            ErrorKind::InvalidJSON(format!(
+                "Could not deserialize response from Matrix get_state_events_for_empty_key API endpoint: `{}`",
+                body
+            ))
+        })?;
+
+        Ok(room_name_response["name"].as_str().map(|name| name.to_string()))
+    }
+
+    fn get_room_power_levels(&self, room_id: RoomId) -> Result<Option<PowerLevels>> {
+        let path_params =
+            get_state_events_for_empty_key::PathParams { room_id, event_type: EventType::RoomPowerLevels.to_string() };
+        let endpoint = self.base_url.clone() + &GetStateEventsForEmptyKeyEndpoint::request_path(path_params);
+        let params = self.params_hash();
+
+        let (body, status_code) = RestApi::call_matrix(&GetStateEventsForEmptyKeyEndpoint::method(), &endpoint, "{}", &params)?;
+        if status_code == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !status_code.is_success() {
+            return Err(build_error(&endpoint, &body, &status_code));
+        }
+
+        let power_levels: PowerLevels = serde_json::from_str(&body).chain_err(|| {
+            ErrorKind::InvalidJSON(format!(
+                "Could not deserialize response from Matrix get_state_events_for_empty_key API endpoint: `{}`",
+                body
+            ))
+        })?;
+
+        Ok(Some(power_levels))
+    }
+
     fn get_room_topic(&self, room_id: RoomId) -> Result<Option<String>> {
         let path_params = get_state_events_for_empty_key::PathParams { room_id, event_type: EventType::RoomTopic.to_string() };
         let endpoint = self.base_url.clone() + &GetStateEventsForEmptyKeyEndpoint::request_path(path_params);
@@ -421,6 +474,45 @@ impl super::MatrixApi for MatrixApi {
         Ok(())
     }
 
+    fn put_room_alias(&self, room_id: RoomId, matrix_room_alias_id: RoomAliasId) -> Result<()> {
+        // the ruma client api path params cannot be used here, because they are not url encoded
+        let encoded_room_alias =
+            url::form_urlencoded::byte_serialize(matrix_room_alias_id.to_string().as_bytes()).collect::<String>();
+        let endpoint = self.base_url.clone() + &format!("/_matrix/client/r0/directory/room/{}", &encoded_room_alias);
+        let params = self.params_hash();
+
+        let body_params = create_alias::BodyParams { room_id };
+        let payload = serde_json::to_string(&body_params).chain_err(|| body_params_error!("create_alias"))?;
+
+        let (body, status_code) = RestApi::call_matrix(&CreateAliasEndpoint::method(), &endpoint, payload, &params)?;
+        if !status_code.is_success() {
+            return Err(build_error(&endpoint, &body, &status_code));
+        }
+
+        Ok(())
+    }
+
+    fn redact_event(&self, room_id: RoomId, event_id: EventId, user_id: UserId) -> Result<()> {
+        // the redact endpoint is not part of the ruma client api, so the path is built here
+        let encoded_room_id = url::form_urlencoded::byte_serialize(room_id.to_string().as_bytes()).collect::<String>();
+        let encoded_event_id = url::form_urlencoded::byte_serialize(event_id.to_string().as_bytes()).collect::<String>();
+        let txn_id = EventId::new(&self.base_url).chain_err(|| ErrorKind::EventIdGenerationFailed)?;
+        let encoded_txn_id = url::form_urlencoded::byte_serialize(txn_id.to_string().as_bytes()).collect::<String>();
+        let endpoint = self.base_url.clone()
+            + &format!("/_matrix/client/r0/rooms/{}/redact/{}/{}", encoded_room_id, encoded_event_id, encoded_txn_id);
+        let user_id = user_id.to_string();
+        let mut params = self.params_hash();
+        params.insert("user_id", &user_id);
+
+        let (body, status_code) = RestApi::call_matrix(&Method::PUT, &endpoint, "{}", &params)?;
+        if !status_code.is_success() {
+            return Err(build_error(&endpoint, &body, &status_code));
+        }
+
+        debug!(self.logger, "User {} successfully redacted event {} in room {}", user_id, event_id, room_id);
+        Ok(())
+    }
+
     fn register(&self, user_id_local_part: String) -> Result<()> {
         let endpoint = self.base_url.clone() + &RegisterEndpoint::request_path(());
         let params = self.params_hash();
@@ -441,14 +533,23 @@ impl super::MatrixApi for MatrixApi {
         Ok(())
     }
 
-    fn send_text_message(&self, room_id: RoomId, user_id: UserId, body: String) -> Result<()> {
-        let formatted_body = render_markdown(&body);
+    fn send_formatted_message(
+        &self,
+        room_id: RoomId,
+        user_id: UserId,
+        body: String,
+        formatted_body: String,
+        relation: Option<MessageRelation>,
+    ) -> Result<Option<EventId>> {
         let mut message = Map::new();
         message.insert("body".to_string(), json!(body));
         message.insert("formatted_body".to_string(), json!(formatted_body));
         message.insert("msgtype".to_string(), json!(MessageType::Text));
         message.insert("format".to_string(), json!("org.matrix.custom.html"));
-        let payload = serde_json::to_string(&message).chain_err(|| body_params_error!("send text message"))?;
+        if let Some(relation) = relation {
+            message.insert("m.relates_to".to_string(), relates_to(relation));
+        }
+        let payload = serde_json::to_string(&message).chain_err(|| body_params_error!("send formatted message"))?;
         let txn_id = EventId::new(&self.base_url).chain_err(|| ErrorKind::EventIdGenerationFailed)?;
         let path_params = send_message_event::PathParams {
             room_id: room_id.clone(),
@@ -466,8 +567,13 @@ impl super::MatrixApi for MatrixApi {
             return Err(build_error(&endpoint, &body, &status_code));
         }
 
-        debug!(self.logger, "User {} successfully sent a text message to room {}", user_id, room_id);
-        Ok(())
+        debug!(self.logger, "User {} successfully sent a formatted message to room {}", user_id, room_id);
+        Ok(event_id_from_response(&body))
+    }
+
+    fn send_text_message(&self, room_id: RoomId, user_id: UserId, body: String) -> Result<Option<EventId>> {
+        let formatted_body = render_markdown(&body);
+        self.send_formatted_message(room_id, user_id, body, formatted_body, None)
     }
 
     fn send_data_message(&self, room_id: RoomId, user_id: UserId, body: String, url: String, mtype: MessageType) -> Result<()> {
@@ -497,6 +603,35 @@ impl super::MatrixApi for MatrixApi {
         Ok(())
     }
 
+    fn send_reaction(&self, room_id: RoomId, user_id: UserId, event_id: EventId, key: String) -> Result<Option<EventId>> {
+        let mut relates_to = Map::new();
+        relates_to.insert("event_id".to_string(), json!(event_id));
+        relates_to.insert("key".to_string(), json!(key));
+        relates_to.insert("rel_type".to_string(), json!("m.annotation"));
+        let mut reaction = Map::new();
+        reaction.insert("m.relates_to".to_string(), json!(relates_to));
+        let payload = serde_json::to_string(&reaction).chain_err(|| body_params_error!("send reaction"))?;
+        let txn_id = EventId::new(&self.base_url).chain_err(|| ErrorKind::EventIdGenerationFailed)?;
+        let path_params = send_message_event::PathParams {
+            room_id: room_id.clone(),
+            event_type: EventType::Custom("m.reaction".to_string()),
+            txn_id: txn_id.to_string(),
+        };
+        let endpoint = self.base_url.clone() + &SendMessageEventEndpoint::request_path(path_params);
+        let user_id = user_id.to_string();
+        let mut params = self.params_hash();
+        params.insert("user_id", &user_id);
+
+        let (body, status_code) = RestApi::call_matrix(&SendMessageEventEndpoint::method(), &endpoint, payload, &params)?;
+
+        if !status_code.is_success() {
+            return Err(build_error(&endpoint, &body, &status_code));
+        }
+
+        debug!(self.logger, "User {} successfully reacted to event {} in room {}", user_id, event_id, room_id);
+        Ok(event_id_from_response(&body))
+    }
+
     fn set_default_powerlevels(&self, room_id: RoomId, room_creator_user_id: UserId) -> Result<()> {
         let path_params = send_state_event_for_empty_key::PathParams { room_id, event_type: EventType::RoomPowerLevels };
         let endpoint = self.base_url.clone() + &SendStateEventForEmptyKeyEndpoint::request_path(path_params);
@@ -522,6 +657,22 @@ impl super::MatrixApi for MatrixApi {
         Ok(())
     }
 
+    fn set_avatar_url(&self, user_id: UserId, avatar_url: String) -> Result<()> {
+        let path_params = set_avatar_url::PathParams { user_id: user_id.clone() };
+        let endpoint = self.base_url.clone() + &SetAvatarUrlEndpoint::request_path(path_params);
+        let user_id = user_id.to_string();
+        let mut params = self.params_hash();
+        params.insert("user_id", &user_id);
+        let body_params = set_avatar_url::BodyParams { avatar_url };
+        let payload = serde_json::to_string(&body_params).chain_err(|| body_params_error!("set avatar URL"))?;
+
+        let (body, status_code) = RestApi::call_matrix(&SetAvatarUrlEndpoint::method(), &endpoint, payload, &params)?;
+        if !status_code.is_success() {
+            return Err(build_error(&endpoint, &body, &status_code));
+        }
+        Ok(())
+    }
+
     fn set_display_name(&self, user_id: UserId, name: String) -> Result<()> {
         let path_params = set_display_name::PathParams { user_id: user_id.clone() };
         let endpoint = self.base_url.clone() + &SetDisplayNameEndpoint::request_path(path_params);
@@ -538,10 +689,30 @@ impl super::MatrixApi for MatrixApi {
         Ok(())
     }
 
-    fn set_room_name(&self, room_id: RoomId, name: String) -> Result<()> {
+    fn set_room_join_rule(&self, room_id: RoomId, user_id: UserId, join_rule: JoinRule) -> Result<()> {
+        let path_params = send_state_event_for_empty_key::PathParams { room_id, event_type: EventType::RoomJoinRules };
+        let endpoint = self.base_url.clone() + &SendStateEventForEmptyKeyEndpoint::request_path(path_params);
+        let user_id = user_id.to_string();
+        let mut params = self.params_hash();
+        params.insert("user_id", &user_id);
+        let mut body_params = serde_json::Map::new();
+        body_params.insert("join_rule".to_string(), Value::String(join_rule.to_string()));
+        let payload = serde_json::to_string(&body_params).chain_err(|| body_params_error!("room join rule"))?;
+
+        let (body, status_code) =
+            RestApi::call_matrix(&SendStateEventForEmptyKeyEndpoint::method(), &endpoint, payload, &params)?;
+        if !status_code.is_success() {
+            return Err(build_error(&endpoint, &body, &status_code));
+        }
+        Ok(())
+    }
+
+    fn set_room_name(&self, room_id: RoomId, user_id: UserId, name: String) -> Result<()> {
         let path_params = send_state_event_for_empty_key::PathParams { room_id, event_type: EventType::RoomName };
         let endpoint = self.base_url.clone() + &SendStateEventForEmptyKeyEndpoint::request_path(path_params);
-        let params = self.params_hash();
+        let user_id = user_id.to_string();
+        let mut params = self.params_hash();
+        params.insert("user_id", &user_id);
         let mut body_params = serde_json::Map::new();
         body_params.insert("name".to_string(), Value::String(name));
         let payload = serde_json::to_string(&body_params).chain_err(|| body_params_error!("room name"))?;
@@ -554,10 +725,12 @@ impl super::MatrixApi for MatrixApi {
         Ok(())
     }
 
-    fn set_room_topic(&self, room_id: RoomId, topic: String) -> Result<()> {
+    fn set_room_topic(&self, room_id: RoomId, user_id: UserId, topic: String) -> Result<()> {
         let path_params = send_state_event_for_empty_key::PathParams { room_id, event_type: EventType::RoomTopic };
         let endpoint = self.base_url.clone() + &SendStateEventForEmptyKeyEndpoint::request_path(path_params);
-        let params = self.params_hash();
+        let user_id = user_id.to_string();
+        let mut params = self.params_hash();
+        params.insert("user_id", &user_id);
         let mut body_params = serde_json::Map::new();
         body_params.insert("topic".to_string(), Value::String(topic));
         let payload = serde_json::to_string(&body_params).chain_err(|| body_params_error!("room topic"))?;
@@ -613,6 +786,24 @@ fn build_error(endpoint: &str, body: &str, status_code: &StatusCode) -> Error {
     Error::from(ErrorKind::MatrixError(matrix_error_resp.error))
 }
 
+fn event_id_from_response(body: &str) -> Option<EventId> {
+    let response: Value = serde_json::from_str(body).ok()?;
+    let event_id = response.get("event_id")?.as_str()?;
+    EventId::try_from(event_id).ok()
+}
+
+fn relates_to(relation: MessageRelation) -> Value {
+    match relation {
+        MessageRelation::Reply(event_id) => json!({ "m.in_reply_to": { "event_id": event_id } }),
+        MessageRelation::Thread { root, in_reply_to } => json!({
+            "rel_type": "m.thread",
+            "event_id": root,
+            "is_falling_back": true,
+            "m.in_reply_to": { "event_id": in_reply_to }
+        }),
+    }
+}
+
 fn render_markdown(input: &str) -> String {
     // The html will not have the same length as the msg, but it's a good starting point
     let mut output = String::with_capacity(input.len());
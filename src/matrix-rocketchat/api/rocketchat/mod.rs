@@ -44,6 +44,16 @@ pub struct Attachment {
     pub title: String,
 }
 
+/// The avatar of a Rocket.Chat user
+pub struct Avatar {
+    /// The content type according to RFC7231
+    pub content_type: HeaderValue,
+    /// The image
+    pub data: Vec<u8>,
+    /// The ETag of the avatar, only present if the server sent one
+    pub etag: Option<String>,
+}
+
 /// A Rocket.Chat channel
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct Channel {
@@ -87,6 +97,24 @@ pub struct MessageAttachment {
     pub title_link: String,
 }
 
+/// The message a new message replies to
+#[derive(Clone, Debug)]
+pub enum Reply {
+    /// The message is posted in the thread that was started by the message with the given ID
+    Thread(String),
+    /// The message quotes another message
+    Quote(Quote),
+}
+
+/// A quoted message, that is added as attachment to the reply
+#[derive(Clone, Debug, Serialize)]
+pub struct Quote {
+    /// The name of the author of the quoted message
+    pub author_name: String,
+    /// The text of the quoted message
+    pub text: String,
+}
+
 /// A Rocket.Chat user
 #[derive(Clone, Deserialize, Debug, Serialize)]
 pub struct User {
@@ -114,36 +142,148 @@ pub struct WebhookMessage {
     pub user_name: String,
     /// Message content
     pub text: String,
+    /// Reactions to the message, indexed by the emoji shortcode (for example `:thumbsup:`)
+    #[serde(default)]
+    pub reactions: Option<HashMap<String, WebhookReaction>>,
+    /// ID of the first message of the thread, only present if the message was posted in a thread
+    #[serde(default)]
+    pub tmid: Option<String>,
+    /// File that was uploaded with the message, only present if the message is a file upload
+    #[serde(default)]
+    pub file: Option<WebhookFile>,
+    /// Attachments of the message, file uploads contain the uploaded file and its description
+    #[serde(default)]
+    pub attachments: Option<Vec<WebhookAttachment>>,
+    /// Type of the message, only present for system messages (for example `r` when the room was renamed)
+    #[serde(default, rename = "t")]
+    pub message_type: Option<String>,
+}
+
+/// A Rocket.Chat user joined or left a channel
+#[derive(Deserialize, Debug, Serialize)]
+pub struct WebhookMembership {
+    /// Type of the membership change
+    pub event: MembershipEvent,
+    /// Rocket.Chat token
+    pub token: Option<String>,
+    /// ID of the channel that the user joined or left
+    pub channel_id: String,
+    /// Name of the channel that the user joined or left
+    pub channel_name: Option<String>,
+    /// ID of the user who joined or left the channel
+    pub user_id: String,
+    /// Name of the user who joined or left the channel
+    pub user_name: String,
+}
+
+/// Membership changes that are sent by the Rocket.Chat outgoing webhook
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Serialize)]
+pub enum MembershipEvent {
+    /// A user joined the channel
+    #[serde(rename = "roomJoined")]
+    RoomJoined,
+    /// A user left the channel
+    #[serde(rename = "roomLeft")]
+    RoomLeft,
+}
+
+/// An event that is sent by the Rocket.Chat outgoing webhook
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(untagged)]
+pub enum WebhookEvent {
+    /// A user joined or left a channel
+    Membership(WebhookMembership),
+    /// A message was posted or updated
+    Message(WebhookMessage),
+}
+
+/// A reaction to a Rocket.Chat message
+#[derive(Clone, Deserialize, Debug, Serialize)]
+pub struct WebhookReaction {
+    /// Names of the users that reacted with the emoji
+    pub usernames: Vec<String>,
+}
+
+/// A file that was uploaded to Rocket.Chat
+#[derive(Clone, Deserialize, Debug, Serialize)]
+pub struct WebhookFile {
+    /// ID of the file
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// Name of the file
+    pub name: String,
+    /// The file's MIME type
+    #[serde(rename = "type")]
+    pub mimetype: Option<String>,
+}
+
+/// An attachment of a Rocket.Chat message
+#[derive(Clone, Deserialize, Debug, Serialize)]
+pub struct WebhookAttachment {
+    /// An optional title, for files this is the name of the file
+    pub title: Option<String>,
+    /// Link to the attached file
+    pub title_link: Option<String>,
+    /// The description that the user entered when uploading the file
+    pub description: Option<String>,
 }
 
 /// Rocket.Chat REST API
 pub trait RocketchatApi {
     /// Get the url of an image that is attached to a message.
     fn attachments(&self, message_id: &str) -> Result<Vec<Attachment>>;
+    /// Get information like the name about a public channel
+    fn channels_info(&self, room_id: &str) -> Result<Channel>;
     /// Get all members of a channel
     fn channels_members(&self, room_id: &str) -> Result<Vec<User>>;
     /// List of channels on the Rocket.Chat server
     fn channels_list(&self) -> Result<Vec<Channel>>;
     /// Get all the channels that the user of the request has joiend.
     fn channels_list_joined(&self) -> Result<Vec<Channel>>;
+    /// Rename a channel
+    fn channels_rename(&self, room_id: &str, name: &str) -> Result<()>;
+    /// Set the topic of a channel
+    fn channels_set_topic(&self, room_id: &str, topic: &str) -> Result<()>;
+    /// Delete a chat message
+    fn chat_delete(&self, message_id: &str, room_id: &str) -> Result<()>;
     /// Get a chat message
     fn chat_get_message(&self, message_id: &str) -> Result<Message>;
-    /// Post a chat message
-    fn chat_post_message(&self, text: &str, room_id: &str) -> Result<()>;
+    /// Post a chat message, returns the ID of the message if the server sent it back. The message
+    /// is either posted in a thread or quotes the message it replies to.
+    fn chat_post_message(&self, text: &str, room_id: &str, reply: Option<&Reply>) -> Result<Option<String>>;
+    /// Add (or remove if `should_react` is false) a reaction to a chat message
+    fn chat_react(&self, message_id: &str, emoji: &str, should_react: bool) -> Result<()>;
+    /// Update the text of an existing chat message
+    fn chat_update(&self, message_id: &str, room_id: &str, text: &str) -> Result<()>;
     /// List of direct messages the user is part of
     fn dm_list(&self) -> Result<Vec<Channel>>;
     /// List of al private groups the authenticated user has joined on the Rocket.Chat server
     fn groups_list(&self) -> Result<Vec<Channel>>;
     /// Get all members of a group
     fn groups_members(&self, room_id: &str) -> Result<Vec<User>>;
+    /// Rename a private group
+    fn groups_rename(&self, room_id: &str, name: &str) -> Result<()>;
+    /// Set the topic of a private group
+    fn groups_set_topic(&self, room_id: &str, topic: &str) -> Result<()>;
+    /// Create a direct message channel with another user, the existing channel is returned if the
+    /// users already have one
+    fn im_create(&self, username: &str) -> Result<Channel>;
+    /// Get all members of a direct message channel, this includes the authenticated user
+    fn im_members(&self, room_id: &str) -> Result<Vec<User>>;
     /// Login a user on the Rocket.Chat server
     fn login(&self, username: &str, password: &str) -> Result<(String, String)>;
+    /// Logout the authenticated user on the Rocket.Chat server
+    fn logout(&self) -> Result<()>;
     /// Get current user information
     fn me(&self) -> Result<User>;
     /// Post a message with an attachment
     fn rooms_upload(&self, file: Vec<u8>, filename: &str, mimetype: HeaderValue, room_id: &str) -> Result<()>;
+    /// Get the avatar of a user. Returns `None` if the avatar still matches the given ETag.
+    fn users_avatar(&self, username: &str, etag: Option<&str>) -> Result<Option<Avatar>>;
     /// Get information like user_id, status, etc. about a user
     fn users_info(&self, username: &str) -> Result<User>;
+    /// Get information like username, status, etc. about a user by the user's ID
+    fn users_info_by_id(&self, user_id: &str) -> Result<User>;
     /// Set credentials that are used for all API calls that need authentication
     fn with_credentials(self: Box<Self>, user_id: String, auth_token: String) -> Box<RocketchatApi>;
 }
@@ -212,6 +352,37 @@ impl RocketchatApi {
     }
 }
 
-impl Key for WebhookMessage {
-    type Value = WebhookMessage;
+impl WebhookMessage {
+    /// Returns true if a file was uploaded with the message.
+    pub fn is_file_upload(&self) -> bool {
+        self.file.is_some() || self.webhook_attachments().iter().any(|attachment| attachment.title_link.is_some())
+    }
+
+    /// The description that the user entered when uploading the file. The message text is not
+    /// used, because it only contains a localized placeholder if the file has no description.
+    pub fn file_description(&self) -> Option<&str> {
+        self.webhook_attachments()
+            .iter()
+            .filter_map(|attachment| attachment.description.as_ref())
+            .map(|description| description.trim())
+            .find(|description| !description.is_empty())
+    }
+
+    fn webhook_attachments(&self) -> &[WebhookAttachment] {
+        self.attachments.as_ref().map(|attachments| attachments.as_slice()).unwrap_or(&[])
+    }
+}
+
+impl WebhookEvent {
+    /// The token that the Rocket.Chat server sent with the event.
+    pub fn token(&self) -> Option<&String> {
+        match *self {
+            WebhookEvent::Membership(ref membership) => membership.token.as_ref(),
+            WebhookEvent::Message(ref message) => message.token.as_ref(),
+        }
+    }
+}
+
+impl Key for WebhookEvent {
+    type Value = WebhookEvent;
 }
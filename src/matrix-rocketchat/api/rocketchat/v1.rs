@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
-use http::header::{HeaderValue, CONTENT_TYPE};
+use http::header::{HeaderValue, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
 use http::{HeaderMap, Method, StatusCode};
 use mime::Mime;
 use reqwest::multipart::{Form, Part};
@@ -9,31 +9,55 @@ use serde_json;
 use slog::Logger;
 
 use api::rocketchat::{
-    Attachment as RocketchatAttachment, Channel, Endpoint, File as RocketchatFile, Message as RocketchatMessage,
-    MessageAttachment, User,
+    Attachment as RocketchatAttachment, Avatar, Channel, Endpoint, File as RocketchatFile, Message as RocketchatMessage,
+    MessageAttachment, Quote, Reply, User,
 };
 use api::{RequestData, RestApi};
 use errors::*;
 use i18n::*;
 
+/// Avatar endpoint path, the username is appended to the path
+pub const AVATAR_PATH: &str = "/avatar";
+/// Channel info endpoint path
+pub const CHANNELS_INFO_PATH: &str = "/api/v1/channels.info";
 /// Room members endpoint path
 pub const CHANNELS_MEMBERS_PATH: &str = "/api/v1/channels.members";
 /// Channels list endpoint path
 pub const CHANNELS_LIST_PATH: &str = "/api/v1/channels.list";
 /// Joined rooms endpoint path
 pub const CHANNELS_LIST_JOINED_PATH: &str = "/api/v1/channels.list.joined";
+/// Rename channel endpoint path
+pub const CHANNELS_RENAME_PATH: &str = "/api/v1/channels.rename";
+/// Set channel topic endpoint path
+pub const CHANNELS_SET_TOPIC_PATH: &str = "/api/v1/channels.setTopic";
+/// Delete a chat message endpoint path
+pub const CHAT_DELETE_PATH: &str = "/api/v1/chat.delete";
 /// Get a chat message endpoint path
 pub const CHAT_GET_MESSAGE_PATH: &str = "/api/v1/chat.getMessage";
 /// Post chat message endpoint path
 pub const CHAT_POST_MESSAGE_PATH: &str = "/api/v1/chat.postMessage";
+/// React to a chat message endpoint path
+pub const CHAT_REACT_PATH: &str = "/api/v1/chat.react";
+/// Update chat message endpoint path
+pub const CHAT_UPDATE_PATH: &str = "/api/v1/chat.update";
 /// Direct messages list endpoint path
 pub const DM_LIST_PATH: &str = "/api/v1/dm.list";
 /// Group list endpoint path
 pub const GROUPS_LIST_PATH: &str = "/api/v1/groups.list";
 /// Group members endpoint path
 pub const GROUPS_MEMBERS_PATH: &str = "/api/v1/groups.members";
+/// Rename group endpoint path
+pub const GROUPS_RENAME_PATH: &str = "/api/v1/groups.rename";
+/// Set group topic endpoint path
+pub const GROUPS_SET_TOPIC_PATH: &str = "/api/v1/groups.setTopic";
+/// Create direct message endpoint path
+pub const IM_CREATE_PATH: &str = "/api/v1/im.create";
+/// Direct message members endpoint path
+pub const IM_MEMBERS_PATH: &str = "/api/v1/im.members";
 /// Login endpoint path
 pub const LOGIN_PATH: &str = "/api/v1/login";
+/// Logout endpoint path
+pub const LOGOUT_PATH: &str = "/api/v1/logout";
 /// Me endpoint path
 pub const ME_PATH: &str = "/api/v1/me";
 /// Users list endpoint path
@@ -256,6 +280,72 @@ impl<'a> Endpoint<String> for LoginEndpoint<'a> {
     }
 }
 
+/// V1 create direct message endpoint
+pub struct ImCreateEndpoint<'a> {
+    base_url: String,
+    user_id: String,
+    auth_token: String,
+    payload: ImCreatePayload<'a>,
+}
+
+/// Payload of the create direct message endpoint
+#[derive(Serialize)]
+pub struct ImCreatePayload<'a> {
+    username: &'a str,
+}
+
+impl<'a> Endpoint<String> for ImCreateEndpoint<'a> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        self.base_url.clone() + IM_CREATE_PATH
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        let payload = serde_json::to_string(&self.payload)
+            .chain_err(|| ErrorKind::InvalidJSON("Could not serialize create direct message payload".to_string()))?;
+        Ok(RequestData::Body(payload))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json")?);
+        headers.insert("X-User-Id", HeaderValue::from_str(&self.user_id)?);
+        headers.insert("X-Auth-Token", HeaderValue::from_str(&self.auth_token)?);
+        Ok(Some(headers))
+    }
+}
+
+/// V1 logout endpoint
+pub struct LogoutEndpoint {
+    base_url: String,
+    user_id: String,
+    auth_token: String,
+}
+
+impl Endpoint<String> for LogoutEndpoint {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        self.base_url.clone() + LOGOUT_PATH
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        Ok(RequestData::Body("".to_string()))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-User-Id", HeaderValue::from_str(&self.user_id)?);
+        headers.insert("X-Auth-Token", HeaderValue::from_str(&self.auth_token)?);
+        Ok(Some(headers))
+    }
+}
+
 /// V1 post chat message endpoint
 pub struct ChatPostMessageEndpoint<'a> {
     base_url: String,
@@ -270,6 +360,10 @@ pub struct PostChatMessagePayload<'a> {
     #[serde(rename = "roomId")]
     room_id: &'a str,
     text: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tmid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attachments: Option<Vec<&'a Quote>>,
 }
 
 impl<'a> Endpoint<String> for ChatPostMessageEndpoint<'a> {
@@ -296,6 +390,242 @@ impl<'a> Endpoint<String> for ChatPostMessageEndpoint<'a> {
     }
 }
 
+/// Avatar endpoint, it doesn't require authentication
+pub struct AvatarEndpoint<'a> {
+    base_url: String,
+    username: &'a str,
+    etag: Option<&'a str>,
+}
+
+impl<'a> Endpoint<String> for AvatarEndpoint<'a> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn url(&self) -> String {
+        format!("{}{}/{}", self.base_url, AVATAR_PATH, self.username)
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        Ok(RequestData::Body("".to_string()))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = self.etag {
+            headers.insert(IF_NONE_MATCH, HeaderValue::from_str(etag)?);
+        }
+        Ok(Some(headers))
+    }
+}
+
+/// V1 delete chat message endpoint
+pub struct ChatDeleteEndpoint<'a> {
+    base_url: String,
+    user_id: String,
+    auth_token: String,
+    payload: ChatDeletePayload<'a>,
+}
+
+/// Payload of the delete chat message endpoint
+#[derive(Serialize)]
+pub struct ChatDeletePayload<'a> {
+    #[serde(rename = "roomId")]
+    room_id: &'a str,
+    #[serde(rename = "msgId")]
+    message_id: &'a str,
+}
+
+impl<'a> Endpoint<String> for ChatDeleteEndpoint<'a> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        self.base_url.clone() + CHAT_DELETE_PATH
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        let payload = serde_json::to_string(&self.payload)
+            .chain_err(|| ErrorKind::InvalidJSON("Could not serialize delete chat message payload".to_string()))?;
+        Ok(RequestData::Body(payload))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json")?);
+        headers.insert("X-User-Id", HeaderValue::from_str(&self.user_id)?);
+        headers.insert("X-Auth-Token", HeaderValue::from_str(&self.auth_token)?);
+        Ok(Some(headers))
+    }
+}
+
+/// V1 rename channel or group endpoint
+pub struct RoomRenameEndpoint<'a> {
+    base_url: String,
+    user_id: String,
+    auth_token: String,
+    path: &'static str,
+    payload: RoomRenamePayload<'a>,
+}
+
+/// Payload of the rename channel or group endpoint
+#[derive(Serialize)]
+pub struct RoomRenamePayload<'a> {
+    #[serde(rename = "roomId")]
+    room_id: &'a str,
+    name: &'a str,
+}
+
+impl<'a> Endpoint<String> for RoomRenameEndpoint<'a> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        self.base_url.clone() + self.path
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        let payload = serde_json::to_string(&self.payload)
+            .chain_err(|| ErrorKind::InvalidJSON("Could not serialize rename room payload".to_string()))?;
+        Ok(RequestData::Body(payload))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json")?);
+        headers.insert("X-User-Id", HeaderValue::from_str(&self.user_id)?);
+        headers.insert("X-Auth-Token", HeaderValue::from_str(&self.auth_token)?);
+        Ok(Some(headers))
+    }
+}
+
+/// V1 set channel or group topic endpoint
+pub struct RoomSetTopicEndpoint<'a> {
+    base_url: String,
+    user_id: String,
+    auth_token: String,
+    path: &'static str,
+    payload: RoomSetTopicPayload<'a>,
+}
+
+/// Payload of the set channel or group topic endpoint
+#[derive(Serialize)]
+pub struct RoomSetTopicPayload<'a> {
+    #[serde(rename = "roomId")]
+    room_id: &'a str,
+    topic: &'a str,
+}
+
+impl<'a> Endpoint<String> for RoomSetTopicEndpoint<'a> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        self.base_url.clone() + self.path
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        let payload = serde_json::to_string(&self.payload)
+            .chain_err(|| ErrorKind::InvalidJSON("Could not serialize set room topic payload".to_string()))?;
+        Ok(RequestData::Body(payload))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json")?);
+        headers.insert("X-User-Id", HeaderValue::from_str(&self.user_id)?);
+        headers.insert("X-Auth-Token", HeaderValue::from_str(&self.auth_token)?);
+        Ok(Some(headers))
+    }
+}
+
+/// V1 react to chat message endpoint
+pub struct ChatReactEndpoint<'a> {
+    base_url: String,
+    user_id: String,
+    auth_token: String,
+    payload: ChatReactPayload<'a>,
+}
+
+/// Payload of the react to chat message endpoint
+#[derive(Serialize)]
+pub struct ChatReactPayload<'a> {
+    #[serde(rename = "messageId")]
+    message_id: &'a str,
+    emoji: &'a str,
+    #[serde(rename = "shouldReact")]
+    should_react: bool,
+}
+
+impl<'a> Endpoint<String> for ChatReactEndpoint<'a> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        self.base_url.clone() + CHAT_REACT_PATH
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        let payload = serde_json::to_string(&self.payload)
+            .chain_err(|| ErrorKind::InvalidJSON("Could not serialize react to chat message payload".to_string()))?;
+        Ok(RequestData::Body(payload))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json")?);
+        headers.insert("X-User-Id", HeaderValue::from_str(&self.user_id)?);
+        headers.insert("X-Auth-Token", HeaderValue::from_str(&self.auth_token)?);
+        Ok(Some(headers))
+    }
+}
+
+/// V1 update chat message endpoint
+pub struct ChatUpdateEndpoint<'a> {
+    base_url: String,
+    user_id: String,
+    auth_token: String,
+    payload: ChatUpdatePayload<'a>,
+}
+
+/// Payload of the update chat message endpoint
+#[derive(Serialize)]
+pub struct ChatUpdatePayload<'a> {
+    #[serde(rename = "roomId")]
+    room_id: &'a str,
+    #[serde(rename = "msgId")]
+    message_id: &'a str,
+    text: &'a str,
+}
+
+impl<'a> Endpoint<String> for ChatUpdateEndpoint<'a> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn url(&self) -> String {
+        self.base_url.clone() + CHAT_UPDATE_PATH
+    }
+
+    fn payload(&self) -> Result<RequestData<String>> {
+        let payload = serde_json::to_string(&self.payload)
+            .chain_err(|| ErrorKind::InvalidJSON("Could not serialize update chat message payload".to_string()))?;
+        Ok(RequestData::Body(payload))
+    }
+
+    fn headers(&self) -> Result<Option<HeaderMap>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str("application/json")?);
+        headers.insert("X-User-Id", HeaderValue::from_str(&self.user_id)?);
+        headers.insert("X-Auth-Token", HeaderValue::from_str(&self.auth_token)?);
+        Ok(Some(headers))
+    }
+}
+
 /// V1 endpoint to post a messag with an attachement
 pub struct RoomsUploadEndpoint<'a> {
     base_url: String,
@@ -349,7 +679,7 @@ pub struct ChannelsMembersEndpoint<'a> {
     query_params: HashMap<&'static str, &'a str>,
 }
 
-/// Response payload from the Rocket.Chat members (channel or group) endpoint.
+/// Response payload from the Rocket.Chat members (channel, group or direct message) endpoint.
 #[derive(Deserialize)]
 ///
 pub struct MembersResponse {
@@ -428,6 +758,13 @@ impl<'a> Endpoint<String> for ChannelsListJoinedEndpoint<'a> {
     }
 }
 
+/// Response payload from the Rocket.Chat channels.info endpoint.
+#[derive(Deserialize)]
+pub struct ChannelsInfoResponse {
+    /// The channel on the Rocket.Chat server
+    pub channel: Channel,
+}
+
 /// Response payload from the Rocket.Chat channels.list endpoint.
 #[derive(Deserialize)]
 pub struct ChannelsListResponse {
@@ -453,6 +790,13 @@ pub struct DMListResponse {
     pub ims: Vec<Channel>,
 }
 
+/// Response payload from the Rocket.Chat im.create endpoint.
+#[derive(Deserialize)]
+pub struct ImCreateResponse {
+    /// The direct message channel
+    pub room: Channel,
+}
+
 /// Response payload from the Rocket.Chat groups.list endpoint.
 #[derive(Deserialize)]
 pub struct GroupsListResponse {
@@ -483,6 +827,21 @@ pub struct UsersInfoResponse {
     pub user: User,
 }
 
+/// Response payload from the Rocket.Chat chat.postMessage endpoint.
+#[derive(Deserialize)]
+pub struct PostChatMessageResponse {
+    /// The message that was created
+    pub message: Option<PostedMessage>,
+}
+
+/// A message that was posted to Rocket.Chat
+#[derive(Deserialize)]
+pub struct PostedMessage {
+    /// The unique message identifier
+    #[serde(rename = "_id")]
+    pub id: String,
+}
+
 /// Response payload from the Rocket.Chat chat.message endpoint.
 #[derive(Deserialize)]
 pub struct MessageResponse {
@@ -517,6 +876,68 @@ impl RocketchatApi {
     pub fn new(base_url: String, logger: Logger) -> RocketchatApi {
         RocketchatApi { base_url, logger, user_id: "".to_string(), auth_token: "".to_string() }
     }
+
+    fn query_users_info(&self, query_params: HashMap<&'static str, &str>) -> Result<User> {
+        let users_info_endpoint = GetWithAuthEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            path: USERS_INFO_PATH,
+            query_params,
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&users_info_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&users_info_endpoint.url(), &body, &status_code));
+        }
+
+        let users_info_response: UsersInfoResponse = serde_json::from_str(&body).chain_err(|| {
+            ErrorKind::InvalidJSON(format!(
+                "Could not deserialize response from Rocket.Chat users.info API endpoint: `{}`",
+                body
+            ))
+        })?;
+
+        Ok(users_info_response.user)
+    }
+
+    fn rename_room(&self, path: &'static str, room_id: &str, name: &str) -> Result<()> {
+        debug!(self.logger, "Renaming Rocket.Chat room {} to {}", room_id, name);
+
+        let rename_endpoint = RoomRenameEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            path,
+            payload: RoomRenamePayload { room_id, name },
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&rename_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&rename_endpoint.url(), &body, &status_code));
+        }
+
+        Ok(())
+    }
+
+    fn set_room_topic(&self, path: &'static str, room_id: &str, topic: &str) -> Result<()> {
+        debug!(self.logger, "Setting the topic of Rocket.Chat room {} to {}", room_id, topic);
+
+        let set_topic_endpoint = RoomSetTopicEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            path,
+            payload: RoomSetTopicPayload { room_id, topic },
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&set_topic_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&set_topic_endpoint.url(), &body, &status_code));
+        }
+
+        Ok(())
+    }
 }
 
 impl super::RocketchatApi for RocketchatApi {
@@ -560,6 +981,34 @@ impl super::RocketchatApi for RocketchatApi {
         Ok(files)
     }
 
+    fn channels_info(&self, room_id: &str) -> Result<Channel> {
+        debug!(self.logger, "Getting channel info for channel {} from Rocket.Chat server {}", room_id, &self.base_url);
+
+        let mut query_params = HashMap::new();
+        query_params.insert("roomId", room_id);
+        let channels_info_endpoint = GetWithAuthEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            path: CHANNELS_INFO_PATH,
+            query_params,
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&channels_info_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&channels_info_endpoint.url(), &body, &status_code));
+        }
+
+        let channels_info_response: ChannelsInfoResponse = serde_json::from_str(&body).chain_err(|| {
+            ErrorKind::InvalidJSON(format!(
+                "Could not deserialize response from Rocket.Chat channels.info API endpoint: `{}`",
+                body
+            ))
+        })?;
+
+        Ok(channels_info_response.channel)
+    }
+
     fn channels_members(&self, room_id: &str) -> Result<Vec<User>> {
         debug!(self.logger, "Getting rooms members for room {} from Rocket.Chat server", room_id);
 
@@ -633,6 +1082,32 @@ impl super::RocketchatApi for RocketchatApi {
         Ok(channels)
     }
 
+    fn channels_rename(&self, room_id: &str, name: &str) -> Result<()> {
+        self.rename_room(CHANNELS_RENAME_PATH, room_id, name)
+    }
+
+    fn channels_set_topic(&self, room_id: &str, topic: &str) -> Result<()> {
+        self.set_room_topic(CHANNELS_SET_TOPIC_PATH, room_id, topic)
+    }
+
+    fn chat_delete(&self, message_id: &str, room_id: &str) -> Result<()> {
+        debug!(self.logger, "Deleting message {} in Rocket.Chat room {}", message_id, room_id);
+
+        let chat_delete_endpoint = ChatDeleteEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            payload: ChatDeletePayload { room_id, message_id },
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&chat_delete_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&chat_delete_endpoint.url(), &body, &status_code));
+        }
+
+        Ok(())
+    }
+
     fn chat_get_message(&self, message_id: &str) -> Result<RocketchatMessage> {
         debug!(self.logger, "Retreiving message {}", message_id);
 
@@ -688,14 +1163,20 @@ impl super::RocketchatApi for RocketchatApi {
         Ok(message)
     }
 
-    fn chat_post_message(&self, text: &str, room_id: &str) -> Result<()> {
+    fn chat_post_message(&self, text: &str, room_id: &str, reply: Option<&Reply>) -> Result<Option<String>> {
         debug!(self.logger, "Forwarding message to to Rocket.Chat room {}", room_id);
 
+        let (tmid, attachments) = match reply {
+            Some(&Reply::Thread(ref thread_id)) => (Some(thread_id.as_str()), None),
+            Some(&Reply::Quote(ref quote)) => (None, Some(vec![quote])),
+            None => (None, None),
+        };
+
         let chat_post_message_endpoint = ChatPostMessageEndpoint {
             base_url: self.base_url.clone(),
             user_id: self.user_id.clone(),
             auth_token: self.auth_token.clone(),
-            payload: PostChatMessagePayload { text: Some(text), room_id },
+            payload: PostChatMessagePayload { text: Some(text), room_id, tmid, attachments },
         };
 
         let (body, status_code) = RestApi::call_rocketchat(&chat_post_message_endpoint)?;
@@ -703,6 +1184,49 @@ impl super::RocketchatApi for RocketchatApi {
             return Err(build_error(&chat_post_message_endpoint.url(), &body, &status_code));
         }
 
+        let post_chat_message_response: PostChatMessageResponse = serde_json::from_str(&body).chain_err(|| {
+            ErrorKind::InvalidJSON(format!(
+                "Could not deserialize response from Rocket.Chat chat.postMessage API endpoint: `{}`",
+                body
+            ))
+        })?;
+
+        Ok(post_chat_message_response.message.map(|message| message.id))
+    }
+
+    fn chat_react(&self, message_id: &str, emoji: &str, should_react: bool) -> Result<()> {
+        debug!(self.logger, "Setting reaction {} on message {} to {}", emoji, message_id, should_react);
+
+        let chat_react_endpoint = ChatReactEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            payload: ChatReactPayload { message_id, emoji, should_react },
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&chat_react_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&chat_react_endpoint.url(), &body, &status_code));
+        }
+
+        Ok(())
+    }
+
+    fn chat_update(&self, message_id: &str, room_id: &str, text: &str) -> Result<()> {
+        debug!(self.logger, "Updating message {} in Rocket.Chat room {}", message_id, room_id);
+
+        let chat_update_endpoint = ChatUpdateEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            payload: ChatUpdatePayload { room_id, message_id, text },
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&chat_update_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&chat_update_endpoint.url(), &body, &status_code));
+        }
+
         Ok(())
     }
 
@@ -783,6 +1307,14 @@ impl super::RocketchatApi for RocketchatApi {
         Ok(users)
     }
 
+    fn groups_rename(&self, room_id: &str, name: &str) -> Result<()> {
+        self.rename_room(GROUPS_RENAME_PATH, room_id, name)
+    }
+
+    fn groups_set_topic(&self, room_id: &str, topic: &str) -> Result<()> {
+        self.set_room_topic(GROUPS_SET_TOPIC_PATH, room_id, topic)
+    }
+
     fn login(&self, username: &str, password: &str) -> Result<(String, String)> {
         debug!(self.logger, "Logging in user with username {} on Rocket.Chat server {}", username, &self.base_url);
 
@@ -799,6 +1331,71 @@ impl super::RocketchatApi for RocketchatApi {
         Ok((login_response.data.user_id, login_response.data.auth_token))
     }
 
+    fn im_create(&self, username: &str) -> Result<Channel> {
+        debug!(self.logger, "Creating direct message with user {} on Rocket.Chat server {}", username, &self.base_url);
+
+        let im_create_endpoint = ImCreateEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+            payload: ImCreatePayload { username },
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&im_create_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&im_create_endpoint.url(), &body, &status_code));
+        }
+
+        let im_create_response: ImCreateResponse = serde_json::from_str(&body).chain_err(|| {
+            ErrorKind::InvalidJSON(format!(
+                "Could not deserialize response from Rocket.Chat im.create API endpoint: `{}`",
+                body
+            ))
+        })?;
+
+        Ok(im_create_response.room)
+    }
+
+    fn im_members(&self, room_id: &str) -> Result<Vec<User>> {
+        debug!(self.logger, "Getting direct message members for channel {} from Rocket.Chat server", room_id);
+
+        let mut users = Vec::new();
+        let mut offset = 0;
+        for i in 0..super::MAX_REQUESTS_PER_ENDPOINT_CALL {
+            if i == super::MAX_REQUESTS_PER_ENDPOINT_CALL {
+                bail_error!(ErrorKind::TooManyRequests(IM_MEMBERS_PATH.to_string()))
+            }
+
+            let mut members_response = get_im_members(&self, room_id, offset)?;
+            users.append(&mut members_response.members);
+            let subtotal = members_response.count + members_response.offset;
+            if subtotal == members_response.total {
+                break;
+            }
+
+            offset = subtotal;
+        }
+
+        Ok(users)
+    }
+
+    fn logout(&self) -> Result<()> {
+        debug!(self.logger, "Logging out user with user_id {} on Rocket.Chat server {}", self.user_id, &self.base_url);
+
+        let logout_endpoint = LogoutEndpoint {
+            base_url: self.base_url.clone(),
+            user_id: self.user_id.clone(),
+            auth_token: self.auth_token.clone(),
+        };
+
+        let (body, status_code) = RestApi::call_rocketchat(&logout_endpoint)?;
+        if !status_code.is_success() {
+            return Err(build_error(&logout_endpoint.url(), &body, &status_code));
+        }
+
+        Ok(())
+    }
+
     fn me(&self) -> Result<User> {
         debug!(self.logger, "Querying username for user_id {} on Rocket.Chat server {}", self.user_id, &self.base_url);
 
@@ -841,32 +1438,49 @@ impl super::RocketchatApi for RocketchatApi {
         Ok(())
     }
 
+    fn users_avatar(&self, username: &str, etag: Option<&str>) -> Result<Option<Avatar>> {
+        debug!(self.logger, "Getting avatar of user {} on Rocket.Chat server {}", username, &self.base_url);
+
+        let avatar_endpoint = AvatarEndpoint { base_url: self.base_url.clone(), username, etag };
+
+        let mut resp = RestApi::get_rocketchat_file(&avatar_endpoint)?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            debug!(self.logger, "Avatar of user {} did not change", username);
+            return Ok(None);
+        }
+
+        if !resp.status().is_success() {
+            let mut body = String::new();
+            resp.read_to_string(&mut body).chain_err(|| ErrorKind::ApiCallFailed(avatar_endpoint.url()))?;
+            return Err(build_error(&avatar_endpoint.url(), &body, &resp.status()));
+        }
+
+        let content_type = match resp.headers().get(CONTENT_TYPE) {
+            Some(content_type) => content_type.clone(),
+            None => HeaderValue::from_static("image/png"),
+        };
+        let etag = resp.headers().get(ETAG).and_then(|etag| etag.to_str().ok()).map(|etag| etag.to_string());
+
+        let mut data = Vec::new();
+        resp.read_to_end(&mut data).chain_err(|| ErrorKind::InternalServerError)?;
+
+        Ok(Some(Avatar { content_type, data, etag }))
+    }
+
     fn users_info(&self, username: &str) -> Result<User> {
         debug!(self.logger, "Querying user info for user {} on Rocket.Chat server {}", &username, &self.base_url);
 
         let mut query_params = HashMap::new();
         query_params.insert("username", username);
-        let users_info_endpoint = GetWithAuthEndpoint {
-            base_url: self.base_url.clone(),
-            user_id: self.user_id.clone(),
-            auth_token: self.auth_token.clone(),
-            path: USERS_INFO_PATH,
-            query_params,
-        };
-
-        let (body, status_code) = RestApi::call_rocketchat(&users_info_endpoint)?;
-        if !status_code.is_success() {
-            return Err(build_error(&users_info_endpoint.url(), &body, &status_code));
-        }
+        self.query_users_info(query_params)
+    }
 
-        let users_info_response: UsersInfoResponse = serde_json::from_str(&body).chain_err(|| {
-            ErrorKind::InvalidJSON(format!(
-                "Could not deserialize response from Rocket.Chat users.info API endpoint: `{}`",
-                body
-            ))
-        })?;
+    fn users_info_by_id(&self, user_id: &str) -> Result<User> {
+        debug!(self.logger, "Querying user info for user ID {} on Rocket.Chat server {}", &user_id, &self.base_url);
 
-        Ok(users_info_response.user)
+        let mut query_params = HashMap::new();
+        query_params.insert("userId", user_id);
+        self.query_users_info(query_params)
     }
 
     fn with_credentials(mut self: Box<Self>, user_id: String, auth_token: String) -> Box<super::RocketchatApi> {
@@ -928,6 +1542,33 @@ fn get_group_members(rocketchat_api: &RocketchatApi, room_id: &str, offset: i32)
     Ok(group_members_response)
 }
 
+fn get_im_members(rocketchat_api: &RocketchatApi, room_id: &str, offset: i32) -> Result<MembersResponse> {
+    let offset_param = offset.to_string();
+    let mut query_params = HashMap::new();
+    query_params.insert("roomId", room_id);
+    query_params.insert("offset", &offset_param);
+    let im_members_endpoint = GetWithAuthEndpoint {
+        base_url: rocketchat_api.base_url.clone(),
+        user_id: rocketchat_api.user_id.clone(),
+        auth_token: rocketchat_api.auth_token.clone(),
+        path: IM_MEMBERS_PATH,
+        query_params,
+    };
+
+    let (body, status_code) = RestApi::call_rocketchat(&im_members_endpoint)?;
+    if !status_code.is_success() {
+        return Err(build_error(&im_members_endpoint.url(), &body, &status_code));
+    }
+
+    let im_members_response: MembersResponse = serde_json::from_str(&body).chain_err(|| {
+        ErrorKind::InvalidJSON(format!(
+            "Could not deserialize response from Rocket.Chat direct message members API endpoint: `{}`",
+            body
+        ))
+    })?;
+    Ok(im_members_response)
+}
+
 fn channels_list_joined(rocketchat_api: &RocketchatApi, offset: i32) -> Result<GetJoinedChannelsResponse> {
     let offset_param = offset.to_string();
     let mut query_params = HashMap::new();
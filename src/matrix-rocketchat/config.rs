@@ -30,6 +30,10 @@ pub struct Config {
     /// If this flag is set to true, the bot user accepts invites from rooms on other homeservers.
     /// Which means that users from other homeservers can use this Rocket.Chat bridge.
     pub accept_remote_invites: bool,
+    /// If this flag is set to true, notices from Matrix (usually sent by bots) are marked as bot
+    /// messages when they are forwarded to Rocket.Chat.
+    #[serde(default)]
+    pub flag_notices_as_bot_messages: bool,
     /// Logging verbosity, available values: debug, info, warning, error.
     pub log_level: String,
     /// Flag that indicates if the application service should output the log to the console
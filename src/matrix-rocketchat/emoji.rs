@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+/// Variation selector that some clients append to emoji to force the emoji presentation
+const VARIATION_SELECTOR: char = '\u{fe0f}';
+
+/// Emoji that can be converted between their unicode representation (used by Matrix) and their
+/// shortcode (used by Rocket.Chat).
+const EMOJI: &[(&str, &str)] = &[
+    ("\u{1f44d}", ":thumbsup:"),
+    ("\u{1f44e}", ":thumbsdown:"),
+    ("\u{1f44c}", ":ok_hand:"),
+    ("\u{1f44f}", ":clap:"),
+    ("\u{1f44b}", ":wave:"),
+    ("\u{1f64f}", ":pray:"),
+    ("\u{1f64c}", ":raised_hands:"),
+    ("\u{1f4aa}", ":muscle:"),
+    ("\u{270c}", ":v:"),
+    ("\u{1f440}", ":eyes:"),
+    ("\u{2764}", ":heart:"),
+    ("\u{1f494}", ":broken_heart:"),
+    ("\u{1f499}", ":blue_heart:"),
+    ("\u{1f49a}", ":green_heart:"),
+    ("\u{1f49b}", ":yellow_heart:"),
+    ("\u{1f49c}", ":purple_heart:"),
+    ("\u{1f600}", ":grinning:"),
+    ("\u{1f601}", ":grin:"),
+    ("\u{1f602}", ":joy:"),
+    ("\u{1f603}", ":smiley:"),
+    ("\u{1f604}", ":smile:"),
+    ("\u{1f605}", ":sweat_smile:"),
+    ("\u{1f606}", ":laughing:"),
+    ("\u{1f609}", ":wink:"),
+    ("\u{1f60a}", ":blush:"),
+    ("\u{1f60d}", ":heart_eyes:"),
+    ("\u{1f60e}", ":sunglasses:"),
+    ("\u{1f610}", ":neutral_face:"),
+    ("\u{1f612}", ":unamused:"),
+    ("\u{1f614}", ":pensive:"),
+    ("\u{1f615}", ":confused:"),
+    ("\u{1f618}", ":kissing_heart:"),
+    ("\u{1f61b}", ":stuck_out_tongue:"),
+    ("\u{1f61c}", ":stuck_out_tongue_winking_eye:"),
+    ("\u{1f622}", ":cry:"),
+    ("\u{1f620}", ":angry:"),
+    ("\u{1f621}", ":rage:"),
+    ("\u{1f62d}", ":sob:"),
+    ("\u{1f631}", ":scream:"),
+    ("\u{1f633}", ":flushed:"),
+    ("\u{1f642}", ":slight_smile:"),
+    ("\u{1f643}", ":upside_down:"),
+    ("\u{1f644}", ":rolling_eyes:"),
+    ("\u{1f914}", ":thinking:"),
+    ("\u{1f923}", ":rofl:"),
+    ("\u{1f937}", ":shrug:"),
+    ("\u{1f926}", ":face_palm:"),
+    ("\u{1f389}", ":tada:"),
+    ("\u{1f38a}", ":confetti_ball:"),
+    ("\u{1f525}", ":fire:"),
+    ("\u{1f4af}", ":100:"),
+    ("\u{1f680}", ":rocket:"),
+    ("\u{2705}", ":white_check_mark:"),
+    ("\u{2714}", ":heavy_check_mark:"),
+    ("\u{274c}", ":x:"),
+    ("\u{2753}", ":question:"),
+    ("\u{2757}", ":exclamation:"),
+    ("\u{26a0}", ":warning:"),
+    ("\u{2b50}", ":star:"),
+    ("\u{1f31f}", ":star2:"),
+    ("\u{2600}", ":sunny:"),
+    ("\u{2615}", ":coffee:"),
+    ("\u{1f37a}", ":beer:"),
+    ("\u{1f355}", ":pizza:"),
+    ("\u{1f382}", ":birthday:"),
+    ("\u{1f381}", ":gift:"),
+    ("\u{1f4a9}", ":poop:"),
+    ("\u{1f648}", ":see_no_evil:"),
+    ("\u{1f649}", ":hear_no_evil:"),
+    ("\u{1f64a}", ":speak_no_evil:"),
+];
+
+lazy_static! {
+    static ref UNICODE_TO_SHORTCODE: HashMap<&'static str, &'static str> = EMOJI.iter().cloned().collect();
+    static ref SHORTCODE_TO_UNICODE: HashMap<&'static str, &'static str> =
+        EMOJI.iter().map(|&(unicode, shortcode)| (shortcode, unicode)).collect();
+}
+
+/// Get the Rocket.Chat shortcode (for example `:thumbsup:`) for a unicode emoji. Returns `None`
+/// if the emoji is not known.
+pub fn shortcode(unicode: &str) -> Option<&'static str> {
+    let unicode: String = unicode.chars().filter(|c| *c != VARIATION_SELECTOR).collect();
+    UNICODE_TO_SHORTCODE.get(unicode.as_str()).cloned()
+}
+
+/// Get the unicode emoji for a Rocket.Chat shortcode (for example `:thumbsup:`). Returns `None`
+/// if the shortcode is not known.
+pub fn unicode(shortcode: &str) -> Option<&'static str> {
+    SHORTCODE_TO_UNICODE.get(shortcode).cloned()
+}
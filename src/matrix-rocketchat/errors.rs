@@ -223,6 +223,31 @@ error_chain!{
             display("Room {} is already connected", room_id)
         }
 
+        NotLoggedIn(rocketchat_url: String) {
+            description("The user is not logged in on the Rocket.Chat server")
+            display("The user is not logged in on the Rocket.Chat server {}", rocketchat_url)
+        }
+
+        UnknownCommand(command: String) {
+            description("The command is not known")
+            display("The command {} is not known", command)
+        }
+
+        UnknownCommandFlag(command: String, flag: String) {
+            description("The command does not support the flag")
+            display("The command {} does not support the flag --{}", command, flag)
+        }
+
+        InvalidCommandArguments(command: String) {
+            description("The number of arguments does not match the command")
+            display("Wrong number of arguments for command {}", command)
+        }
+
+        UnterminatedQuote {
+            description("A quoted argument of the command is not closed")
+            display("The command contains an unterminated quote")
+        }
+
         AdminRoomForRocketchatServerNotFound(rocketchat_url: String) {
             description("The user does not have an admin room that is connected to the given Rocket.Chat server")
             display("No admin room found that is connected to the Rocket.Chat server {}", rocketchat_url)
@@ -254,6 +279,16 @@ error_chain!{
             display("Attempt to connect with the Rocket.Chat server ID {}, which is already in use.", rocketchat_server_id)
         }
 
+        RocketchatServerNotFound(rocketchat_server_id: String) {
+            description("No Rocket.Chat server with the given ID found")
+            display("The Rocket.Chat server with the ID {} does not exist", rocketchat_server_id)
+        }
+
+        RocketchatServerInUse(rocketchat_server_id: String) {
+            description("The Rocket.Chat server is still used by admin rooms, bridged rooms or direct message rooms")
+            display("The Rocket.Chat server {} is still in use and cannot be removed", rocketchat_server_id)
+        }
+
         RocketchatTokenAlreadyInUse(token: String) {
             description("The token is already used by another server")
             display("The token {} is already in use by another server", token)
@@ -274,6 +309,16 @@ error_chain!{
             display("Bridging the channel {} failed, because the user hasn't joined it on Rocket.Chat", channel_name)
         }
 
+        RocketchatDeleteMessageFailed(message_id: String, err: String) {
+            description("Deleting a message on Rocket.Chat failed")
+            display("Deleting message {} on Rocket.Chat failed: {}", message_id, err)
+        }
+
+        RocketchatUpdateRoomFailed(room_id: String, err: String) {
+            description("Updating the name or topic of a room on Rocket.Chat failed")
+            display("Updating the room {} on Rocket.Chat failed: {}", room_id, err)
+        }
+
         RocketchatUploadFailed(url: String, err: String) {
             description("Uploading file to Rocket.Chat failed")
             display("Uploading file {} to Rocket.Chat failed: {}", url, err)
@@ -324,11 +369,6 @@ error_chain!{
             display("Could not get logger from iron")
         }
 
-        GettingMatrixUserForDirectMessageRoomError {
-            description("Error when getting matrix user for a direct message room")
-            display("Could not get matrix user for direct message room")
-        }
-
         TooManyUsersInAdminRoom(room_id: RoomId) {
             description("Too many users in admin room")
             display("Room {} has more then two members and cannot be used as admin room", room_id)
@@ -45,11 +45,13 @@ impl<'a> ErrorNotifier<'a> {
                 }
                 error!(self.logger, "{}", msg);
                 let user_msg = t!(["defaults", "internal_error"]).l(DEFAULT_LANGUAGE);
-                return self.matrix_api.send_text_message(room_id, matrix_bot_id, user_msg);
+                self.matrix_api.send_text_message(room_id, matrix_bot_id, user_msg)?;
+                return Ok(());
             }
         };
 
-        self.matrix_api.send_text_message(room_id, matrix_bot_id, user_message.l(DEFAULT_LANGUAGE))
+        self.matrix_api.send_text_message(room_id, matrix_bot_id, user_message.l(DEFAULT_LANGUAGE))?;
+        Ok(())
     }
 }
 
@@ -4,12 +4,18 @@
 mod rocketchat;
 /// Process login request for Rocket.Chat
 mod rocketchat_login;
+/// Answers room alias queries from the Matrix homeserver
+mod rooms;
 /// Processes requests from the Matrix homeserver
 mod transactions;
+/// Answers user queries from the Matrix homeserver
+mod users;
 /// Sends a welcome message to the caller
 mod welcome;
 
 pub use self::rocketchat::Rocketchat;
 pub use self::rocketchat_login::RocketchatLogin;
+pub use self::rooms::Rooms;
 pub use self::transactions::Transactions;
+pub use self::users::Users;
 pub use self::welcome::Welcome;
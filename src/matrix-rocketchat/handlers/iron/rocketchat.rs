@@ -1,16 +1,16 @@
 use iron::prelude::*;
 use iron::{status, Handler};
 
-use api::rocketchat::WebhookMessage;
+use api::rocketchat::WebhookEvent;
 use api::MatrixApi;
 use config::Config;
-use handlers::rocketchat::Forwarder;
+use handlers::rocketchat::{Forwarder, MembershipHandler};
 use log::{self, IronLogger};
 use middleware::RocketchatToken;
 use models::{ConnectionPool, RocketchatServer, VirtualUser};
 
 /// Rocket.Chat is an endpoint of the application service API which is called by the Rocket.Chat
-/// server to push new messages.
+/// server to push new messages and membership changes.
 pub struct Rocketchat {
     /// Application service configuration
     pub config: Config,
@@ -34,12 +34,23 @@ impl Handler for Rocketchat {
         let logger = IronLogger::from_request(request)?;
         let connection = ConnectionPool::from_request(request)?;
 
-        let message = request.extensions.get::<WebhookMessage>().expect("Middleware ensures the presence of a message");
+        let event = request.extensions.get::<WebhookEvent>().expect("Middleware ensures the presence of an event");
         let server = request.extensions.get::<RocketchatServer>().expect("Middleware ensures the presence of a server");
 
         let virtual_user = VirtualUser::new(&self.config, &logger, self.matrix_api.as_ref());
-        let forwarder = Forwarder::new(&self.config, &connection, &logger, self.matrix_api.as_ref(), &virtual_user);
-        if let Err(err) = forwarder.send(server, message) {
+        let result = match *event {
+            WebhookEvent::Message(ref message) => {
+                let forwarder = Forwarder::new(&self.config, &connection, &logger, self.matrix_api.as_ref(), &virtual_user);
+                forwarder.send(server, message)
+            }
+            WebhookEvent::Membership(ref membership) => {
+                let membership_handler =
+                    MembershipHandler::new(&self.config, &connection, &logger, self.matrix_api.as_ref(), &virtual_user);
+                membership_handler.process(server, membership)
+            }
+        };
+
+        if let Err(err) = result {
             log::log_error(&logger, &err);
         }
 
@@ -10,7 +10,7 @@ use config::Config;
 use errors::*;
 use i18n::*;
 use log::IronLogger;
-use models::{ConnectionPool, Credentials, RocketchatServer};
+use models::{ConnectionPool, Credentials, RocketchatServer, TokenCredentials};
 
 /// `RocketchatLogin` is an endpoint that allows a user to login to Rocket.Chat via REST API.
 pub struct RocketchatLogin {
@@ -20,25 +20,45 @@ pub struct RocketchatLogin {
     pub matrix_api: Box<MatrixApi>,
 }
 
+/// A login request, either with the Rocket.Chat password or with a personal access token
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LoginRequest {
+    PersonalAccessToken(TokenCredentials),
+    Password(Credentials),
+}
+
 impl Handler for RocketchatLogin {
     fn handle(&self, request: &mut Request) -> IronResult<Response> {
         let logger = IronLogger::from_request(request)?;
         info!(logger, "Received login command via REST API");
 
         let connection = ConnectionPool::from_request(request)?;
-        let credentials = deserialize_credentials(&mut request.body)?;
-        let server = match RocketchatServer::find_by_url(&connection, &credentials.rocketchat_url)? {
+        let login_request = deserialize_login_request(&mut request.body)?;
+        let rocketchat_url = match login_request {
+            LoginRequest::PersonalAccessToken(ref credentials) => credentials.rocketchat_url.clone(),
+            LoginRequest::Password(ref credentials) => credentials.rocketchat_url.clone(),
+        };
+        let server = match RocketchatServer::find_by_url(&connection, &rocketchat_url)? {
             Some(server) => server,
             None => {
                 return Err(user_error!(
-                    ErrorKind::AdminRoomForRocketchatServerNotFound(credentials.rocketchat_url.clone()),
-                    t!(["errors", "rocketchat_server_not_found"])
-                        .with_vars(vec![("rocketchat_url", credentials.rocketchat_url.clone())])
+                    ErrorKind::AdminRoomForRocketchatServerNotFound(rocketchat_url.clone()),
+                    t!(["errors", "rocketchat_server_not_found"]).with_vars(vec![("rocketchat_url", rocketchat_url.clone())])
                 ))?;
             }
         };
 
-        if let Err(err) = server.login(&self.config, &connection, &logger, self.matrix_api.as_ref(), &credentials, None) {
+        let login_result = match login_request {
+            LoginRequest::PersonalAccessToken(ref credentials) => {
+                server.login_with_token(&self.config, &connection, &logger, self.matrix_api.as_ref(), credentials, None)
+            }
+            LoginRequest::Password(ref credentials) => {
+                server.login(&self.config, &connection, &logger, self.matrix_api.as_ref(), credentials, None)
+            }
+        };
+
+        if let Err(err) = login_result {
             return Err(err)?;
         }
 
@@ -46,7 +66,7 @@ impl Handler for RocketchatLogin {
     }
 }
 
-fn deserialize_credentials(body: &mut Body) -> Result<Credentials> {
+fn deserialize_login_request(body: &mut Body) -> Result<LoginRequest> {
     let mut payload = String::new();
     body.read_to_string(&mut payload).chain_err(|| ErrorKind::InternalServerError)?;
     serde_json::from_str(&payload)
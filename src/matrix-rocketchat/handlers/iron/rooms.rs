@@ -0,0 +1,60 @@
+use std::convert::TryFrom;
+
+use iron::prelude::*;
+use iron::{status, Handler};
+use router::Router;
+use ruma_identifiers::RoomAliasId;
+use url::percent_encoding::percent_decode;
+
+use api::MatrixApi;
+use config::Config;
+use handlers::matrix::QueryHandler;
+use log::{self, IronLogger};
+use middleware::AccessToken;
+use models::ConnectionPool;
+
+/// Rooms is an endpoint of the application service API which is called by the homeserver to
+/// query if a room alias exists.
+pub struct Rooms {
+    config: Config,
+    matrix_api: Box<MatrixApi>,
+}
+
+impl Rooms {
+    /// Rooms endpoint with middleware
+    pub fn chain(config: Config, matrix_api: Box<MatrixApi>) -> Chain {
+        let rooms = Rooms { config: config.clone(), matrix_api };
+        let mut chain = Chain::new(rooms);
+        chain.link_before(AccessToken { config });
+
+        chain
+    }
+}
+
+impl Handler for Rooms {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        let logger = IronLogger::from_request(request)?;
+
+        let url_room_alias =
+            request.extensions.get::<Router>().and_then(|params| params.find("room_alias")).unwrap_or_default();
+        let decoded_room_alias = percent_decode(url_room_alias.as_bytes()).decode_utf8_lossy().to_string();
+        let room_alias_id = match RoomAliasId::try_from(decoded_room_alias.as_ref()) {
+            Ok(room_alias_id) => room_alias_id,
+            Err(_) => {
+                debug!(logger, "Received query for invalid room alias {}", decoded_room_alias);
+                return Ok(Response::with((status::NotFound, "{}".to_string())));
+            }
+        };
+
+        let connection = ConnectionPool::from_request(request)?;
+
+        match QueryHandler::new(&self.config, &connection, &logger, self.matrix_api.as_ref()).query_room_alias(&room_alias_id) {
+            Ok(true) => Ok(Response::with((status::Ok, "{}".to_string()))),
+            Ok(false) => Ok(Response::with((status::NotFound, "{}".to_string()))),
+            Err(err) => {
+                log::log_error(&logger, &err);
+                Err(err.into())
+            }
+        }
+    }
+}
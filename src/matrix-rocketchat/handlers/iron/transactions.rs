@@ -1,8 +1,10 @@
+use std::collections::HashMap;
 use std::io::Read;
 
 use iron::prelude::*;
 use iron::request::Body;
 use iron::{status, Handler};
+use ruma_identifiers::EventId;
 use serde_json;
 
 use api::MatrixApi;
@@ -11,7 +13,7 @@ use errors::*;
 use handlers::matrix::Dispatcher;
 use log::{self, IronLogger};
 use middleware::AccessToken;
-use models::{ConnectionPool, Events};
+use models::{ConnectionPool, Events, MessageContentExtensions};
 
 /// Transactions is an endpoint of the application service API which is called by the homeserver
 /// to push new events.
@@ -35,8 +37,8 @@ impl Handler for Transactions {
     fn handle(&self, request: &mut Request) -> IronResult<Response> {
         let logger = IronLogger::from_request(request)?;
 
-        let events_batch = match deserialize_events(&mut request.body) {
-            Ok(events_batch) => events_batch,
+        let (events_batch, extensions) = match deserialize_events(&mut request.body) {
+            Ok(events_with_extensions) => events_with_extensions,
             Err(err) => {
                 log::log_error(&logger, &err);
                 return Ok(Response::with((status::Ok, "{}".to_string())));
@@ -45,8 +47,8 @@ impl Handler for Transactions {
 
         let connection = ConnectionPool::from_request(request)?;
 
-        if let Err(err) =
-            Dispatcher::new(&self.config, &connection, &logger, self.matrix_api.clone()).process(events_batch.events)
+        if let Err(err) = Dispatcher::new(&self.config, &connection, &logger, self.matrix_api.clone())
+            .process(events_batch.events, &extensions)
         {
             log::log_error(&logger, &err);
         }
@@ -55,16 +57,17 @@ impl Handler for Transactions {
     }
 }
 
-fn deserialize_events(body: &mut Body) -> Result<Events> {
+fn deserialize_events(body: &mut Body) -> Result<(Events, HashMap<EventId, MessageContentExtensions>)> {
     let mut payload = String::new();
     body.read_to_string(&mut payload).chain_err(|| ErrorKind::InternalServerError)?;
-    serde_json::from_str(&payload)
-        .chain_err(|| {
-            ErrorKind::InvalidJSON(format!(
-                "Could not deserialize events that were sent to the transactions endpoint: \
-                 `{}`",
-                payload
-            ))
-        })
-        .map_err(Error::from)
+    let events = serde_json::from_str(&payload).chain_err(|| {
+        ErrorKind::InvalidJSON(format!(
+            "Could not deserialize events that were sent to the transactions endpoint: \
+             `{}`",
+            payload
+        ))
+    })?;
+    let extensions = MessageContentExtensions::from_payload(&payload)?;
+
+    Ok((events, extensions))
 }
@@ -0,0 +1,59 @@
+use std::convert::TryFrom;
+
+use iron::prelude::*;
+use iron::{status, Handler};
+use router::Router;
+use ruma_identifiers::UserId;
+use url::percent_encoding::percent_decode;
+
+use api::MatrixApi;
+use config::Config;
+use handlers::matrix::QueryHandler;
+use log::{self, IronLogger};
+use middleware::AccessToken;
+use models::ConnectionPool;
+
+/// Users is an endpoint of the application service API which is called by the homeserver to
+/// query if a virtual user exists.
+pub struct Users {
+    config: Config,
+    matrix_api: Box<MatrixApi>,
+}
+
+impl Users {
+    /// Users endpoint with middleware
+    pub fn chain(config: Config, matrix_api: Box<MatrixApi>) -> Chain {
+        let users = Users { config: config.clone(), matrix_api };
+        let mut chain = Chain::new(users);
+        chain.link_before(AccessToken { config });
+
+        chain
+    }
+}
+
+impl Handler for Users {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        let logger = IronLogger::from_request(request)?;
+
+        let url_user_id = request.extensions.get::<Router>().and_then(|params| params.find("user_id")).unwrap_or_default();
+        let decoded_user_id = percent_decode(url_user_id.as_bytes()).decode_utf8_lossy().to_string();
+        let user_id = match UserId::try_from(decoded_user_id.as_ref()) {
+            Ok(user_id) => user_id,
+            Err(_) => {
+                debug!(logger, "Received query for invalid user ID {}", decoded_user_id);
+                return Ok(Response::with((status::NotFound, "{}".to_string())));
+            }
+        };
+
+        let connection = ConnectionPool::from_request(request)?;
+
+        match QueryHandler::new(&self.config, &connection, &logger, self.matrix_api.as_ref()).query_user(&user_id) {
+            Ok(true) => Ok(Response::with((status::Ok, "{}".to_string()))),
+            Ok(false) => Ok(Response::with((status::NotFound, "{}".to_string()))),
+            Err(err) => {
+                log::log_error(&logger, &err);
+                Err(err.into())
+            }
+        }
+    }
+}
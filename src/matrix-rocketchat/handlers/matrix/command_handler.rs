@@ -2,16 +2,20 @@ use diesel::sqlite::SqliteConnection;
 use diesel::Connection;
 use ruma_events::room::message::MessageEvent;
 use ruma_events::room::message::MessageEventContent;
-use ruma_identifiers::{RoomAliasId, UserId};
+use ruma_identifiers::{RoomAliasId, RoomId, UserId};
 use slog::Logger;
 
 use api::rocketchat::Channel;
 use api::{MatrixApi, RocketchatApi};
 use config::Config;
 use errors::*;
+use handlers::matrix::command_parser::{self, Command};
 use i18n::*;
+use log;
 use models::{
-    Credentials, NewRocketchatServer, NewUserOnRocketchatServer, RocketchatRoom, RocketchatServer, Room, UserOnRocketchatServer,
+    AdminRoom, BridgedRoom, Credentials, DirectMessageMember, DirectMessageReceiver, DirectMessageRoom, NewAdminRoom,
+    NewRocketchatServer, NewUserOnRocketchatServer, RocketchatRoom, RocketchatServer, Room, TokenCredentials,
+    UserOnRocketchatServer, VirtualUser, CHANNEL_KIND, GROUP_KIND,
 };
 use MAX_ROCKETCHAT_SERVER_ID_LENGTH;
 
@@ -46,42 +50,74 @@ impl<'a> CommandHandler<'a> {
             }
         };
 
-        if message.starts_with("connect") {
-            debug!(self.logger, "Received connect command: {}", message);
+        let result = self.execute(event, &message);
 
-            self.connect(event, &message)?;
-        } else if message == "help" {
-            debug!(self.logger, "Received help command");
-
-            self.help(event)?;
-        } else if message.starts_with("login") {
-            debug!(self.logger, "Received login command");
+        // login messages are redacted even if the command failed, because they contain the
+        // password or token in any case
+        if command_parser::contains_credentials(&message) {
+            self.redact_credentials(event)?;
+        }
 
-            let server = self.get_rocketchat_server()?;
-            self.login(event, &server, &message)?;
-        } else if message == "list" {
-            debug!(self.logger, "Received list command");
+        result
+    }
 
-            let server = self.get_rocketchat_server()?;
-            self.list_rocketchat_rooms(event, &server)?;
-        } else if message.starts_with("bridge") {
-            debug!(self.logger, "Received bridge command");
+    fn execute(&self, event: &MessageEvent, message: &str) -> Result<()> {
+        let command = match Command::parse(message)? {
+            Some(command) => command,
+            None => {
+                debug!(self.logger, "Skipping empty command");
+                return Ok(());
+            }
+        };
 
-            let server = self.get_rocketchat_server()?;
-            self.bridge(event, &server, &message)?;
-        } else if message.starts_with("unbridge") {
-            debug!(self.logger, "Received unbridge command");
+        debug!(self.logger, "Received {} command", command.spec.name);
 
-            let server = self.get_rocketchat_server()?;
-            self.unbridge(event, &server, &message)?;
-        } else {
-            debug!(self.logger, "Skipping event, don't know how to handle command `{}`", message);
+        match command.spec.name {
+            "connect" => self.connect(event, &command)?,
+            "help" => self.help(event, &command)?,
+            "login" => {
+                let server = self.get_rocketchat_server()?;
+                self.login(event, &server, &command)?;
+            }
+            "login-token" => {
+                let server = self.get_rocketchat_server()?;
+                self.login_with_token(event, &server, &command)?;
+            }
+            "list" => {
+                let server = self.get_rocketchat_server()?;
+                self.list_rocketchat_rooms(event, &server)?;
+            }
+            "bridge" => {
+                let server = self.get_rocketchat_server()?;
+                self.bridge(event, &server, &command)?;
+            }
+            "unbridge" => {
+                let server = self.get_rocketchat_server()?;
+                self.unbridge(event, &server, &command)?;
+            }
+            "dm" => {
+                let server = self.get_rocketchat_server()?;
+                self.direct_message(event, &server, &command)?;
+            }
+            "logout" => {
+                let server = self.get_rocketchat_server()?;
+                self.logout(event, &server)?;
+            }
+            "disconnect" => {
+                let server = self.get_rocketchat_server()?;
+                self.disconnect(event, &server, &command)?;
+            }
+            "status" | "whoami" => {
+                let server = self.get_rocketchat_server()?;
+                self.status(event, &server)?;
+            }
+            name => debug!(self.logger, "Skipping event, don't know how to handle command `{}`", name),
         }
 
         Ok(())
     }
 
-    fn connect(&self, event: &MessageEvent, message: &str) -> Result<()> {
+    fn connect(&self, event: &MessageEvent, command: &Command) -> Result<()> {
         self.connection
             .transaction(|| {
                 if self.admin_room.is_connected(self.connection)? {
@@ -91,19 +127,25 @@ impl<'a> CommandHandler<'a> {
                     );
                 }
 
-                let mut command = message.split_whitespace().collect::<Vec<&str>>().into_iter();
-                let rocketchat_url = command.by_ref().nth(1).unwrap_or_default();
+                let rocketchat_url = command.arg(0);
 
                 debug!(self.logger, "Connecting to Rocket.Chat server {}", rocketchat_url);
 
-                let server = match command.by_ref().next() {
+                let server = match command.args.get(1) {
                     Some(token) => {
-                        let rocketchat_id = command.by_ref().next().unwrap_or_default();
+                        let rocketchat_id = command.arg(2);
                         self.connect_new_rocketchat_server(rocketchat_id, rocketchat_url, token, &event.user_id)?
                     }
                     None => self.get_existing_rocketchat_server(rocketchat_url)?,
                 };
 
+                let new_admin_room = NewAdminRoom {
+                    matrix_room_id: &self.admin_room.id,
+                    matrix_user_id: &event.user_id,
+                    rocketchat_server_id: &server.id,
+                };
+                AdminRoom::insert(self.connection, &new_admin_room)?;
+
                 let new_user_on_rocketchat_server = NewUserOnRocketchatServer {
                     matrix_user_id: event.user_id.clone(),
                     rocketchat_server_id: server.id,
@@ -112,7 +154,8 @@ impl<'a> CommandHandler<'a> {
                 };
 
                 UserOnRocketchatServer::upsert(self.connection, &new_user_on_rocketchat_server)?;
-                self.matrix_api.set_room_topic(self.admin_room.id.clone(), rocketchat_url.to_string())?;
+                let bot_user_id = self.config.matrix_bot_user_id()?;
+                self.matrix_api.set_room_topic(self.admin_room.id.clone(), bot_user_id, rocketchat_url.to_string())?;
 
                 let body = CommandHandler::build_help_message(
                     self.connection,
@@ -181,9 +224,16 @@ impl<'a> CommandHandler<'a> {
         RocketchatServer::insert(self.connection, &new_rocketchat_server)
     }
 
-    fn help(&self, event: &MessageEvent) -> Result<()> {
-        let help_message =
-            CommandHandler::build_help_message(self.connection, self.admin_room, self.config.as_url.clone(), &event.user_id)?;
+    fn help(&self, event: &MessageEvent, command: &Command) -> Result<()> {
+        let help_message = match command.args.first() {
+            Some(name) => Command::find_spec(name)?.help().l(DEFAULT_LANGUAGE),
+            None => CommandHandler::build_help_message(
+                self.connection,
+                self.admin_room,
+                self.config.as_url.clone(),
+                &event.user_id,
+            )?,
+        };
         let bot_user_id = self.config.matrix_bot_user_id()?;
         self.matrix_api.send_text_message(self.admin_room.id.clone(), bot_user_id, help_message)?;
 
@@ -191,15 +241,11 @@ impl<'a> CommandHandler<'a> {
         Ok(())
     }
 
-    fn login(&self, event: &MessageEvent, server: &RocketchatServer, message: &str) -> Result<()> {
-        let mut command = message.split_whitespace().collect::<Vec<&str>>().into_iter();
-        let username = command.by_ref().nth(1).unwrap_or_default();
-        let password = command.by_ref().fold("".to_string(), |acc, x| acc + x);
-
+    fn login(&self, event: &MessageEvent, server: &RocketchatServer, command: &Command) -> Result<()> {
         let credentials = Credentials {
             user_id: event.user_id.clone(),
-            rocketchat_username: username.to_string(),
-            password: password.to_string(),
+            rocketchat_username: command.arg(0).to_string(),
+            password: command.arg(1).to_string(),
             rocketchat_url: server.rocketchat_url.clone(),
         };
 
@@ -207,6 +253,33 @@ impl<'a> CommandHandler<'a> {
         server.login(self.config, self.connection, self.logger, self.matrix_api, &credentials, admin_room_id)
     }
 
+    fn login_with_token(&self, event: &MessageEvent, server: &RocketchatServer, command: &Command) -> Result<()> {
+        let credentials = TokenCredentials {
+            user_id: event.user_id.clone(),
+            rocketchat_user_id: command.arg(0).to_string(),
+            personal_access_token: command.arg(1).to_string(),
+            rocketchat_url: server.rocketchat_url.clone(),
+        };
+
+        let admin_room_id = Some(self.admin_room.id.clone());
+        server.login_with_token(self.config, self.connection, self.logger, self.matrix_api, &credentials, admin_room_id)
+    }
+
+    fn redact_credentials(&self, event: &MessageEvent) -> Result<()> {
+        let bot_user_id = self.config.matrix_bot_user_id()?;
+        let message = match self.matrix_api.redact_event(event.room_id.clone(), event.event_id.clone(), bot_user_id.clone()) {
+            Ok(()) => t!(["admin_room", "credentials_redacted"]),
+            Err(err) => {
+                log::log_info(self.logger, &err);
+                t!(["admin_room", "credentials_redaction_failed"])
+            }
+        };
+        self.matrix_api.send_text_message(event.room_id.clone(), bot_user_id, message.l(DEFAULT_LANGUAGE))?;
+
+        debug!(self.logger, "Redacted credentials of user {} in room {}", event.user_id, event.room_id);
+        Ok(())
+    }
+
     fn list_rocketchat_rooms(&self, event: &MessageEvent, server: &RocketchatServer) -> Result<()> {
         let user_on_rocketchat_server = UserOnRocketchatServer::find(self.connection, &event.user_id, server.id.clone())?;
         let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
@@ -222,7 +295,7 @@ impl<'a> CommandHandler<'a> {
         Ok(())
     }
 
-    fn bridge(&self, event: &MessageEvent, server: &RocketchatServer, message: &str) -> Result<()> {
+    fn bridge(&self, event: &MessageEvent, server: &RocketchatServer, command: &Command) -> Result<()> {
         let bot_user_id = self.config.matrix_bot_user_id()?;
         let user_on_rocketchat_server = UserOnRocketchatServer::find(self.connection, &event.user_id, server.id.clone())?;
         let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
@@ -233,19 +306,18 @@ impl<'a> CommandHandler<'a> {
         let channels = rocketchat_api.channels_list()?;
         let groups = rocketchat_api.groups_list()?;
 
-        let mut command = message.split_whitespace().collect::<Vec<&str>>().into_iter();
-        let rocketchat_room_name = command.by_ref().nth(1).unwrap_or_default();
+        let rocketchat_room_name = command.arg(0);
 
-        let (rocketchat_room_id, users) =
+        let (rocketchat_room_id, kind, users) =
             match channels.iter().find(|channel| channel.name.clone().unwrap_or_default() == rocketchat_room_name) {
                 Some(channel) => {
                     let users = rocketchat_api.channels_members(&channel.id)?;
-                    (channel.id.clone(), users)
+                    (channel.id.clone(), CHANNEL_KIND, users)
                 }
                 None => match groups.iter().find(|group| group.name.clone().unwrap_or_default() == rocketchat_room_name) {
                     Some(group) => {
                         let users = rocketchat_api.groups_members(&group.id)?;
-                        (group.id.clone(), users)
+                        (group.id.clone(), GROUP_KIND, users)
                     }
                     None => {
                         bail_error!(
@@ -267,7 +339,7 @@ impl<'a> CommandHandler<'a> {
         }
 
         let rocketchat_room = RocketchatRoom::new(self.config, self.logger, self.matrix_api, rocketchat_room_id, &server.id);
-        let room_id = match rocketchat_room.matrix_id()? {
+        let room_id = match rocketchat_room.matrix_id(self.connection)? {
             Some(room_id) => {
                 let room = Room::new(self.config, self.logger, self.matrix_api, room_id.clone());
                 room.bridge_for_user(event.user_id.clone(), rocketchat_room_name.to_string())?;
@@ -276,10 +348,11 @@ impl<'a> CommandHandler<'a> {
             None => {
                 let usernames: Vec<String> = users.into_iter().map(|u| u.username).collect();
                 rocketchat_room.bridge(
+                    self.connection,
                     rocketchat_api.as_ref(),
                     &Some(rocketchat_room_name.to_string()),
+                    kind,
                     &usernames,
-                    &bot_user_id,
                     &event.user_id,
                 )?
             }
@@ -293,9 +366,8 @@ impl<'a> CommandHandler<'a> {
         Ok(())
     }
 
-    fn unbridge(&self, event: &MessageEvent, server: &RocketchatServer, message: &str) -> Result<()> {
-        let mut command = message.split_whitespace().collect::<Vec<&str>>().into_iter();
-        let name = command.nth(1).unwrap_or_default().to_string();
+    fn unbridge(&self, event: &MessageEvent, server: &RocketchatServer, command: &Command) -> Result<()> {
+        let name = command.arg(0).to_string();
 
         let user_on_rocketchat_server = UserOnRocketchatServer::find(self.connection, &event.user_id, server.id.clone())?;
         let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
@@ -305,8 +377,8 @@ impl<'a> CommandHandler<'a> {
 
         let rocketchat_room =
             RocketchatRoom::from_name(self.config, self.logger, self.matrix_api, &name, &server.id, rocketchat_api.as_ref())?;
-        let rocketchat_room_id = match rocketchat_room.matrix_id()? {
-            Some(rocketchat_room_id) => rocketchat_room_id,
+        let bridged_room = match BridgedRoom::find_by_rocketchat_channel_id(self.connection, &server.id, &rocketchat_room.id)? {
+            Some(bridged_room) => bridged_room,
             None => {
                 bail_error!(
                     ErrorKind::UnbridgeOfNotBridgedRoom(name.to_string()),
@@ -315,7 +387,7 @@ impl<'a> CommandHandler<'a> {
             }
         };
 
-        let room = Room::new(self.config, self.logger, self.matrix_api, rocketchat_room_id.clone());
+        let room = Room::new(self.config, self.logger, self.matrix_api, bridged_room.matrix_room_id.clone());
         let user_ids = room.user_ids(None)?;
         // scope to drop non_virtual_user_ids
         {
@@ -343,21 +415,301 @@ impl<'a> CommandHandler<'a> {
             );
         }
 
+        self.unbridge_room(&room, canonical_alias_id, &bridged_room, user_ids)?;
+
+        let bot_user_id = self.config.matrix_bot_user_id()?;
+        let message = t!(["admin_room", "room_successfully_unbridged"]).with_vars(vec![("rocketchat_room_name", name.clone())]);
+        self.matrix_api.send_text_message(event.room_id.clone(), bot_user_id, message.l(DEFAULT_LANGUAGE))?;
+
+        info!(self.logger, "Successfully unbridged room {}", name.clone());
+        Ok(())
+    }
+
+    fn unbridge_room(
+        &self,
+        room: &Room,
+        canonical_alias_id: RoomAliasId,
+        bridged_room: &BridgedRoom,
+        user_ids: Vec<UserId>,
+    ) -> Result<()> {
         self.matrix_api.delete_room_alias(canonical_alias_id)?;
+        bridged_room.delete(self.connection)?;
 
         for user_id in user_ids {
             debug!(self.logger, "Leaving and forgetting room {} for user {}", room.id, user_id);
             room.forget(user_id)?;
         }
 
+        Ok(())
+    }
+
+    fn direct_message(&self, event: &MessageEvent, server: &RocketchatServer, command: &Command) -> Result<()> {
+        let user_on_rocketchat_server = UserOnRocketchatServer::find(self.connection, &event.user_id, server.id.clone())?;
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            user_on_rocketchat_server.rocketchat_user_id.clone().unwrap_or_default(),
+            user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+
+        let rocketchat_user = rocketchat_api.users_info(command.arg(0))?;
+        let channel = rocketchat_api.im_create(&rocketchat_user.username)?;
+        let members = rocketchat_api.im_members(&channel.id)?;
+        DirectMessageMember::replace_all(self.connection, &server.id, &channel.id, &members)?;
+
+        let virtual_user = VirtualUser::new(self.config, self.logger, self.matrix_api);
+        let sender_id = virtual_user.find_or_register(&server.id, &rocketchat_user.id, &rocketchat_user.username)?;
+
+        let existing_room = Room::get_dm(
+            self.config,
+            self.connection,
+            self.logger,
+            self.matrix_api,
+            &server.id,
+            channel.id.clone(),
+            &sender_id,
+            &event.user_id,
+        )?;
+        let room = match existing_room {
+            Some(room) => {
+                room.join_user(event.user_id.clone(), room.query_user_id(self.connection)?)?;
+                room
+            }
+            None => Room::create_dm(
+                self.config,
+                self.connection,
+                self.logger,
+                self.matrix_api,
+                &server.id,
+                channel.id.clone(),
+                &sender_id,
+                &rocketchat_user.username,
+                &event.user_id,
+            )?,
+        };
+
         let bot_user_id = self.config.matrix_bot_user_id()?;
-        let message = t!(["admin_room", "room_successfully_unbridged"]).with_vars(vec![("rocketchat_room_name", name.clone())]);
+        let message = t!(["admin_room", "direct_message_room_ready"])
+            .with_vars(vec![("rocketchat_username", rocketchat_user.username.clone()), ("room_id", room.id.to_string())]);
         self.matrix_api.send_text_message(event.room_id.clone(), bot_user_id, message.l(DEFAULT_LANGUAGE))?;
 
-        info!(self.logger, "Successfully unbridged room {}", name.clone());
+        info!(self.logger, "Successfully started direct message {} in room {}", channel.id, room.id);
+        Ok(())
+    }
+
+    fn logout(&self, event: &MessageEvent, server: &RocketchatServer) -> Result<()> {
+        let mut user_on_rocketchat_server = UserOnRocketchatServer::find(self.connection, &event.user_id, server.id.clone())?;
+        if !user_on_rocketchat_server.is_logged_in() {
+            bail_error!(
+                ErrorKind::NotLoggedIn(server.rocketchat_url.clone()),
+                t!(["errors", "not_logged_in"]).with_vars(vec![("rocketchat_url", server.rocketchat_url.clone())])
+            );
+        }
+
+        self.logout_user(&mut user_on_rocketchat_server, server)?;
+
+        let bot_user_id = self.config.matrix_bot_user_id()?;
+        let message =
+            t!(["admin_room", "logout_successful"]).with_vars(vec![("rocketchat_url", server.rocketchat_url.clone())]);
+        self.matrix_api.send_text_message(event.room_id.clone(), bot_user_id, message.l(DEFAULT_LANGUAGE))?;
+
+        info!(self.logger, "Successfully logged out user {} from {}", event.user_id, server.rocketchat_url);
+        Ok(())
+    }
+
+    // The credentials are removed even if the Rocket.Chat server rejects the logout request,
+    // because the auth token is most likely not valid anymore in that case.
+    fn logout_user(&self, user_on_rocketchat_server: &mut UserOnRocketchatServer, server: &RocketchatServer) -> Result<()> {
+        let rocketchat_user_id = user_on_rocketchat_server.rocketchat_user_id.clone();
+        let logout_result = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone()).and_then(|rocketchat_api| {
+            rocketchat_api
+                .with_credentials(
+                    rocketchat_user_id.clone().unwrap_or_default(),
+                    user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
+                )
+                .logout()
+        });
+        if let Err(err) = logout_result {
+            log::log_info(self.logger, &err);
+        }
+
+        user_on_rocketchat_server.set_credentials(self.connection, rocketchat_user_id, None)
+    }
+
+    fn disconnect(&self, event: &MessageEvent, server: &RocketchatServer, command: &Command) -> Result<()> {
+        let unbridged_room_ids =
+            if command.has_flag("unbridge") { self.unbridge_rooms_of_user(&event.user_id, server)? } else { Vec::new() };
+
+        let mut user_on_rocketchat_server = UserOnRocketchatServer::find(self.connection, &event.user_id, server.id.clone())?;
+        if user_on_rocketchat_server.is_logged_in() {
+            self.logout_user(&mut user_on_rocketchat_server, server)?;
+        }
+
+        if let Some(admin_room) = AdminRoom::find_by_matrix_room_id(self.connection, &self.admin_room.id)? {
+            admin_room.delete(self.connection)?;
+        }
+
+        // the topic is only informational, the room is disconnected even if it cannot be removed
+        let bot_user_id = self.config.matrix_bot_user_id()?;
+        if let Err(err) = self.matrix_api.set_room_topic(self.admin_room.id.clone(), bot_user_id.clone(), "".to_string()) {
+            log::log_info(self.logger, &err);
+        }
+
+        let rocketchat_url = ("rocketchat_url", server.rocketchat_url.clone());
+        let message = if unbridged_room_ids.is_empty() {
+            t!(["admin_room", "disconnect_successful"]).with_vars(vec![rocketchat_url])
+        } else {
+            let rooms = unbridged_room_ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(", ");
+            t!(["admin_room", "disconnect_successful_with_unbridged_rooms"]).with_vars(vec![rocketchat_url, ("rooms", rooms)])
+        };
+        self.matrix_api.send_text_message(event.room_id.clone(), bot_user_id, message.l(DEFAULT_LANGUAGE))?;
+
+        info!(self.logger, "Successfully disconnected room {} from {}", self.admin_room.id, server.rocketchat_url);
+        Ok(())
+    }
+
+    // Only rooms in which the user is the last Matrix user and that have no other aliases are unbridged.
+    fn unbridge_rooms_of_user(&self, user_id: &UserId, server: &RocketchatServer) -> Result<Vec<RoomId>> {
+        let mut unbridged_room_ids = Vec::new();
+        for bridged_room in BridgedRoom::find_by_rocketchat_server_id(self.connection, &server.id)? {
+            let room = Room::new(self.config, self.logger, self.matrix_api, bridged_room.matrix_room_id.clone());
+            let user_ids = room.user_ids(None)?;
+            let non_virtual_user_ids: Vec<&UserId> =
+                user_ids.iter().filter(|id| !self.config.is_application_service_user(id)).collect();
+            if non_virtual_user_ids != vec![user_id] {
+                continue;
+            }
+
+            let rocketchat_room = RocketchatRoom::new(
+                self.config,
+                self.logger,
+                self.matrix_api,
+                bridged_room.rocketchat_channel_id.clone(),
+                &server.id,
+            );
+            let canonical_alias_id = rocketchat_room.build_room_alias_id()?;
+            if room.aliases()?.iter().any(|alias| alias != &canonical_alias_id) {
+                debug!(self.logger, "Not unbridging room {}, because it has aliases", room.id);
+                continue;
+            }
+
+            self.unbridge_room(&room, canonical_alias_id, &bridged_room, user_ids)?;
+            unbridged_room_ids.push(room.id.clone());
+        }
+
+        Ok(unbridged_room_ids)
+    }
+
+    fn status(&self, event: &MessageEvent, server: &RocketchatServer) -> Result<()> {
+        let user_on_rocketchat_server = UserOnRocketchatServer::find(self.connection, &event.user_id, server.id.clone())?;
+
+        let (login_status, bridged_rooms) = if user_on_rocketchat_server.is_logged_in() {
+            let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+                user_on_rocketchat_server.rocketchat_user_id.clone().unwrap_or_default(),
+                user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
+            );
+
+            // the token is considered invalid if the Rocket.Chat server does not return the user
+            match rocketchat_api.me() {
+                Ok(user) => (
+                    t!(["admin_room", "status_logged_in"]).with_vars(vec![("rocketchat_username", user.username)]),
+                    self.build_bridged_rooms_status(server, rocketchat_api.as_ref())?,
+                ),
+                Err(err) => {
+                    log::log_info(self.logger, &err);
+                    (t!(["admin_room", "status_invalid_token"]), Vec::new())
+                }
+            }
+        } else {
+            (t!(["admin_room", "status_not_logged_in"]), Vec::new())
+        };
+
+        let direct_message_rooms = self.build_direct_message_rooms_status(server, &user_on_rocketchat_server)?;
+
+        let message = t!(["admin_room", "status"]).with_vars(vec![
+            ("rocketchat_url", server.rocketchat_url.clone()),
+            ("rocketchat_server_id", server.id.clone()),
+            ("login_status", login_status.l(DEFAULT_LANGUAGE)),
+            ("bridged_rooms", CommandHandler::format_status_list(bridged_rooms)),
+            ("direct_message_rooms", CommandHandler::format_status_list(direct_message_rooms)),
+        ]);
+        let bot_user_id = self.config.matrix_bot_user_id()?;
+        self.matrix_api.send_text_message(event.room_id.clone(), bot_user_id, message.l(DEFAULT_LANGUAGE))?;
+
+        debug!(self.logger, "Successfully executed status command for user {}", event.user_id);
         Ok(())
     }
 
+    // The bridged rooms are looked up in the channels and groups that the user joined on the
+    // Rocket.Chat server, which also provides their names. The application service cannot query the
+    // rooms of Matrix users outside of its namespace.
+    fn build_bridged_rooms_status(&self, server: &RocketchatServer, rocketchat_api: &RocketchatApi) -> Result<Vec<String>> {
+        let mut joined_rocketchat_rooms = rocketchat_api.channels_list_joined()?;
+        joined_rocketchat_rooms.append(&mut rocketchat_api.groups_list()?);
+
+        let mut bridged_rooms = Vec::new();
+        for bridged_room in BridgedRoom::find_by_rocketchat_server_id(self.connection, &server.id)? {
+            let channel_id = &bridged_room.rocketchat_channel_id;
+            if let Some(rocketchat_room) = joined_rocketchat_rooms.iter().find(|room| &room.id == channel_id) {
+                let name = rocketchat_room.name.clone().unwrap_or_else(|| channel_id.clone());
+                bridged_rooms.push(format!("{} ({})", name, bridged_room.matrix_room_id));
+            }
+        }
+
+        Ok(bridged_rooms)
+    }
+
+    // The direct message rooms are listed with the other participants of the direct message, the
+    // participants are only stored for direct messages with more than two participants, otherwise
+    // the user that is used to query the room is the other participant.
+    fn build_direct_message_rooms_status(
+        &self,
+        server: &RocketchatServer,
+        user_on_rocketchat_server: &UserOnRocketchatServer,
+    ) -> Result<Vec<String>> {
+        let virtual_user = VirtualUser::new(self.config, self.logger, self.matrix_api);
+        let user_id = &user_on_rocketchat_server.matrix_user_id;
+        let own_rocketchat_user_id = user_on_rocketchat_server.rocketchat_user_id.clone().unwrap_or_default();
+
+        let mut direct_message_rooms = Vec::new();
+        for receiver in DirectMessageReceiver::find_by_matrix_user_id(self.connection, &server.id, user_id)? {
+            let members = DirectMessageMember::find_by_rocketchat_channel_id(
+                self.connection,
+                &server.id,
+                &receiver.rocketchat_channel_id,
+            )?;
+
+            let mut participant_ids = Vec::new();
+            for member in members.iter().filter(|member| member.rocketchat_user_id != own_rocketchat_user_id) {
+                let participant_id = match UserOnRocketchatServer::find_by_rocketchat_user_id(
+                    self.connection,
+                    server.id.clone(),
+                    member.rocketchat_user_id.clone(),
+                )? {
+                    Some(user_on_rocketchat_server) => user_on_rocketchat_server.matrix_user_id,
+                    None => virtual_user.build_user_id(&member.rocketchat_user_id, &server.id)?,
+                };
+                participant_ids.push(participant_id.to_string());
+            }
+
+            if participant_ids.is_empty() {
+                if let Some(room) = DirectMessageRoom::find_by_matrix_room_id(self.connection, &receiver.matrix_room_id)? {
+                    participant_ids.push(room.matrix_query_user_id.to_string());
+                }
+            }
+
+            direct_message_rooms.push(format!("{} ({})", participant_ids.join(", "), receiver.matrix_room_id));
+        }
+
+        Ok(direct_message_rooms)
+    }
+
+    fn format_status_list(entries: Vec<String>) -> String {
+        if entries.is_empty() {
+            return t!(["admin_room", "status_no_rooms"]).l(DEFAULT_LANGUAGE);
+        }
+
+        entries.iter().fold("".to_string(), |list, entry| list + "*   " + entry + "\n\n")
+    }
+
     fn get_existing_rocketchat_server(&self, rocketchat_url: &str) -> Result<RocketchatServer> {
         let server: RocketchatServer = match RocketchatServer::find_by_url(self.connection, rocketchat_url)? {
             Some(server) => server,
@@ -402,7 +754,7 @@ impl<'a> CommandHandler<'a> {
         for r in rocketchat_rooms {
             let rocketchat_room =
                 RocketchatRoom::new(self.config, self.logger, self.matrix_api, r.id.clone(), rocketchat_server_id);
-            let formatter = if rocketchat_room.is_bridged_for_user(user_id)? {
+            let formatter = if rocketchat_room.is_bridged_for_user(self.connection, user_id)? {
                 "**"
             } else if joined_rocketchat_rooms.iter().any(|jc| jc.id == r.id) {
                 "*"
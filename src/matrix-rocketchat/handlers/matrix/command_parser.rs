@@ -0,0 +1,277 @@
+use std::cmp;
+
+use errors::*;
+use i18n::*;
+
+/// Maximum edit distance between an unknown command and a known command to suggest the known one
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Metadata of a command that can be used in the admin room
+pub struct CommandSpec {
+    /// Name of the command
+    pub name: &'static str,
+    /// Arguments that have to be provided
+    pub required_args: &'static [&'static str],
+    /// Arguments that can be omitted, they are only valid after all the required arguments
+    pub optional_args: &'static [&'static str],
+    /// Flags (without the leading `--`) that the command accepts
+    pub flags: &'static [&'static str],
+}
+
+/// All the commands that can be used in the admin room
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "connect",
+        required_args: &["rocketchaturl"],
+        optional_args: &["token", "rocketchat_server_id"],
+        flags: &[],
+    },
+    CommandSpec { name: "help", required_args: &[], optional_args: &["command"], flags: &[] },
+    CommandSpec { name: "login", required_args: &["rocketchatusername", "password"], optional_args: &[], flags: &[] },
+    CommandSpec {
+        name: "login-token",
+        required_args: &["rocketchat_user_id", "personal_access_token"],
+        optional_args: &[],
+        flags: &[],
+    },
+    CommandSpec { name: "list", required_args: &[], optional_args: &[], flags: &[] },
+    CommandSpec { name: "bridge", required_args: &["rocketchatroomname"], optional_args: &[], flags: &[] },
+    CommandSpec { name: "unbridge", required_args: &["rocketchatroomname"], optional_args: &[], flags: &[] },
+    CommandSpec { name: "dm", required_args: &["rocketchatusername"], optional_args: &[], flags: &[] },
+    CommandSpec { name: "logout", required_args: &[], optional_args: &[], flags: &[] },
+    CommandSpec { name: "disconnect", required_args: &[], optional_args: &[], flags: &["unbridge"] },
+    CommandSpec { name: "status", required_args: &[], optional_args: &[], flags: &[] },
+    CommandSpec { name: "whoami", required_args: &[], optional_args: &[], flags: &[] },
+];
+
+/// Commands that contain credentials, messages with those commands are redacted
+pub const COMMANDS_WITH_CREDENTIALS: &[&str] = &["login", "login-token"];
+
+/// A parsed command from the admin room
+pub struct Command {
+    /// The metadata of the command
+    pub spec: &'static CommandSpec,
+    /// The arguments in the order in which they were provided
+    pub args: Vec<String>,
+    /// The flags (without the leading `--`) that were provided
+    pub flags: Vec<String>,
+}
+
+/// A token of a command message, quoted tokens are never interpreted as flags.
+struct Token {
+    value: String,
+    quoted: bool,
+}
+
+impl Command {
+    /// Parse a message from the admin room. Arguments that contain whitespace can be quoted with
+    /// `"` or `'`, flags start with `--`. Returns `None` if the message is empty.
+    /// The secret of commands with credentials is the rest of the message after the user and is
+    /// taken verbatim, so that passwords can contain quotes, backslashes or start with `--`.
+    pub fn parse(message: &str) -> Result<Option<Command>> {
+        let mut parts = message.trim_left().splitn(2, char::is_whitespace);
+        if let Some(spec) = parts.next().and_then(|name| COMMANDS.iter().find(|spec| spec.name == name)) {
+            if COMMANDS_WITH_CREDENTIALS.contains(&spec.name) {
+                return Command::parse_credentials(spec, parts.next().unwrap_or_default()).map(Some);
+            }
+        }
+
+        let mut tokens = tokenize(message)?.into_iter();
+        let name = match tokens.next() {
+            Some(token) => token.value,
+            None => return Ok(None),
+        };
+        let spec = Command::find_spec(&name)?;
+
+        let mut args = Vec::new();
+        let mut flags = Vec::new();
+        let mut flags_allowed = true;
+        for token in tokens {
+            if flags_allowed && !token.quoted && token.value == "--" {
+                flags_allowed = false;
+            } else if flags_allowed && !token.quoted && token.value.starts_with("--") {
+                let flag = token.value[2..].to_string();
+                if !spec.flags.iter().any(|f| *f == flag) {
+                    bail_error!(
+                        ErrorKind::UnknownCommandFlag(spec.name.to_string(), flag.clone()),
+                        t!(["errors", "unknown_command_flag"]).with_vars(vec![
+                            ("command", spec.name.to_string()),
+                            ("flag", flag),
+                            ("usage", spec.usage()),
+                        ])
+                    );
+                }
+                flags.push(flag);
+            } else {
+                args.push(token.value);
+            }
+        }
+
+        if args.len() < spec.required_args.len() || args.len() > spec.required_args.len() + spec.optional_args.len() {
+            bail_error!(
+                ErrorKind::InvalidCommandArguments(spec.name.to_string()),
+                t!(["errors", "invalid_command_arguments"])
+                    .with_vars(vec![("command", spec.name.to_string()), ("usage", spec.usage())])
+            );
+        }
+
+        Ok(Some(Command { spec, args, flags }))
+    }
+
+    fn parse_credentials(spec: &'static CommandSpec, args: &str) -> Result<Command> {
+        let mut parts = args.trim_left().splitn(2, char::is_whitespace);
+        let user = parts.next().unwrap_or_default();
+        let secret = parts.next().unwrap_or_default().trim();
+
+        if user.is_empty() || secret.is_empty() {
+            bail_error!(
+                ErrorKind::InvalidCommandArguments(spec.name.to_string()),
+                t!(["errors", "invalid_credentials_arguments"])
+                    .with_vars(vec![("command", spec.name.to_string()), ("usage", spec.usage())])
+            );
+        }
+
+        Ok(Command { spec, args: vec![user.to_string(), secret.to_string()], flags: Vec::new() })
+    }
+
+    /// Find the metadata of a command by its name. Returns an error that suggests a similar
+    /// command if no command with the name exists.
+    pub fn find_spec(name: &str) -> Result<&'static CommandSpec> {
+        if let Some(spec) = COMMANDS.iter().find(|spec| spec.name == name) {
+            return Ok(spec);
+        }
+
+        match suggest(name) {
+            Some(suggestion) => Err(user_error!(
+                ErrorKind::UnknownCommand(name.to_string()),
+                t!(["errors", "unknown_command_with_suggestion"])
+                    .with_vars(vec![("command", name.to_string()), ("suggestion", suggestion.to_string())])
+            )),
+            None => Err(user_error!(
+                ErrorKind::UnknownCommand(name.to_string()),
+                t!(["errors", "unknown_command"]).with_vars(vec![("command", name.to_string())])
+            )),
+        }
+    }
+
+    /// Get the argument at the given position, an empty string is returned for omitted optional
+    /// arguments.
+    pub fn arg(&self, index: usize) -> &str {
+        self.args.get(index).map(|arg| arg.as_str()).unwrap_or_default()
+    }
+
+    /// Check if a flag was provided
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+impl CommandSpec {
+    /// The usage of the command, for example `connect <rocketchaturl> [token] [rocketchat_server_id]`
+    pub fn usage(&self) -> String {
+        let mut usage = self.name.to_string();
+        for arg in self.required_args {
+            usage.push_str(&format!(" <{}>", arg));
+        }
+        for arg in self.optional_args {
+            usage.push_str(&format!(" [{}]", arg));
+        }
+        for flag in self.flags {
+            usage.push_str(&format!(" [--{}]", flag));
+        }
+
+        usage
+    }
+
+    /// The help message for the command, the description is taken from the translations.
+    pub fn help(&self) -> I18n {
+        let description = t!(["commands", self.name]).l(DEFAULT_LANGUAGE);
+        t!(["admin_room", "command_help"]).with_vars(vec![("usage", self.usage()), ("description", description)])
+    }
+}
+
+/// Check if a message is a command that contains credentials. Only the name of the command is
+/// checked, so that messages that cannot be parsed are detected as well. The name is compared
+/// case-insensitively, because mobile clients often capitalize the first word of a message.
+pub fn contains_credentials(message: &str) -> bool {
+    message
+        .split_whitespace()
+        .next()
+        .map_or(false, |name| COMMANDS_WITH_CREDENTIALS.iter().any(|command| command.eq_ignore_ascii_case(name)))
+}
+
+fn tokenize(message: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut chars = message.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let token = current.get_or_insert_with(|| Token { value: String::new(), quoted: false });
+                token.quoted = true;
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\\') if c == '"' => {
+                            if let Some(escaped) = chars.next() {
+                                token.value.push(escaped);
+                            }
+                        }
+                        Some(other) => token.value.push(other),
+                        None => {
+                            bail_error!(
+                                ErrorKind::UnterminatedQuote,
+                                t!(["errors", "unterminated_quote"]).with_vars(vec![("quote", c.to_string())])
+                            );
+                        }
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if let Some(token) = current.take() {
+                    tokens.push(token);
+                }
+            }
+            c => current.get_or_insert_with(|| Token { value: String::new(), quoted: false }).value.push(c),
+        }
+    }
+
+    if let Some(token) = current {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+// A command is suggested when the unknown command starts with its name (e.g. `connectfoo`) or
+// when it only contains a few typos. The longest matching name wins (`login-tokens` suggests
+// `login-token` and not `login`).
+fn suggest(name: &str) -> Option<&'static str> {
+    COMMANDS
+        .iter()
+        .map(|spec| {
+            let distance = if name.starts_with(spec.name) { 0 } else { edit_distance(name, spec.name) };
+            (spec.name, distance)
+        })
+        .filter(|&(_, distance)| distance <= MAX_SUGGESTION_DISTANCE)
+        .min_by_key(|&(name, distance)| (distance, cmp::Reverse(name.len())))
+        .map(|(name, _)| name)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current_row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution_cost = if ca == *cb { 0 } else { 1 };
+            let distance = cmp::min(cmp::min(current_row[j] + 1, previous_row[j + 1] + 1), previous_row[j] + substitution_cost);
+            current_row.push(distance);
+        }
+        previous_row = current_row;
+    }
+
+    previous_row[b.len()]
+}
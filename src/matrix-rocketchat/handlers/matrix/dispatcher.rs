@@ -1,15 +1,18 @@
+use std::collections::HashMap;
+
 use diesel::sqlite::SqliteConnection;
 use ruma_events::collections::all::Event;
-use ruma_identifiers::RoomId;
+use ruma_events::EventType;
+use ruma_identifiers::{EventId, RoomId};
 use slog::Logger;
 
 use api::MatrixApi;
 use config::Config;
 use errors::*;
-use handlers::matrix::{MembershipHandler, MessageHandler};
+use handlers::matrix::{MembershipHandler, MessageHandler, RedactionHandler, RoomSetting, RoomStateHandler};
 use handlers::ErrorNotifier;
 use log;
-use models::Room;
+use models::{MessageContentExtensions, Room};
 
 /// Dispatches events to the corresponding handler.
 pub struct Dispatcher<'a> {
@@ -31,8 +34,9 @@ impl<'a> Dispatcher<'a> {
     }
 
     /// Processes the events that are passed to the method by forwarding them to the
-    /// corresponding handler.
-    pub fn process(&self, events: Vec<Box<Event>>) -> Result<()> {
+    /// corresponding handler. The message content extensions are passed along with the
+    /// message events they belong to.
+    pub fn process(&self, events: Vec<Box<Event>>, extensions: &HashMap<EventId, MessageContentExtensions>) -> Result<()> {
         for event in events {
             match *event {
                 Event::RoomMember(member_event) => {
@@ -45,10 +49,40 @@ impl<'a> Dispatcher<'a> {
                 }
                 Event::RoomMessage(message_event) => {
                     let handler = MessageHandler::new(self.config, self.connection, self.logger, self.matrix_api.clone());
-                    if let Err(err) = handler.process(&message_event) {
+                    let extension = extensions.get(&message_event.event_id).cloned().unwrap_or_default();
+                    if let Err(err) = handler.process(&message_event, &extension) {
                         return self.handle_error(&err, &message_event.room_id);
                     }
                 }
+                Event::CustomRoom(ref custom_event)
+                    if custom_event.event_type == EventType::Custom("m.reaction".to_string()) =>
+                {
+                    let handler = MessageHandler::new(self.config, self.connection, self.logger, self.matrix_api.clone());
+                    let extension = extensions.get(&custom_event.event_id).cloned().unwrap_or_default();
+                    if let Err(err) = handler.process_reaction(custom_event, &extension) {
+                        return self.handle_error(&err, &custom_event.room_id);
+                    }
+                }
+                Event::RoomRedaction(redaction_event) => {
+                    let handler = RedactionHandler::new(self.config, self.connection, self.logger, self.matrix_api.as_ref());
+                    if let Err(err) = handler.process(&redaction_event) {
+                        return self.handle_error(&err, &redaction_event.room_id);
+                    }
+                }
+                Event::RoomName(name_event) => {
+                    let handler = RoomStateHandler::new(self.config, self.connection, self.logger, self.matrix_api.as_ref());
+                    let (room_id, user_id) = (&name_event.room_id, &name_event.user_id);
+                    if let Err(err) = handler.process(room_id, user_id, RoomSetting::Name, &name_event.content.name) {
+                        return self.handle_error(&err, room_id);
+                    }
+                }
+                Event::RoomTopic(topic_event) => {
+                    let handler = RoomStateHandler::new(self.config, self.connection, self.logger, self.matrix_api.as_ref());
+                    let (room_id, user_id) = (&topic_event.room_id, &topic_event.user_id);
+                    if let Err(err) = handler.process(room_id, user_id, RoomSetting::Topic, &topic_event.content.topic) {
+                        return self.handle_error(&err, room_id);
+                    }
+                }
                 _ => debug!(self.logger, "Skipping event, because the event type is not known"),
             }
         }
@@ -1,16 +1,49 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
 use diesel::sqlite::SqliteConnection;
+use regex::{Captures, Regex};
 use ruma_events::room::message::{MessageEvent, MessageEventContent};
+use ruma_events::CustomRoomEvent;
+use ruma_identifiers::{EventId, UserId};
 use slog::Logger;
+use url::percent_encoding::percent_decode;
 use url::Url;
 
+use api::rocketchat::{Quote, Reply};
 use api::{MatrixApi, RocketchatApi};
+use config::Config;
+use emoji;
 use errors::*;
 use http::header::HeaderValue;
 use i18n::*;
-use models::{RocketchatServer, UserOnRocketchatServer};
+use markdown;
+use models::{
+    BridgedMessage, BridgedReaction, MessageContentExtensions, NewBridgedMessage, NewBridgedReaction, RocketchatServer,
+    UserOnRocketchatServer, VirtualUser,
+};
+
+const OPENSTREETMAP_URL: &str = "https://www.openstreetmap.org";
+
+lazy_static! {
+    static ref MATRIX_PILL: Regex = Regex::new(r"\[([^\]]*)\]\(https://matrix\.to/#/([^)\s]+)\)").unwrap();
+    static ref MATRIX_USER_ID: Regex = Regex::new(r"@[a-zA-Z0-9._=/+-]+:[a-zA-Z0-9.-]+(?::[0-9]+)?").unwrap();
+    static ref MATRIX_REPLY_FALLBACK: Regex = Regex::new(r"(?s)<mx-reply>.*?</mx-reply>").unwrap();
+}
+
+/// The fallback that is part of the body of a Matrix reply
+struct ReplyFallback<'b> {
+    /// The Matrix user ID of the sender of the message that was replied to
+    sender: &'b str,
+    /// The text of the message that was replied to
+    quoted_text: String,
+    /// The body of the reply without the fallback
+    body: &'b str,
+}
 
 /// Forwards messages
 pub struct Forwarder<'a> {
+    config: &'a Config,
     connection: &'a SqliteConnection,
     logger: &'a Logger,
     matrix_api: &'a MatrixApi,
@@ -18,12 +51,23 @@ pub struct Forwarder<'a> {
 
 impl<'a> Forwarder<'a> {
     /// Create a new `Forwarder`.
-    pub fn new(connection: &'a SqliteConnection, logger: &'a Logger, matrix_api: &'a MatrixApi) -> Forwarder<'a> {
-        Forwarder { connection, logger, matrix_api }
+    pub fn new(
+        config: &'a Config,
+        connection: &'a SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+    ) -> Forwarder<'a> {
+        Forwarder { config, connection, logger, matrix_api }
     }
 
     /// Forwards messages to Rocket.Chat
-    pub fn process(&self, event: &MessageEvent, server: RocketchatServer, channel_id: &str) -> Result<()> {
+    pub fn process(
+        &self,
+        event: &MessageEvent,
+        extension: &MessageContentExtensions,
+        server: RocketchatServer,
+        channel_id: &str,
+    ) -> Result<()> {
         let mut user_on_rocketchat_server =
             match UserOnRocketchatServer::find_by_matrix_user_id(self.connection, &event.user_id, server.id)? {
                 Some(user_on_rocketchat_server) => user_on_rocketchat_server,
@@ -38,34 +82,295 @@ impl<'a> Forwarder<'a> {
             user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
         );
 
-        match event.content {
+        if let Some(replaced_event_id) = extension.replaced_event_id() {
+            let new_content = extension.new_content.as_ref().map(|new_content| match new_content.html_body() {
+                Some(html_body) => markdown::html_to_rocketchat(html_body),
+                None => new_content.body.clone(),
+            });
+            let text = new_content.unwrap_or_else(|| event_body(&event.content).trim_left_matches("* ").to_string());
+            let text = self.replace_mentions(rocketchat_api.as_ref(), &server.id, &text)?;
+            match BridgedMessage::find_by_matrix_event_id(self.connection, replaced_event_id)? {
+                Some(ref message) if message.rocketchat_server_id == server.id => {
+                    rocketchat_api.chat_update(&message.rocketchat_message_id, &message.rocketchat_room_id, &text)?;
+                }
+                _ => {
+                    debug!(self.logger, "Edited message {} not found, posting the new content instead", replaced_event_id);
+                    let message_id = rocketchat_api.chat_post_message(&text, channel_id, None)?;
+                    self.save_message_id(event, message_id, &server.id, channel_id, None)?;
+                }
+            }
+
+            return user_on_rocketchat_server.set_last_message_sent(self.connection);
+        }
+
+        let is_reply = extension.in_reply_to_event_id().is_some();
+        let reply = self.reply(rocketchat_api.as_ref(), event, extension, &server.id, channel_id)?;
+        let message_id = match event.content {
             MessageEventContent::Text(ref content) => {
-                rocketchat_api.chat_post_message(&content.body, channel_id)?;
+                let text = match extension.html_body() {
+                    Some(html_body) => markdown::html_to_rocketchat(&MATRIX_REPLY_FALLBACK.replace_all(html_body, "")),
+                    None => strip_reply_fallback(&content.body, is_reply).to_string(),
+                };
+                let text = self.replace_mentions(rocketchat_api.as_ref(), &server.id, &text)?;
+                rocketchat_api.chat_post_message(&text, channel_id, reply.as_ref())?
             }
             MessageEventContent::Image(ref content) => {
                 let mimetype = content.clone().info.chain_err(|| ErrorKind::MissingMimeType)?.mimetype;
                 self.forward_file_to_rocketchat(rocketchat_api.as_ref(), &content.url, mimetype, &content.body, channel_id)?;
+                None
             }
             MessageEventContent::File(ref content) => {
                 let mimetype = content.clone().info.chain_err(|| ErrorKind::MissingMimeType)?.mimetype;
                 self.forward_file_to_rocketchat(rocketchat_api.as_ref(), &content.url, mimetype, &content.body, channel_id)?;
+                None
             }
             MessageEventContent::Audio(ref content) => {
                 let mimetype = content.clone().info.chain_err(|| ErrorKind::MissingMimeType)?.mimetype;
                 self.forward_file_to_rocketchat(rocketchat_api.as_ref(), &content.url, mimetype, &content.body, channel_id)?;
+                None
             }
             MessageEventContent::Video(ref content) => {
                 let mimetype = content.clone().info.chain_err(|| ErrorKind::MissingMimeType)?.mimetype;
                 self.forward_file_to_rocketchat(rocketchat_api.as_ref(), &content.url, mimetype, &content.body, channel_id)?;
+                None
             }
-            MessageEventContent::Emote(_) | MessageEventContent::Location(_) | MessageEventContent::Notice(_) => {
-                info!(self.logger, "Not forwarding message, forwarding emote, location or notice messages is not implemented.")
+            MessageEventContent::Emote(ref content) => {
+                let display_name = self.matrix_api.get_display_name(event.user_id.clone())?.filter(|name| !name.is_empty());
+                let name = display_name.unwrap_or_else(|| event.user_id.localpart().to_string());
+                let text = format!("_{} {}_", name, strip_reply_fallback(&content.body, is_reply));
+                rocketchat_api.chat_post_message(&text, channel_id, reply.as_ref())?
             }
-        }
+            MessageEventContent::Notice(ref content) => {
+                let body = strip_reply_fallback(&content.body, is_reply);
+                let text = if self.config.flag_notices_as_bot_messages {
+                    let prefix = t!(["defaults", "bot_message_prefix"]).l(DEFAULT_LANGUAGE);
+                    format!("{} {}", prefix, body)
+                } else {
+                    body.to_string()
+                };
+                rocketchat_api.chat_post_message(&text, channel_id, reply.as_ref())?
+            }
+            MessageEventContent::Location(ref content) => {
+                let text = match build_map_link(&content.geo_uri) {
+                    Some(map_link) => format!("{}: {}", content.body, map_link),
+                    None => {
+                        debug!(self.logger, "Could not parse geo URI `{}`, forwarding it as is", content.geo_uri);
+                        format!("{}: {}", content.body, content.geo_uri)
+                    }
+                };
+                rocketchat_api.chat_post_message(&text, channel_id, reply.as_ref())?
+            }
+        };
 
+        let thread_id = match reply {
+            Some(Reply::Thread(ref thread_id)) => Some(thread_id.as_str()),
+            _ => None,
+        };
+        self.save_message_id(event, message_id, &server.id, channel_id, thread_id)?;
         user_on_rocketchat_server.set_last_message_sent(self.connection)
     }
 
+    /// Forwards a reaction to the Rocket.Chat message that was bridged for the annotated event
+    pub fn forward_reaction(
+        &self,
+        event: &CustomRoomEvent,
+        extension: &MessageContentExtensions,
+        server: RocketchatServer,
+    ) -> Result<()> {
+        let (annotated_event_id, key) = match extension.annotation() {
+            Some(annotation) => annotation,
+            None => {
+                debug!(self.logger, "Skipping reaction {}, because it does not annotate an event", event.event_id);
+                return Ok(());
+            }
+        };
+
+        let user_on_rocketchat_server =
+            match UserOnRocketchatServer::find_by_matrix_user_id(self.connection, &event.user_id, server.id.clone())? {
+                Some(user_on_rocketchat_server) => user_on_rocketchat_server,
+                None => {
+                    debug!(self.logger, "Skipping reaction, because it was sent by a virtual user");
+                    return Ok(());
+                }
+            };
+
+        let message = match BridgedMessage::find_by_matrix_event_id(self.connection, annotated_event_id)? {
+            Some(message) if message.rocketchat_server_id == server.id => message,
+            _ => {
+                debug!(self.logger, "Skipping reaction, because the event {} was not bridged", annotated_event_id);
+                return Ok(());
+            }
+        };
+
+        let emoji = match emoji::shortcode(key) {
+            Some(emoji) => emoji,
+            None => {
+                debug!(self.logger, "Skipping reaction, because there is no Rocket.Chat emoji for `{}`", key);
+                return Ok(());
+            }
+        };
+
+        let rocketchat_user_id = user_on_rocketchat_server.rocketchat_user_id.clone().unwrap_or_default();
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url, self.logger.clone())?.with_credentials(
+            rocketchat_user_id.clone(),
+            user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+        rocketchat_api.chat_react(&message.rocketchat_message_id, emoji, true)?;
+
+        let new_bridged_reaction = NewBridgedReaction {
+            matrix_event_id: &event.event_id,
+            matrix_room_id: &event.room_id,
+            rocketchat_message_id: &message.rocketchat_message_id,
+            rocketchat_server_id: &server.id,
+            rocketchat_user_id: &rocketchat_user_id,
+            emoji,
+        };
+        BridgedReaction::insert(self.connection, &new_bridged_reaction)?;
+
+        Ok(())
+    }
+
+    // Matrix threads and replies to messages that are part of a Rocket.Chat thread are posted in
+    // the Rocket.Chat thread, other replies quote the message they reply to.
+    fn reply(
+        &self,
+        rocketchat_api: &RocketchatApi,
+        event: &MessageEvent,
+        extension: &MessageContentExtensions,
+        server_id: &str,
+        channel_id: &str,
+    ) -> Result<Option<Reply>> {
+        if let Some(root_event_id) = extension.thread_root_event_id() {
+            return match self.find_bridged_message(root_event_id, server_id, channel_id)? {
+                Some(root) => {
+                    let thread_id = root.thread_id(self.connection)?.unwrap_or(root.rocketchat_message_id);
+                    Ok(Some(Reply::Thread(thread_id)))
+                }
+                None => {
+                    debug!(self.logger, "Posting message without thread, because the root {} was not bridged", root_event_id);
+                    Ok(None)
+                }
+            };
+        }
+
+        let in_reply_to_event_id = match extension.in_reply_to_event_id() {
+            Some(in_reply_to_event_id) => in_reply_to_event_id,
+            None => return Ok(None),
+        };
+
+        if let Some(message) = self.find_bridged_message(in_reply_to_event_id, server_id, channel_id)? {
+            if let Some(thread_id) = message.thread_id(self.connection)? {
+                return Ok(Some(Reply::Thread(thread_id)));
+            }
+        }
+
+        let fallback = match reply_fallback(event_body(&event.content)) {
+            Some(fallback) => fallback,
+            None => {
+                debug!(self.logger, "Posting reply {} without quote, because it doesn't contain a fallback", event.event_id);
+                return Ok(None);
+            }
+        };
+
+        let author_name = match UserId::try_from(fallback.sender) {
+            Ok(user_id) => self.rocketchat_username(rocketchat_api, server_id, &user_id)?,
+            Err(_) => None,
+        };
+        let author_name = author_name.unwrap_or_else(|| fallback.sender.to_string());
+        Ok(Some(Reply::Quote(Quote { author_name, text: fallback.quoted_text })))
+    }
+
+    fn find_bridged_message(&self, event_id: &EventId, server_id: &str, channel_id: &str) -> Result<Option<BridgedMessage>> {
+        let message = BridgedMessage::find_by_matrix_event_id(self.connection, event_id)?;
+        Ok(message.filter(|message| message.rocketchat_server_id == server_id && message.rocketchat_room_id == channel_id))
+    }
+
+    // Matrix pills and Matrix user IDs are replaced by the Rocket.Chat username of the user, so
+    // that the mention is highlighted on Rocket.Chat.
+    fn replace_mentions(&self, rocketchat_api: &RocketchatApi, server_id: &str, text: &str) -> Result<String> {
+        let pill_ids = MATRIX_PILL.captures_iter(text).map(|captures| decode_matrix_id(&captures[2]));
+        let user_ids = MATRIX_USER_ID.find_iter(text).map(|user_id| user_id.as_str().to_string());
+
+        let mut usernames = HashMap::new();
+        for matrix_id in pill_ids.chain(user_ids) {
+            if usernames.contains_key(&matrix_id) {
+                continue;
+            }
+
+            let username = match UserId::try_from(matrix_id.as_ref()) {
+                Ok(user_id) => self.rocketchat_username(rocketchat_api, server_id, &user_id)?,
+                Err(_) => None,
+            };
+            usernames.insert(matrix_id, username);
+        }
+
+        let mention = |matrix_id: &str| {
+            usernames.get(matrix_id).and_then(|username| username.as_ref()).map(|username| format!("@{}", username))
+        };
+        let text = MATRIX_PILL.replace_all(text, |captures: &Captures| {
+            mention(&decode_matrix_id(&captures[2])).unwrap_or_else(|| captures[0].to_string())
+        });
+        let text = MATRIX_USER_ID
+            .replace_all(&text, |captures: &Captures| mention(&captures[0]).unwrap_or_else(|| captures[0].to_string()));
+
+        Ok(text.to_string())
+    }
+
+    fn rocketchat_username(&self, rocketchat_api: &RocketchatApi, server_id: &str, user_id: &UserId) -> Result<Option<String>> {
+        let rocketchat_user_id = if self.config.is_application_service_virtual_user(user_id) {
+            let (user_server_id, rocketchat_user_id) = VirtualUser::rocketchat_server_and_user_id_from_matrix_id(user_id);
+            if user_server_id != server_id {
+                return Ok(None);
+            }
+            rocketchat_user_id
+        } else {
+            match UserOnRocketchatServer::find_by_matrix_user_id(self.connection, user_id, server_id.to_string())? {
+                Some(UserOnRocketchatServer { rocketchat_user_id: Some(rocketchat_user_id), .. }) => rocketchat_user_id,
+                _ => return Ok(None),
+            }
+        };
+
+        match rocketchat_api.users_info_by_id(&rocketchat_user_id) {
+            Ok(user) => Ok(Some(user.username)),
+            Err(err) => {
+                debug!(
+                    self.logger,
+                    "Could not find Rocket.Chat user {} for mention of {}: {}", rocketchat_user_id, user_id, err
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    fn save_message_id(
+        &self,
+        event: &MessageEvent,
+        message_id: Option<String>,
+        server_id: &str,
+        channel_id: &str,
+        thread_id: Option<&str>,
+    ) -> Result<()> {
+        let message_id = match message_id {
+            Some(message_id) => message_id,
+            None => {
+                debug!(self.logger, "Rocket.Chat did not return a message ID for event {}", event.event_id);
+                return Ok(());
+            }
+        };
+
+        let new_bridged_message = NewBridgedMessage {
+            matrix_event_id: &event.event_id,
+            matrix_room_id: &event.room_id,
+            rocketchat_message_id: &message_id,
+            rocketchat_room_id: channel_id,
+            rocketchat_server_id: server_id,
+            rocketchat_thread_id: thread_id,
+        };
+        BridgedMessage::insert(self.connection, &new_bridged_message)?;
+
+        Ok(())
+    }
+
     fn forward_file_to_rocketchat(
         &self,
         rocketchat_api: &RocketchatApi,
@@ -92,3 +397,72 @@ impl<'a> Forwarder<'a> {
         Ok(())
     }
 }
+
+fn decode_matrix_id(matrix_id: &str) -> String {
+    percent_decode(matrix_id.as_bytes()).decode_utf8_lossy().to_string()
+}
+
+// Replies start with the quoted message (`> <@user:example.com> text`) followed by an empty
+// line, so that clients that don't support replies can display the context.
+fn reply_fallback(body: &str) -> Option<ReplyFallback> {
+    if !body.starts_with('>') {
+        return None;
+    }
+
+    let fallback_end = body.find("\n\n")?;
+    let mut quoted_lines = Vec::new();
+    for line in body[..fallback_end].lines() {
+        if !line.starts_with('>') {
+            return None;
+        }
+        quoted_lines.push(line[1..].trim_left());
+    }
+
+    // the first line starts with the sender of the message, emotes are prefixed with an asterisk
+    let first_line = quoted_lines.first().cloned()?.trim_left_matches("* ");
+    if !first_line.starts_with('<') {
+        return None;
+    }
+    let sender_end = first_line.find('>')?;
+    let sender = &first_line[1..sender_end];
+    quoted_lines[0] = first_line[sender_end + 1..].trim_left();
+
+    Some(ReplyFallback { sender, quoted_text: quoted_lines.join("\n"), body: &body[fallback_end + 2..] })
+}
+
+fn strip_reply_fallback(body: &str, is_reply: bool) -> &str {
+    if !is_reply {
+        return body;
+    }
+
+    reply_fallback(body).map(|fallback| fallback.body).unwrap_or(body)
+}
+
+fn event_body(content: &MessageEventContent) -> &str {
+    match *content {
+        MessageEventContent::Audio(ref content) => &content.body,
+        MessageEventContent::Emote(ref content) => &content.body,
+        MessageEventContent::File(ref content) => &content.body,
+        MessageEventContent::Image(ref content) => &content.body,
+        MessageEventContent::Location(ref content) => &content.body,
+        MessageEventContent::Notice(ref content) => &content.body,
+        MessageEventContent::Text(ref content) => &content.body,
+        MessageEventContent::Video(ref content) => &content.body,
+    }
+}
+
+/// Build a link to a map based on a geo URI as defined in RFC 5870 (e.g. `geo:46.948,7.447;u=35`).
+/// Returns `None` if the URI does not contain a valid latitude and longitude.
+fn build_map_link(geo_uri: &str) -> Option<String> {
+    let coordinates = geo_uri.trim_left_matches("geo:").split(';').next().unwrap_or_default();
+    let mut coordinates = coordinates.split(',');
+    let latitude: f64 = coordinates.next()?.trim().parse().ok()?;
+    let longitude: f64 = coordinates.next()?.trim().parse().ok()?;
+
+    let is_finite = latitude.is_finite() && longitude.is_finite();
+    if !geo_uri.starts_with("geo:") || !is_finite || latitude.abs() > 90.0 || longitude.abs() > 180.0 {
+        return None;
+    }
+
+    Some(format!("{}/?mlat={}&mlon={}#map=16/{}/{}", OPENSTREETMAP_URL, latitude, longitude, latitude, longitude))
+}
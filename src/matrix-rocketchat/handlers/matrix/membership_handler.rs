@@ -6,14 +6,17 @@ use ruma_events::room::member::{MemberEvent, MembershipState};
 use ruma_identifiers::UserId;
 use slog::Logger;
 
-use api::MatrixApi;
+use api::matrix::DEFAULT_REDACT_POWER_LEVEL;
+use api::{MatrixApi, RocketchatApi};
 use config::Config;
 use errors::*;
 use handlers::matrix::CommandHandler;
 use handlers::ErrorNotifier;
 use i18n::*;
 use log;
-use models::Room;
+use models::{
+    AdminRoom, DirectMessageMember, DirectMessageReceiver, RocketchatServer, Room, UserOnRocketchatServer, VirtualUser,
+};
 
 /// Handles membership events for a specific room
 pub struct MembershipHandler<'a> {
@@ -49,6 +52,11 @@ impl<'a> MembershipHandler<'a> {
 
                 self.handle_bot_invite(matrix_bot_user_id)?;
             }
+            MembershipState::Invite if self.config.is_application_service_virtual_user(&state_key) => {
+                debug!(self.logger, "Virtual user `{}` got invite for room `{}`", state_key, self.room.id);
+
+                self.handle_virtual_user_invite(&event.user_id, state_key)?;
+            }
             MembershipState::Join if addressed_to_matrix_bot => {
                 debug!(self.logger, "Received join event for bot user {} and room {}", matrix_bot_user_id, self.room.id);
 
@@ -62,7 +70,12 @@ impl<'a> MembershipHandler<'a> {
             MembershipState::Leave if !addressed_to_matrix_bot => {
                 debug!(self.logger, "User {} left room {}", event.user_id, event.room_id);
 
-                self.handle_user_leave()?;
+                self.handle_user_leave(&state_key)?;
+            }
+            MembershipState::Ban if !addressed_to_matrix_bot => {
+                debug!(self.logger, "User {} was banned from room {}", &state_key, event.room_id);
+
+                self.handle_user_ban(&state_key)?;
             }
             _ => {
                 let msg = format!(
@@ -92,8 +105,71 @@ impl<'a> MembershipHandler<'a> {
         Ok(())
     }
 
+    // A Matrix user starts a direct message by inviting the virtual user of a Rocket.Chat user
+    // into a room. Virtual users that are invited by the application service itself are added to
+    // bridged rooms, those invites are handled when the room is bridged.
+    fn handle_virtual_user_invite(&self, inviter_id: &UserId, virtual_user_id: UserId) -> Result<()> {
+        if self.config.is_application_service_user(inviter_id) {
+            debug!(self.logger, "Virtual user {} was invited by the application service, skipping", virtual_user_id);
+            return Ok(());
+        }
+
+        if !self.config.accept_remote_invites && self.is_remote_invite()? {
+            info!(
+                self.logger,
+                "Virtual user {} was invited by a user from another homeserver ({}). \
+                 Ignoring the invite because remote invites are disabled.",
+                virtual_user_id,
+                &self.room.id
+            );
+            return Ok(());
+        }
+
+        let (server_id, rocketchat_user_id) = VirtualUser::rocketchat_server_and_user_id_from_matrix_id(&virtual_user_id);
+        let server = match RocketchatServer::find_by_id(self.conn, &server_id)? {
+            Some(server) => server,
+            None => {
+                debug!(self.logger, "No Rocket.Chat server with ID {} found, ignoring invite", server_id);
+                return Ok(());
+            }
+        };
+
+        let inviter = UserOnRocketchatServer::find_by_matrix_user_id(self.conn, inviter_id, server.id.clone())?;
+        let inviter = match inviter.filter(|user_on_rocketchat_server| user_on_rocketchat_server.is_logged_in()) {
+            Some(inviter) => inviter,
+            None => {
+                info!(
+                    self.logger,
+                    "User {} is not logged in on {}, cannot start a direct message", inviter_id, server.rocketchat_url
+                );
+                return Ok(());
+            }
+        };
+
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            inviter.rocketchat_user_id.clone().unwrap_or_default(),
+            inviter.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+        let rocketchat_user = rocketchat_api.users_info_by_id(&rocketchat_user_id)?;
+        let channel = rocketchat_api.im_create(&rocketchat_user.username)?;
+
+        let members = rocketchat_api.im_members(&channel.id)?;
+        DirectMessageMember::replace_all(self.conn, &server.id, &channel.id, &members)?;
+
+        let virtual_user = VirtualUser::new(self.config, self.logger, self.matrix_api);
+        virtual_user.find_or_register(&server.id, &rocketchat_user_id, &rocketchat_user.username)?;
+
+        // the room has to be registered before the virtual user joins, otherwise the join is
+        // handled as if it happened in an admin room
+        self.room.register_dm(self.conn, &server.id, channel.id.clone(), virtual_user_id.clone(), &[inviter_id.clone()])?;
+        self.matrix_api.join(self.room.id.clone(), virtual_user_id)?;
+
+        info!(self.logger, "Direct message room {} for channel {} successfully started from Matrix", self.room.id, channel.id);
+        Ok(())
+    }
+
     fn handle_bot_join(&self, matrix_bot_user_id: UserId) -> Result<()> {
-        let is_admin_room = match self.room.is_admin_room() {
+        let is_admin_room = match self.room.is_admin_room(self.conn) {
             Ok(is_admin_room) => is_admin_room,
             Err(err) => {
                 warn!(
@@ -111,7 +187,7 @@ impl<'a> MembershipHandler<'a> {
         }
 
         // leave direct message room, the bot only joined it to be able to read the room members
-        if self.room.is_direct_message_room()? {
+        if self.room.is_direct_message_room(self.conn)? {
             self.matrix_api.leave_room(self.room.id.clone(), matrix_bot_user_id)?;
         }
 
@@ -130,7 +206,7 @@ impl<'a> MembershipHandler<'a> {
 
         match CommandHandler::build_help_message(self.conn, self.room, self.config.as_url.clone(), &room_creator_id) {
             Ok(body) => {
-                self.matrix_api.send_text_message(self.room.id.clone(), matrix_bot_user_id, body)?;
+                self.matrix_api.send_text_message(self.room.id.clone(), matrix_bot_user_id.clone(), body)?;
             }
             Err(err) => {
                 log::log_info(self.logger, &err);
@@ -138,31 +214,73 @@ impl<'a> MembershipHandler<'a> {
         }
 
         let room_name = t!(["defaults", "admin_room_display_name"]).l(DEFAULT_LANGUAGE);
-        if let Err(err) = self.matrix_api.set_room_name(self.room.id.clone(), room_name) {
+        if let Err(err) = self.matrix_api.set_room_name(self.room.id.clone(), matrix_bot_user_id.clone(), room_name) {
             log::log_info(self.logger, &err);
         }
 
+        self.check_redact_power_level(matrix_bot_user_id)
+    }
+
+    // The bot redacts messages that contain credentials, the user is asked to grant the permission
+    // if the bot cannot do that.
+    fn check_redact_power_level(&self, matrix_bot_user_id: UserId) -> Result<()> {
+        let power_levels = match self.matrix_api.get_room_power_levels(self.room.id.clone()) {
+            Ok(power_levels) => power_levels,
+            Err(err) => {
+                log::log_info(self.logger, &err);
+                return Ok(());
+            }
+        };
+
+        let required_power_level = match power_levels {
+            Some(ref power_levels) if power_levels.can_redact(&matrix_bot_user_id) => return Ok(()),
+            Some(ref power_levels) => power_levels.redact,
+            None => DEFAULT_REDACT_POWER_LEVEL,
+        };
+
+        info!(self.logger, "Bot user cannot redact messages in admin room {}", self.room.id);
+        let body = t!(["admin_room", "redact_power_level_missing"])
+            .with_vars(vec![("power_level", required_power_level.to_string())])
+            .l(DEFAULT_LANGUAGE);
+        self.matrix_api.send_text_message(self.room.id.clone(), matrix_bot_user_id, body)?;
         Ok(())
     }
 
     fn handle_user_join(&self) -> Result<()> {
-        if self.room.is_admin_room()? && !self.is_private_room()? {
+        if self.room.is_admin_room(self.conn)? && !self.is_private_room()? {
             info!(self.logger, "Another user join the admin room {}, bot user is leaving", self.room.id);
             let bot_user_id = self.config.matrix_bot_user_id()?;
             let body = t!(["errors", "other_user_joined"]).l(DEFAULT_LANGUAGE);
             self.matrix_api.send_text_message(self.room.id.clone(), bot_user_id.clone(), body)?;
+            self.delete_admin_room()?;
             self.room.forget(bot_user_id)?;
         }
         Ok(())
     }
 
-    fn handle_user_leave(&self) -> Result<()> {
-        if self.room.is_admin_room()? {
+    fn handle_user_leave(&self, user_id: &UserId) -> Result<()> {
+        if self.room.is_admin_room(self.conn)? {
+            self.delete_admin_room()?;
             let bot_user_id = self.config.matrix_bot_user_id()?;
             return self.room.forget(bot_user_id);
         }
 
-        Ok(())
+        // the user does not receive direct messages in this room anymore, the room is looked up
+        // again with the next direct message
+        DirectMessageReceiver::delete_by_matrix_room_id(self.conn, &self.room.id, user_id)
+    }
+
+    fn handle_user_ban(&self, user_id: &UserId) -> Result<()> {
+        // a banned user cannot read the room anymore, so the direct messages have to be
+        // delivered to another room
+        DirectMessageReceiver::delete_by_matrix_room_id(self.conn, &self.room.id, user_id)
+    }
+
+    fn delete_admin_room(&self) -> Result<()> {
+        match AdminRoom::find_by_matrix_room_id(self.conn, &self.room.id)? {
+            Some(admin_room) => admin_room.delete(self.conn),
+            None => Ok(()),
+        }
     }
 
     fn is_remote_invite(&self) -> Result<bool> {
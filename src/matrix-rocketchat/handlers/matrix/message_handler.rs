@@ -1,12 +1,13 @@
 use diesel::sqlite::SqliteConnection;
 use ruma_events::room::message::MessageEvent;
+use ruma_events::CustomRoomEvent;
 use slog::Logger;
 
 use api::MatrixApi;
 use config::Config;
 use errors::*;
 use handlers::matrix::{CommandHandler, Forwarder};
-use models::{RocketchatServer, Room};
+use models::{MessageContentExtensions, RocketchatServer, Room};
 
 /// Handles message events
 pub struct MessageHandler<'a> {
@@ -28,7 +29,7 @@ impl<'a> MessageHandler<'a> {
     }
 
     /// Handles messages that are sent in a room
-    pub fn process(&self, event: &MessageEvent) -> Result<()> {
+    pub fn process(&self, event: &MessageEvent, extension: &MessageContentExtensions) -> Result<()> {
         if event.user_id == self.config.matrix_bot_user_id()? {
             debug!(self.logger, "Skipping event, because it was sent by the bot user");
             return Ok(());
@@ -36,10 +37,11 @@ impl<'a> MessageHandler<'a> {
 
         let matrix_api = self.matrix_api.as_ref();
         let room = Room::new(self.config, self.logger, self.matrix_api.as_ref(), event.room_id.clone());
-        if room.is_admin_room()? {
+        if room.is_admin_room(self.connection)? {
             CommandHandler::new(self.config, self.connection, self.logger, matrix_api, &room).process(event)?;
         } else if let Some((server, channel_id)) = self.get_rocketchat_server_with_room(&room)? {
-            Forwarder::new(self.connection, self.logger, matrix_api).process(event, server, &channel_id)?;
+            let forwarder = Forwarder::new(self.config, self.connection, self.logger, matrix_api);
+            forwarder.process(event, extension, server, &channel_id)?;
         } else {
             debug!(self.logger, "Skipping event, because the room {} is not bridged", &event.room_id);
         }
@@ -47,9 +49,28 @@ impl<'a> MessageHandler<'a> {
         Ok(())
     }
 
+    /// Handles reactions to messages that are sent in a room
+    pub fn process_reaction(&self, event: &CustomRoomEvent, extension: &MessageContentExtensions) -> Result<()> {
+        if event.user_id == self.config.matrix_bot_user_id()? {
+            debug!(self.logger, "Skipping reaction, because it was sent by the bot user");
+            return Ok(());
+        }
+
+        let matrix_api = self.matrix_api.as_ref();
+        let room = Room::new(self.config, self.logger, self.matrix_api.as_ref(), event.room_id.clone());
+        if let Some((server, _)) = self.get_rocketchat_server_with_room(&room)? {
+            let forwarder = Forwarder::new(self.config, self.connection, self.logger, matrix_api);
+            forwarder.forward_reaction(event, extension, server)?;
+        } else {
+            debug!(self.logger, "Skipping reaction, because the room {} is not bridged", &event.room_id);
+        }
+
+        Ok(())
+    }
+
     fn get_rocketchat_server_with_room(&self, room: &Room) -> Result<Option<(RocketchatServer, String)>> {
         // if it's a normal room, this will match
-        if let Some(channel_id) = room.rocketchat_channel_id()? {
+        if let Some(channel_id) = room.rocketchat_channel_id(self.connection)? {
             if let Some(server) = room.rocketchat_server(self.connection)? {
                 return Ok(Some((server, channel_id)));
             }
@@ -2,6 +2,8 @@
 
 /// Handles commands from the admin room
 mod command_handler;
+/// Parses commands from the admin room
+mod command_parser;
 /// Dispatches incomming events to the correct component
 mod dispatcher;
 /// Forwards messages to Rocket.Chat
@@ -10,9 +12,18 @@ mod forwarder;
 mod membership_handler;
 /// Handles message events
 mod message_handler;
+/// Handles user and room alias queries from the homeserver
+mod query_handler;
+/// Handles redactions of forwarded messages
+mod redaction_handler;
+/// Handles name and topic changes in bridged rooms
+mod room_state_handler;
 
 pub use self::command_handler::CommandHandler;
 pub use self::dispatcher::Dispatcher;
 pub use self::forwarder::Forwarder;
 pub use self::membership_handler::MembershipHandler;
 pub use self::message_handler::MessageHandler;
+pub use self::query_handler::QueryHandler;
+pub use self::redaction_handler::RedactionHandler;
+pub use self::room_state_handler::{RoomSetting, RoomStateHandler};
@@ -0,0 +1,161 @@
+use diesel::sqlite::SqliteConnection;
+use ruma_events::room::join_rules::JoinRule;
+use ruma_identifiers::{RoomAliasId, UserId};
+use slog::Logger;
+
+use api::{MatrixApi, RocketchatApi};
+use config::Config;
+use errors::*;
+use models::{RocketchatRoom, RocketchatServer, VirtualUser, CHANNEL_KIND};
+
+/// Handles user and room alias queries from the homeserver
+pub struct QueryHandler<'a> {
+    config: &'a Config,
+    connection: &'a SqliteConnection,
+    logger: &'a Logger,
+    matrix_api: &'a MatrixApi,
+}
+
+impl<'a> QueryHandler<'a> {
+    /// Create a new `QueryHandler`.
+    pub fn new(
+        config: &'a Config,
+        connection: &'a SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+    ) -> QueryHandler<'a> {
+        QueryHandler { config, connection, logger, matrix_api }
+    }
+
+    /// Registers the virtual user for a Rocket.Chat user on demand. Returns `false` if the user ID
+    /// does not belong to a user on a connected Rocket.Chat server.
+    pub fn query_user(&self, user_id: &UserId) -> Result<bool> {
+        if !self.config.is_application_service_virtual_user(user_id) {
+            debug!(self.logger, "User {} is not a virtual user of the application service", user_id);
+            return Ok(false);
+        }
+
+        let (server_id, rocketchat_user_id) = VirtualUser::rocketchat_server_and_user_id_from_matrix_id(user_id);
+        let server = match RocketchatServer::find_by_id(self.connection, &server_id)? {
+            Some(server) => server,
+            None => {
+                debug!(self.logger, "Rocket.Chat server {} of user {} not found", server_id, user_id);
+                return Ok(false);
+            }
+        };
+
+        let user_on_rocketchat_server = match server.logged_in_users_on_rocketchat_server(self.connection)?.into_iter().next() {
+            Some(user_on_rocketchat_server) => user_on_rocketchat_server,
+            None => {
+                debug!(self.logger, "No user is logged in on {}, cannot look up user {}", server.rocketchat_url, user_id);
+                return Ok(false);
+            }
+        };
+
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            user_on_rocketchat_server.rocketchat_user_id.clone().unwrap_or_default(),
+            user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+        let rocketchat_user = match rocketchat_api.users_info_by_id(&rocketchat_user_id) {
+            Ok(rocketchat_user) => rocketchat_user,
+            Err(err) => {
+                debug!(self.logger, "Rocket.Chat user {} not found on {}: {}", rocketchat_user_id, server.rocketchat_url, err);
+                return Ok(false);
+            }
+        };
+
+        let virtual_user = VirtualUser::new(self.config, self.logger, self.matrix_api);
+        virtual_user.find_or_register(&server.id, &rocketchat_user.id, &rocketchat_user.username)?;
+
+        info!(self.logger, "Virtual user {} registered on demand", user_id);
+        Ok(true)
+    }
+
+    /// Bridges a public Rocket.Chat channel when its room alias is queried, so that users can join
+    /// the channel by alias. Returns `false` if the alias does not belong to a channel that can be
+    /// bridged.
+    pub fn query_room_alias(&self, room_alias_id: &RoomAliasId) -> Result<bool> {
+        let alias_parts: Vec<&str> = room_alias_id.alias().splitn(3, '#').collect();
+        if alias_parts.len() != 3 || alias_parts[0] != self.config.sender_localpart {
+            debug!(self.logger, "Room alias {} is not in the namespace of the application service", room_alias_id);
+            return Ok(false);
+        }
+        let server_id = alias_parts[1];
+        let channel_id = alias_parts[2];
+
+        let server = match RocketchatServer::find_by_id(self.connection, server_id)? {
+            Some(server) => server,
+            None => {
+                debug!(self.logger, "Rocket.Chat server {} of room alias {} not found", server_id, room_alias_id);
+                return Ok(false);
+            }
+        };
+
+        let rocketchat_room =
+            RocketchatRoom::new(self.config, self.logger, self.matrix_api, channel_id.to_string(), &server.id);
+        // the homeserver only queries the alias if it does not know it, so the alias of an already
+        // bridged room was removed and is added again
+        if let Some(room_id) = rocketchat_room.matrix_id(self.connection)? {
+            self.matrix_api.put_room_alias(room_id.clone(), room_alias_id.clone())?;
+            self.matrix_api.put_canonical_room_alias(room_id.clone(), Some(room_alias_id.clone()))?;
+            info!(self.logger, "Added the alias {} to the already bridged room {} again", room_alias_id, room_id);
+            return Ok(true);
+        }
+
+        let logged_in_users = server.logged_in_users_on_rocketchat_server(self.connection)?;
+        let user_on_rocketchat_server = match logged_in_users.first() {
+            Some(user_on_rocketchat_server) => user_on_rocketchat_server,
+            None => {
+                debug!(self.logger, "No user is logged in on {}, cannot bridge channel {}", server.rocketchat_url, channel_id);
+                return Ok(false);
+            }
+        };
+
+        // only public channels are bridged on demand, the content of private groups must not be
+        // exposed to users that are not members of the group
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            user_on_rocketchat_server.rocketchat_user_id.clone().unwrap_or_default(),
+            user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+        let channel = match rocketchat_api.channels_info(channel_id) {
+            Ok(channel) => channel,
+            Err(err) => {
+                debug!(self.logger, "Channel {} not found on {}: {}", channel_id, server.rocketchat_url, err);
+                return Ok(false);
+            }
+        };
+
+        let users = rocketchat_api.channels_members(&channel.id)?;
+        let member_ids: Vec<Option<&String>> = users.iter().map(|user| Some(&user.id)).collect();
+        let invited_user = match logged_in_users
+            .iter()
+            .find(|logged_in_user| member_ids.contains(&logged_in_user.rocketchat_user_id.as_ref()))
+        {
+            Some(invited_user) => invited_user,
+            None => {
+                debug!(self.logger, "None of the logged in users is a member of the channel {}", channel_id);
+                return Ok(false);
+            }
+        };
+
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            invited_user.rocketchat_user_id.clone().unwrap_or_default(),
+            invited_user.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+        let usernames: Vec<String> = users.into_iter().map(|user| user.username).collect();
+        let room_id = rocketchat_room.bridge(
+            self.connection,
+            rocketchat_api.as_ref(),
+            &channel.name,
+            CHANNEL_KIND,
+            &usernames,
+            &invited_user.matrix_user_id,
+        )?;
+
+        let bot_user_id = self.config.matrix_bot_user_id()?;
+        self.matrix_api.set_room_join_rule(room_id.clone(), bot_user_id, JoinRule::Public)?;
+
+        info!(self.logger, "Channel {} bridged on demand to room {}", channel_id, room_id);
+        Ok(true)
+    }
+}
@@ -0,0 +1,129 @@
+use diesel::sqlite::SqliteConnection;
+use ruma_events::room::redaction::RedactionEvent;
+use slog::Logger;
+
+use api::{MatrixApi, RocketchatApi};
+use config::Config;
+use errors::*;
+use handlers::ErrorNotifier;
+use i18n::*;
+use models::{BridgedMessage, BridgedReaction, RocketchatServer, Room, UserOnRocketchatServer};
+
+/// Handles redaction events
+pub struct RedactionHandler<'a> {
+    config: &'a Config,
+    connection: &'a SqliteConnection,
+    logger: &'a Logger,
+    matrix_api: &'a MatrixApi,
+}
+
+impl<'a> RedactionHandler<'a> {
+    /// Create a new `RedactionHandler`.
+    pub fn new(
+        config: &'a Config,
+        connection: &'a SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+    ) -> RedactionHandler<'a> {
+        RedactionHandler { config, connection, logger, matrix_api }
+    }
+
+    /// Deletes the Rocket.Chat message or removes the Rocket.Chat reaction that was created for
+    /// the redacted event.
+    pub fn process(&self, event: &RedactionEvent) -> Result<()> {
+        if self.config.is_application_service_user(&event.user_id) {
+            debug!(self.logger, "Skipping redaction, because it was sent by the application service");
+            return Ok(());
+        }
+
+        // the redacted event has to belong to the room in which the redaction was sent, otherwise
+        // a redaction in one room could delete messages of another bridged room
+        let reaction = BridgedReaction::find_by_matrix_event_id(self.connection, &event.redacts)?;
+        if let Some(reaction) = reaction.filter(|reaction| reaction.matrix_room_id == event.room_id) {
+            return self.remove_reaction(event, reaction);
+        }
+
+        let message = BridgedMessage::find_by_matrix_event_id(self.connection, &event.redacts)?;
+        let message = match message.filter(|message| message.matrix_room_id == event.room_id) {
+            Some(message) => message,
+            None => {
+                debug!(self.logger, "Skipping redaction, because event {} was not forwarded to Rocket.Chat", event.redacts);
+                return Ok(());
+            }
+        };
+
+        let server = match RocketchatServer::find_by_id(self.connection, &message.rocketchat_server_id)? {
+            Some(server) => server,
+            None => {
+                debug!(self.logger, "Skipping redaction, Rocket.Chat server {} not found", message.rocketchat_server_id);
+                return Ok(());
+            }
+        };
+
+        let user_on_rocketchat_server =
+            match UserOnRocketchatServer::find_by_matrix_user_id(self.connection, &event.user_id, server.id.clone())? {
+                Some(user_on_rocketchat_server) => user_on_rocketchat_server,
+                None => {
+                    debug!(self.logger, "Skipping redaction, user {} is not logged in on {}", event.user_id, server.id);
+                    return Ok(());
+                }
+            };
+
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            user_on_rocketchat_server.rocketchat_user_id.unwrap_or_default(),
+            user_on_rocketchat_server.rocketchat_auth_token.unwrap_or_default(),
+        );
+
+        if let Err(err) = rocketchat_api.chat_delete(&message.rocketchat_message_id, &message.rocketchat_room_id) {
+            let err = user_error!(
+                ErrorKind::RocketchatDeleteMessageFailed(message.rocketchat_message_id.clone(), err.to_string()),
+                t!(["errors", "rocketchat_delete_message_failed"])
+                    .with_vars(vec![("room_id", event.room_id.to_string()), ("err", err.to_string())])
+            );
+            return self.notify_user(err, event, &server);
+        }
+
+        message.delete(self.connection)
+    }
+
+    fn remove_reaction(&self, event: &RedactionEvent, reaction: BridgedReaction) -> Result<()> {
+        let server = match RocketchatServer::find_by_id(self.connection, &reaction.rocketchat_server_id)? {
+            Some(server) => server,
+            None => {
+                debug!(self.logger, "Skipping redaction, Rocket.Chat server {} not found", reaction.rocketchat_server_id);
+                return Ok(());
+            }
+        };
+
+        let user_on_rocketchat_server =
+            match UserOnRocketchatServer::find_by_matrix_user_id(self.connection, &event.user_id, server.id.clone())? {
+                Some(user_on_rocketchat_server) => user_on_rocketchat_server,
+                None => {
+                    debug!(self.logger, "Skipping redaction, user {} is not logged in on {}", event.user_id, server.id);
+                    return Ok(());
+                }
+            };
+
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            user_on_rocketchat_server.rocketchat_user_id.unwrap_or_default(),
+            user_on_rocketchat_server.rocketchat_auth_token.unwrap_or_default(),
+        );
+        rocketchat_api.chat_react(&reaction.rocketchat_message_id, &reaction.emoji, false)?;
+
+        reaction.delete(self.connection)
+    }
+
+    fn notify_user(&self, err: Error, event: &RedactionEvent, server: &RocketchatServer) -> Result<()> {
+        let admin_room =
+            match Room::get_admin_room(self.config, self.connection, self.logger, self.matrix_api, &event.user_id, server)? {
+                Some(admin_room) => admin_room,
+                None => {
+                    debug!(self.logger, "No admin room found for user {}, reporting the error in the room", event.user_id);
+                    return Err(err);
+                }
+            };
+
+        let error_notifier = ErrorNotifier { config: self.config, logger: self.logger, matrix_api: self.matrix_api };
+        error_notifier.send_message_to_user(&err, admin_room.id)
+    }
+}
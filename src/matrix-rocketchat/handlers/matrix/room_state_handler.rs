@@ -0,0 +1,134 @@
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::{RoomId, UserId};
+use slog::Logger;
+
+use api::{MatrixApi, RocketchatApi};
+use config::Config;
+use errors::*;
+use handlers::ErrorNotifier;
+use i18n::*;
+use models::{BridgedRoom, RocketchatServer, Room, UserOnRocketchatServer, CHANNEL_KIND, GROUP_KIND};
+
+/// Room settings that are kept in sync between Matrix and Rocket.Chat
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoomSetting {
+    /// The name of the room
+    Name,
+    /// The topic of the room
+    Topic,
+}
+
+/// Handles name and topic changes in bridged rooms
+pub struct RoomStateHandler<'a> {
+    config: &'a Config,
+    connection: &'a SqliteConnection,
+    logger: &'a Logger,
+    matrix_api: &'a MatrixApi,
+}
+
+impl<'a> RoomStateHandler<'a> {
+    /// Create a new `RoomStateHandler`.
+    pub fn new(
+        config: &'a Config,
+        connection: &'a SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+    ) -> RoomStateHandler<'a> {
+        RoomStateHandler { config, connection, logger, matrix_api }
+    }
+
+    /// Changes the name or topic of the Rocket.Chat channel or group that is bridged to the room
+    /// with the credentials of the user that changed the setting on Matrix.
+    pub fn process(&self, room_id: &RoomId, user_id: &UserId, setting: RoomSetting, value: &str) -> Result<()> {
+        if self.config.is_application_service_user(user_id) {
+            debug!(self.logger, "Skipping room state change, because it was sent by the application service");
+            return Ok(());
+        }
+
+        let bridged_room = match BridgedRoom::find_by_matrix_room_id(self.connection, room_id)? {
+            Some(bridged_room) => bridged_room,
+            None => {
+                debug!(self.logger, "Skipping room state change, because the room {} is not bridged", room_id);
+                return Ok(());
+            }
+        };
+        let server = match RocketchatServer::find_by_id(self.connection, &bridged_room.rocketchat_server_id)? {
+            Some(server) => server,
+            None => {
+                debug!(self.logger, "Skipping room state change, the server of room {} does not exist", room_id);
+                return Ok(());
+            }
+        };
+
+        let channel_id = bridged_room.rocketchat_channel_id.clone();
+        let mut user_on_rocketchat_server =
+            match UserOnRocketchatServer::find_by_matrix_user_id(self.connection, user_id, server.id.clone())? {
+                Some(user_on_rocketchat_server) => user_on_rocketchat_server,
+                None => {
+                    debug!(self.logger, "Skipping room state change, user {} is not logged in on {}", user_id, server.id);
+                    return Ok(());
+                }
+            };
+
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            user_on_rocketchat_server.rocketchat_user_id.clone().unwrap_or_default(),
+            user_on_rocketchat_server.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+
+        if let Err(err) = self.update_rocketchat_room(rocketchat_api.as_ref(), &bridged_room, setting, value) {
+            let setting_name = match setting {
+                RoomSetting::Name => "name",
+                RoomSetting::Topic => "topic",
+            };
+            let err = user_error!(
+                ErrorKind::RocketchatUpdateRoomFailed(channel_id.clone(), err.to_string()),
+                t!(["errors", "rocketchat_update_room_failed"]).with_vars(vec![
+                    ("setting", setting_name.to_string()),
+                    ("room_id", room_id.to_string()),
+                    ("err", err.to_string())
+                ])
+            );
+            return self.notify_user(err, user_id, &server);
+        }
+
+        // Rocket.Chat sends the change back as a system message, which must not be forwarded again
+        user_on_rocketchat_server.set_last_message_sent(self.connection)
+    }
+
+    // Rocket.Chat uses different endpoints for public channels and private groups. Rooms that were
+    // bridged before the kind was stored are looked up in the groups of the user.
+    fn update_rocketchat_room(
+        &self,
+        rocketchat_api: &RocketchatApi,
+        bridged_room: &BridgedRoom,
+        setting: RoomSetting,
+        value: &str,
+    ) -> Result<()> {
+        let channel_id = bridged_room.rocketchat_channel_id.as_str();
+        let is_group = match bridged_room.kind.as_ref().map(|kind| kind.as_str()) {
+            Some(GROUP_KIND) => true,
+            Some(CHANNEL_KIND) => false,
+            _ => rocketchat_api.groups_list()?.iter().any(|group| group.id == channel_id),
+        };
+        match (setting, is_group) {
+            (RoomSetting::Name, false) => rocketchat_api.channels_rename(channel_id, value),
+            (RoomSetting::Name, true) => rocketchat_api.groups_rename(channel_id, value),
+            (RoomSetting::Topic, false) => rocketchat_api.channels_set_topic(channel_id, value),
+            (RoomSetting::Topic, true) => rocketchat_api.groups_set_topic(channel_id, value),
+        }
+    }
+
+    fn notify_user(&self, err: Error, user_id: &UserId, server: &RocketchatServer) -> Result<()> {
+        let admin_room =
+            match Room::get_admin_room(self.config, self.connection, self.logger, self.matrix_api, user_id, server)? {
+                Some(admin_room) => admin_room,
+                None => {
+                    debug!(self.logger, "No admin room found for user {}, reporting the error in the room", user_id);
+                    return Err(err);
+                }
+            };
+
+        let error_notifier = ErrorNotifier { config: self.config, logger: self.logger, matrix_api: self.matrix_api };
+        error_notifier.send_message_to_user(&err, admin_room.id)
+    }
+}
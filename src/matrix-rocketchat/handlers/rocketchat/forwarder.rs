@@ -1,22 +1,40 @@
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use diesel::sqlite::SqliteConnection;
+use regex::Regex;
 use ruma_events::room::message::MessageType;
-use ruma_identifiers::UserId;
+use ruma_identifiers::{EventId, UserId};
 use slog::Logger;
 
-use api::rocketchat::WebhookMessage;
+use api::matrix::MessageRelation;
+use api::rocketchat::{User, WebhookMessage, WebhookReaction};
 use api::{MatrixApi, RocketchatApi};
 use config::Config;
+use emoji;
 use errors::*;
 use http::header::HeaderValue;
-use i18n::*;
 use log;
-use models::{RocketchatRoom, RocketchatServer, Room, UserOnRocketchatServer, VirtualUser};
+use markdown;
+use models::{
+    BridgedMessage, BridgedReaction, DirectMessageMember, NewBridgedMessage, NewBridgedReaction, NewNonDirectMessageChannel,
+    NonDirectMessageChannel, RocketchatRoom, RocketchatServer, Room, UserOnRocketchatServer, VirtualUser,
+};
 
-const IMAGE_MESSAGE_TEXT: &str = "Uploaded an image";
-const FILE_MESSAGE_TEXT: &str = "Uploaded a file";
 const RESEND_THRESHOLD_IN_SECONDS: i64 = 3;
+const DIRECT_MESSAGE_MEMBERS_REFRESH_INTERVAL_IN_SECONDS: i64 = 600;
+const DIRECT_MESSAGE_LOOKUP_CANDIDATES_LIMIT: usize = 5;
+const NON_DIRECT_MESSAGE_CHANNEL_RECHECK_INTERVAL_IN_SECONDS: i64 = 3600;
+const MATRIX_TO_URL: &str = "https://matrix.to";
+const ROOM_MENTION: &str = "@room";
+const ROOM_RENAMED: &str = "r";
+const ROOM_TOPIC_CHANGED: &str = "room_changed_topic";
+const ROOM_ANNOUNCEMENT_CHANGED: &str = "room_changed_announcement";
+
+lazy_static! {
+    static ref ROCKETCHAT_MENTION: Regex = Regex::new(r"(^|[^0-9a-zA-Z_.@-])@([0-9a-zA-Z_.-]*[0-9a-zA-Z_-])").unwrap();
+    static ref ROCKETCHAT_QUOTE: Regex = Regex::new(r"^\[ \]\([^)\s]*\?msg=([^)&\s]+)\)\s*").unwrap();
+}
 
 /// Forwards messages from Rocket.Chat to Matrix
 pub struct Forwarder<'a> {
@@ -46,8 +64,30 @@ impl<'a> Forwarder<'a> {
 
     /// Send a message to the Matrix channel.
     pub fn send(&self, server: &RocketchatServer, message: &WebhookMessage) -> Result<()> {
-        let is_direct_message = message.channel_id.contains(&message.user_id);
-        if !is_direct_message && !self.is_sendable_message(message.user_id.clone(), server.id.clone())? {
+        // Rocket.Chat sends the message again when a reaction changes, the content of a message is
+        // only forwarded the first time it is received, the reactions are synced every time.
+        let bridged_message =
+            match BridgedMessage::find_by_rocketchat_message_id(self.connection, &server.id, &message.message_id)? {
+                Some(bridged_message) => {
+                    debug!(self.logger, "Message {} was already bridged, only syncing reactions", message.message_id);
+                    Some(bridged_message)
+                }
+                None => {
+                    self.forward_message(server, message)?;
+                    BridgedMessage::find_by_rocketchat_message_id(self.connection, &server.id, &message.message_id)?
+                }
+            };
+
+        if let (Some(reactions), Some(bridged_message)) = (message.reactions.as_ref(), bridged_message) {
+            self.forward_reactions(server, &bridged_message, reactions)?;
+        }
+
+        Ok(())
+    }
+
+    fn forward_message(&self, server: &RocketchatServer, message: &WebhookMessage) -> Result<()> {
+        let direct_message_members = self.direct_message_members(server, message)?;
+        if direct_message_members.is_none() && !self.is_sendable_message(message.user_id.clone(), server.id.clone())? {
             debug!(
                 self.logger,
                 "Skipping message, because the message was just posted by the user Matrix and echoed back from Rocket.Chat"
@@ -55,7 +95,11 @@ impl<'a> Forwarder<'a> {
             return Ok(());
         }
 
-        let room = match self.prepare_room(server, message)? {
+        let room = match direct_message_members {
+            Some(ref members) => self.prepare_dm_room(server, message, members)?,
+            None => self.prepare_room_for_channel(server, message)?,
+        };
+        let room = match room {
             Some(room) => room,
             None => {
                 debug!(
@@ -75,11 +119,363 @@ impl<'a> Forwarder<'a> {
             }
         }
 
-        if message.text == IMAGE_MESSAGE_TEXT || message.text == FILE_MESSAGE_TEXT {
+        if let Err(err) = self.sync_avatar(server, message, &sender_id) {
+            log::log_error(self.logger, &err)
+        }
+
+        if let Some(ref message_type) = message.message_type {
+            if [ROOM_RENAMED, ROOM_TOPIC_CHANGED, ROOM_ANNOUNCEMENT_CHANGED].contains(&message_type.as_str()) {
+                return self.update_room_state(&room, &sender_id, message_type, &message.text);
+            }
+        }
+
+        if message.is_file_upload() {
             self.forward_file(server, message, &room, &sender_id)
         } else {
-            self.matrix_api.send_text_message(room.id.clone(), sender_id, message.text.clone())
+            let (text, relation) = self.relation(server, message, &room)?;
+            let (body, text) = self.replace_mentions(server, &room, text)?;
+            let formatted_body = markdown::rocketchat_to_html(&text);
+            let event_id =
+                self.matrix_api.send_formatted_message(room.id.clone(), sender_id, body, formatted_body, relation)?;
+            self.save_event_id(server, message, &room, event_id)
+        }
+    }
+
+    // Rocket.Chat system messages contain the new value of the changed channel setting as text.
+    // Matrix rooms only have a topic, so the announcement replaces the topic as well. Virtual users
+    // usually don't have the power level to change the room state, the bot user steps in for them.
+    fn update_room_state(&self, room: &Room, sender_id: &UserId, message_type: &str, value: &str) -> Result<()> {
+        let current_value = match message_type {
+            ROOM_RENAMED => self.matrix_api.get_room_name(room.id.clone())?,
+            _ => self.matrix_api.get_room_topic(room.id.clone())?,
+        };
+        if current_value.as_ref().map(|current_value| current_value.as_str()) == Some(value) {
+            debug!(self.logger, "Skipping room state update for room {}, the value did not change", room.id);
+            return Ok(());
+        }
+
+        let update = |user_id: UserId| match message_type {
+            ROOM_RENAMED => self.matrix_api.set_room_name(room.id.clone(), user_id, value.to_string()),
+            _ => self.matrix_api.set_room_topic(room.id.clone(), user_id, value.to_string()),
+        };
+
+        if let Err(err) = update(sender_id.clone()) {
+            debug!(self.logger, "Could not update room {} as {}, will update it as bot user: {}", room.id, sender_id, err);
+            update(self.config.matrix_bot_user_id()?)?;
+        }
+
+        Ok(())
+    }
+
+    // Messages that were posted in a Rocket.Chat thread are sent as part of the Matrix thread,
+    // quoted messages are sent as Matrix replies. The link to the quoted message is removed from
+    // the returned text.
+    fn relation<'m>(
+        &self,
+        server: &RocketchatServer,
+        message: &'m WebhookMessage,
+        room: &Room,
+    ) -> Result<(&'m str, Option<MessageRelation>)> {
+        if let Some(ref thread_id) = message.tmid {
+            match BridgedMessage::find_by_rocketchat_message_id(self.connection, &server.id, thread_id)? {
+                Some(ref root) if root.matrix_room_id == room.id => {
+                    let latest_message = BridgedMessage::find_latest_in_thread(self.connection, &server.id, thread_id)?;
+                    let in_reply_to = latest_message.map(|latest_message| latest_message.matrix_event_id);
+                    let relation = MessageRelation::Thread {
+                        root: root.matrix_event_id.clone(),
+                        in_reply_to: in_reply_to.unwrap_or_else(|| root.matrix_event_id.clone()),
+                    };
+                    return Ok((&message.text, Some(relation)));
+                }
+                _ => debug!(self.logger, "Sending message without thread, because the root {} was not bridged", thread_id),
+            }
+        }
+
+        if let Some(captures) = ROCKETCHAT_QUOTE.captures(&message.text) {
+            let quote = captures.get(0).expect("The whole match is always present");
+            match BridgedMessage::find_by_rocketchat_message_id(self.connection, &server.id, &captures[1])? {
+                Some(ref quoted_message) if quoted_message.matrix_room_id == room.id => {
+                    let relation = MessageRelation::Reply(quoted_message.matrix_event_id.clone());
+                    return Ok((&message.text[quote.end()..], Some(relation)));
+                }
+                _ => debug!(self.logger, "Sending quote as text, because the message {} was not bridged", &captures[1]),
+            }
+        }
+
+        Ok((&message.text, None))
+    }
+
+    // Rocket.Chat mentions are replaced by the display name of the mentioned user in the body and
+    // by a pill in the formatted body, so that the mention is highlighted on Matrix. The returned
+    // text still uses the Rocket.Chat markdown dialect.
+    fn replace_mentions(&self, server: &RocketchatServer, room: &Room, text: &str) -> Result<(String, String)> {
+        if !ROCKETCHAT_MENTION.is_match(text) {
+            return Ok((text.to_string(), text.to_string()));
+        }
+
+        let rocketchat_api = self.rocketchat_api_for_room(server, room)?;
+        let code_ranges = markdown::rocketchat_code_ranges(text);
+
+        let mut body = String::with_capacity(text.len());
+        let mut formatted_text = String::with_capacity(text.len());
+        let mut position = 0;
+        for captures in ROCKETCHAT_MENTION.captures_iter(text) {
+            let username = captures.get(2).expect("The username is part of every mention");
+            // the mention starts with the @ right before the username
+            let mention_start = username.start() - 1;
+            // mentions in code are displayed as they are
+            if code_ranges.iter().any(|range| range.start <= mention_start && mention_start < range.end) {
+                continue;
+            }
+            body.push_str(&text[position..mention_start]);
+            formatted_text.push_str(&text[position..mention_start]);
+            position = username.end();
+
+            let mentioned_user = match (username.as_str(), &rocketchat_api) {
+                ("all", _) | ("here", _) => {
+                    body.push_str(ROOM_MENTION);
+                    formatted_text.push_str(ROOM_MENTION);
+                    continue;
+                }
+                (username, &Some(ref rocketchat_api)) => self.mentioned_user(rocketchat_api.as_ref(), server, username)?,
+                (_, &None) => None,
+            };
+
+            match mentioned_user {
+                Some((user_id, display_name)) => {
+                    body.push_str(&display_name);
+                    let link_text = markdown::escape_markdown(&display_name);
+                    formatted_text.push_str(&format!("[{}]({}/#/{})", link_text, MATRIX_TO_URL, user_id));
+                }
+                None => {
+                    body.push_str(&text[mention_start..position]);
+                    formatted_text.push_str(&text[mention_start..position]);
+                }
+            }
+        }
+        body.push_str(&text[position..]);
+        formatted_text.push_str(&text[position..]);
+
+        Ok((body, formatted_text))
+    }
+
+    fn mentioned_user(
+        &self,
+        rocketchat_api: &RocketchatApi,
+        server: &RocketchatServer,
+        username: &str,
+    ) -> Result<Option<(UserId, String)>> {
+        let rocketchat_user = match rocketchat_api.users_info(username) {
+            Ok(rocketchat_user) => rocketchat_user,
+            Err(err) => {
+                debug!(self.logger, "Not replacing mention of {}, the user was not found: {}", username, err);
+                return Ok(None);
+            }
+        };
+
+        let user_id = match UserOnRocketchatServer::find_by_rocketchat_user_id(
+            self.connection,
+            server.id.clone(),
+            rocketchat_user.id.clone(),
+        )? {
+            Some(user_on_rocketchat_server) => user_on_rocketchat_server.matrix_user_id,
+            None => self.virtual_user.build_user_id(&rocketchat_user.id, &server.id)?,
+        };
+
+        let display_name = self.matrix_api.get_display_name(user_id.clone())?.filter(|name| !name.is_empty());
+        Ok(Some((user_id, display_name.unwrap_or_else(|| username.to_string()))))
+    }
+
+    // Avatars are publicly accessible on Rocket.Chat, so they are downloaded without credentials.
+    fn sync_avatar(&self, server: &RocketchatServer, message: &WebhookMessage, sender_id: &UserId) -> Result<()> {
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?;
+        self.virtual_user.sync_avatar(self.connection, rocketchat_api.as_ref(), &server.id, sender_id, &message.user_name)
+    }
+
+    fn rocketchat_api_for_room(&self, server: &RocketchatServer, room: &Room) -> Result<Option<Box<RocketchatApi>>> {
+        let users = room.logged_in_users(self.connection, server.id.clone())?;
+        let user = match users.first() {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+            user.rocketchat_user_id.clone().unwrap_or_default(),
+            user.rocketchat_auth_token.clone().unwrap_or_default(),
+        );
+        Ok(Some(rocketchat_api))
+    }
+
+    fn save_event_id(
+        &self,
+        server: &RocketchatServer,
+        message: &WebhookMessage,
+        room: &Room,
+        event_id: Option<EventId>,
+    ) -> Result<()> {
+        let event_id = match event_id {
+            Some(event_id) => event_id,
+            None => {
+                debug!(self.logger, "The homeserver did not return an event ID for message {}", message.message_id);
+                return Ok(());
+            }
+        };
+
+        if BridgedMessage::find_by_rocketchat_message_id(self.connection, &server.id, &message.message_id)?.is_some() {
+            debug!(self.logger, "Message {} was already bridged", message.message_id);
+            return Ok(());
+        }
+
+        let new_bridged_message = NewBridgedMessage {
+            matrix_event_id: &event_id,
+            matrix_room_id: &room.id,
+            rocketchat_message_id: &message.message_id,
+            rocketchat_room_id: &message.channel_id,
+            rocketchat_server_id: &server.id,
+            rocketchat_thread_id: message.tmid.as_ref().map(|tmid| tmid.as_str()),
+        };
+        BridgedMessage::insert(self.connection, &new_bridged_message)?;
+
+        Ok(())
+    }
+
+    fn forward_reactions(
+        &self,
+        server: &RocketchatServer,
+        bridged_message: &BridgedMessage,
+        reactions: &HashMap<String, WebhookReaction>,
+    ) -> Result<()> {
+        debug!(self.logger, "Forwarding reactions to message {}", bridged_message.rocketchat_message_id);
+
+        let room = Room::new(self.config, self.logger, self.matrix_api, bridged_message.matrix_room_id.clone());
+        let rocketchat_api = match self.rocketchat_api_for_room(server, &room)? {
+            Some(rocketchat_api) => rocketchat_api,
+            None => {
+                warn!(self.logger, "No logged in user in bridged room {} found, cannot forward reactions", room.id);
+                return Ok(());
+            }
+        };
+
+        let bridged_reactions = BridgedReaction::find_by_rocketchat_message_id(
+            self.connection,
+            &server.id,
+            &bridged_message.rocketchat_message_id,
+        )?;
+
+        let mut rocketchat_user_ids = HashMap::new();
+        let mut has_unknown_users = false;
+        for username in reactions.values().flat_map(|reaction| reaction.usernames.iter()) {
+            if rocketchat_user_ids.contains_key(username) {
+                continue;
+            }
+
+            match rocketchat_api.users_info(username) {
+                Ok(rocketchat_user) => {
+                    rocketchat_user_ids.insert(username, rocketchat_user.id);
+                }
+                Err(err) => {
+                    debug!(self.logger, "Skipping reactions of {}, the user was not found: {}", username, err);
+                    has_unknown_users = true;
+                }
+            }
+        }
+
+        let mut current_reactions = Vec::new();
+        for (emoji, reaction) in reactions {
+            for username in &reaction.usernames {
+                let rocketchat_user_id = match rocketchat_user_ids.get(username) {
+                    Some(rocketchat_user_id) => rocketchat_user_id,
+                    None => continue,
+                };
+                let is_bridged = bridged_reactions
+                    .iter()
+                    .any(|bridged| &bridged.rocketchat_user_id == rocketchat_user_id && &bridged.emoji == emoji);
+                if !is_bridged {
+                    self.add_reaction(server, bridged_message, &room, rocketchat_user_id, username, emoji)?;
+                }
+                current_reactions.push((rocketchat_user_id.clone(), emoji.clone()));
+            }
+        }
+
+        // the reactions of users that could not be looked up are unknown, removing reactions could
+        // remove the ones of those users
+        if has_unknown_users {
+            debug!(self.logger, "Not removing reactions from message {}", bridged_message.rocketchat_message_id);
+            return Ok(());
+        }
+
+        for bridged_reaction in bridged_reactions {
+            let is_current = current_reactions.iter().any(|&(ref user_id, ref emoji)| {
+                user_id == &bridged_reaction.rocketchat_user_id && emoji == &bridged_reaction.emoji
+            });
+            if !is_current {
+                self.remove_reaction(server, bridged_reaction)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_reaction(
+        &self,
+        server: &RocketchatServer,
+        bridged_message: &BridgedMessage,
+        room: &Room,
+        rocketchat_user_id: &str,
+        rocketchat_user_name: &str,
+        emoji: &str,
+    ) -> Result<()> {
+        let sender_id = self.virtual_user.find_or_register(&server.id, rocketchat_user_id, rocketchat_user_name)?;
+        room.join_user(sender_id.clone(), self.config.matrix_bot_user_id()?)?;
+
+        let key = emoji::unicode(emoji).unwrap_or(emoji);
+        let event_id = self.matrix_api.send_reaction(
+            room.id.clone(),
+            sender_id,
+            bridged_message.matrix_event_id.clone(),
+            key.to_string(),
+        )?;
+
+        let event_id = match event_id {
+            Some(event_id) => event_id,
+            None => {
+                debug!(self.logger, "The homeserver did not return an event ID for reaction {}", emoji);
+                return Ok(());
+            }
+        };
+
+        let new_bridged_reaction = NewBridgedReaction {
+            matrix_event_id: &event_id,
+            matrix_room_id: &room.id,
+            rocketchat_message_id: &bridged_message.rocketchat_message_id,
+            rocketchat_server_id: &server.id,
+            rocketchat_user_id,
+            emoji,
+        };
+        BridgedReaction::insert(self.connection, &new_bridged_reaction)?;
+
+        Ok(())
+    }
+
+    fn remove_reaction(&self, server: &RocketchatServer, bridged_reaction: BridgedReaction) -> Result<()> {
+        let rocketchat_user_id = bridged_reaction.rocketchat_user_id.clone();
+        if UserOnRocketchatServer::find_by_rocketchat_user_id(self.connection, server.id.clone(), rocketchat_user_id)?.is_some()
+        {
+            debug!(
+                self.logger,
+                "Not removing reaction {}, because it was sent by a Matrix user", bridged_reaction.matrix_event_id
+            );
+            return Ok(());
         }
+
+        let sender_id = self.virtual_user.build_user_id(&bridged_reaction.rocketchat_user_id, &server.id)?;
+        self.matrix_api.redact_event(
+            bridged_reaction.matrix_room_id.clone(),
+            bridged_reaction.matrix_event_id.clone(),
+            sender_id,
+        )?;
+
+        bridged_reaction.delete(self.connection)
     }
 
     fn is_sendable_message(&self, rocketchat_user_id: String, server_id: String) -> Result<bool> {
@@ -95,29 +491,22 @@ impl<'a> Forwarder<'a> {
         }
     }
 
-    fn prepare_room(&self, server: &RocketchatServer, message: &WebhookMessage) -> Result<Option<Room>> {
-        let is_direct_message_room = message.channel_id.contains(&message.user_id);
-        if is_direct_message_room {
-            self.prepare_dm_room(server, message)
-        } else {
-            self.prepare_room_for_channel(server, message)
+    fn prepare_dm_room(&self, server: &RocketchatServer, message: &WebhookMessage, members: &[User]) -> Result<Option<Room>> {
+        let receivers = self.logged_in_direct_message_members(server, members)?;
+        if members.len() > 2 {
+            let receiver_ids: Vec<UserId> = receivers.into_iter().map(|receiver| receiver.matrix_user_id).collect();
+            return self.prepare_group_dm_room(server, message, members, &receiver_ids);
         }
-    }
 
-    fn prepare_dm_room(&self, server: &RocketchatServer, message: &WebhookMessage) -> Result<Option<Room>> {
-        let receiver = match self.find_matching_user_for_direct_message(server, message)? {
+        let receiver = receivers.into_iter().find(|receiver| receiver.rocketchat_user_id.as_ref() != Some(&message.user_id));
+        let receiver = match receiver {
             Some(receiver) => receiver,
             None => {
-                debug!(self.logger, "Ignoring message, because not matching user for the direct chat message was found");
+                debug!(self.logger, "Not forwarding direct message, because no participant except the sender uses Matrix");
                 return Ok(None);
             }
         };
 
-        if receiver.rocketchat_user_id.clone().unwrap_or_default() == message.user_id {
-            debug!(self.logger, "Not forwarding direct message, because the sender is the receivers virtual user");
-            return Ok(None);
-        }
-
         let room = match self.try_to_find_or_create_direct_message_room(server, &receiver, message)? {
             Some(room) => room,
             None => return Ok(None),
@@ -136,8 +525,10 @@ impl<'a> Forwarder<'a> {
 
         if let Some(room) = Room::get_dm(
             self.config,
+            self.connection,
             self.logger,
             self.matrix_api,
+            &server.id,
             message.channel_id.clone(),
             &sender_id,
             &receiver.matrix_user_id,
@@ -149,10 +540,174 @@ impl<'a> Forwarder<'a> {
         self.auto_bridge_direct_message_channel(server, receiver, message)
     }
 
+    // Direct messages with more than two participants are forwarded to a single room that contains
+    // all participants that are logged in via Matrix and the virtual users of all participants,
+    // the same way it's done for bridged channels.
+    fn prepare_group_dm_room(
+        &self,
+        server: &RocketchatServer,
+        message: &WebhookMessage,
+        members: &[User],
+        receiver_ids: &[UserId],
+    ) -> Result<Option<Room>> {
+        if !self.is_sendable_message(message.user_id.clone(), server.id.clone())? {
+            debug!(
+                self.logger,
+                "Skipping direct message, because the message was just posted by the user Matrix and echoed back from Rocket.Chat"
+            );
+            return Ok(None);
+        }
+
+        let existing_room =
+            Room::get_group_dm(self.config, self.connection, self.logger, self.matrix_api, &server.id, &message.channel_id)?;
+        let room = match existing_room {
+            Some(room) => room,
+            None => {
+                let creator_id = self.virtual_user.find_or_register(&server.id, &message.user_id, &message.user_name)?;
+                let usernames: Vec<String> = members.iter().map(|member| member.username.clone()).collect();
+                Room::create_group_dm(
+                    self.config,
+                    self.connection,
+                    self.logger,
+                    self.matrix_api,
+                    &server.id,
+                    message.channel_id.clone(),
+                    &creator_id,
+                    &usernames,
+                    receiver_ids,
+                )?
+            }
+        };
+
+        room.sync_dm_members(self.connection, &server.id, members, receiver_ids)?;
+        Ok(Some(room))
+    }
+
+    // Returns the participants if the channel is a direct message and `None` for other channels,
+    // the webhook itself doesn't contain the type of the channel. The participants of a direct
+    // message channel are looked up once and stored, they are looked up again when a user that is
+    // not a known participant sends a message to the channel or when the stored participants are
+    // older than the refresh interval, so that participants that left the channel are removed from
+    // the Matrix room. The stored participants are used if the lookup fails. Channels that turn out
+    // not to be direct messages are stored as well and are not looked up again until the recheck
+    // interval passed or a user logged in.
+    fn direct_message_members(&self, server: &RocketchatServer, message: &WebhookMessage) -> Result<Option<Vec<User>>> {
+        let channel = RocketchatRoom::new(self.config, self.logger, self.matrix_api, message.channel_id.clone(), &server.id);
+        if channel.matrix_id(self.connection)?.is_some() {
+            return Ok(None);
+        }
+
+        let stored_members =
+            DirectMessageMember::find_by_rocketchat_channel_id(self.connection, &server.id, &message.channel_id)?;
+        let stored_users: Vec<User> = stored_members.iter().map(|member| member.rocketchat_user()).collect();
+        let is_known_participant = stored_users.iter().any(|user| user.id == message.user_id);
+        if is_known_participant
+            && !DirectMessageMember::are_outdated(
+                self.connection,
+                &server.id,
+                &message.channel_id,
+                DIRECT_MESSAGE_MEMBERS_REFRESH_INTERVAL_IN_SECONDS,
+            )?
+        {
+            return Ok(Some(stored_users));
+        }
+
+        if stored_users.is_empty()
+            && NonDirectMessageChannel::is_known(
+                self.connection,
+                &server.id,
+                &message.channel_id,
+                NON_DIRECT_MESSAGE_CHANNEL_RECHECK_INTERVAL_IN_SECONDS,
+            )?
+        {
+            return Ok(None);
+        }
+
+        for user in self.direct_message_lookup_candidates(server, message, &stored_users)? {
+            debug!(
+                self.logger,
+                "Looking up the participants of direct message channel {} as {}", message.channel_id, user.matrix_user_id
+            );
+            let rocketchat_api = RocketchatApi::new(server.rocketchat_url.clone(), self.logger.clone())?.with_credentials(
+                user.rocketchat_user_id.clone().unwrap_or_default(),
+                user.rocketchat_auth_token.clone().unwrap_or_default(),
+            );
+
+            match rocketchat_api.im_members(&message.channel_id) {
+                Ok(members) => {
+                    DirectMessageMember::replace_all(self.connection, &server.id, &message.channel_id, &members)?;
+                    return Ok(Some(members));
+                }
+                Err(err) => debug!(
+                    self.logger,
+                    "User {} could not look up the participants of channel {}: {}",
+                    user.matrix_user_id,
+                    message.channel_id,
+                    err
+                ),
+            }
+        }
+
+        if !stored_users.is_empty() {
+            return Ok(Some(stored_users));
+        }
+
+        debug!(self.logger, "Channel {} is not a direct message with a participant that uses Matrix", message.channel_id);
+        let new_non_direct_message_channel =
+            NewNonDirectMessageChannel { rocketchat_server_id: &server.id, rocketchat_channel_id: &message.channel_id };
+        NonDirectMessageChannel::insert(self.connection, &new_non_direct_message_channel)?;
+        Ok(None)
+    }
+
+    // Only participants can look up the members of a direct message channel. A sender that is
+    // logged in via Matrix participates in every channel the sender posts to, so the sender's
+    // credentials alone decide whether the channel is a direct message. Otherwise the logged in
+    // participants are used if they are known, or a limited number of the logged in users on the
+    // server, the ones that most recently sent a message first.
+    fn direct_message_lookup_candidates(
+        &self,
+        server: &RocketchatServer,
+        message: &WebhookMessage,
+        stored_users: &[User],
+    ) -> Result<Vec<UserOnRocketchatServer>> {
+        let sender =
+            UserOnRocketchatServer::find_by_rocketchat_user_id(self.connection, server.id.clone(), message.user_id.clone())?;
+        if let Some(sender) = sender.filter(|sender| sender.is_logged_in()) {
+            return Ok(vec![sender]);
+        }
+
+        if !stored_users.is_empty() {
+            return self.logged_in_direct_message_members(server, stored_users);
+        }
+
+        let mut candidates = server.logged_in_users_on_rocketchat_server(self.connection)?;
+        candidates.sort_by(|a, b| b.last_message_sent.cmp(&a.last_message_sent));
+        candidates.truncate(DIRECT_MESSAGE_LOOKUP_CANDIDATES_LIMIT);
+        Ok(candidates)
+    }
+
+    fn logged_in_direct_message_members(
+        &self,
+        server: &RocketchatServer,
+        members: &[User],
+    ) -> Result<Vec<UserOnRocketchatServer>> {
+        let mut logged_in_members = Vec::new();
+        for member in members {
+            if let Some(user_on_rocketchat_server) =
+                UserOnRocketchatServer::find_by_rocketchat_user_id(self.connection, server.id.clone(), member.id.clone())?
+            {
+                if user_on_rocketchat_server.is_logged_in() {
+                    logged_in_members.push(user_on_rocketchat_server);
+                }
+            }
+        }
+
+        Ok(logged_in_members)
+    }
+
     fn invite_user_into_direct_message_room(&self, room: &Room, receiver: &UserOnRocketchatServer) -> Result<()> {
-        let direct_message_recepient = room.direct_message_matrix_user()?;
-        if direct_message_recepient.is_none() {
-            let inviting_user_id = self.matrix_api.get_room_creator(room.id.clone())?;
+        if !room.direct_message_matrix_users(self.connection)?.contains(&receiver.matrix_user_id) {
+            let inviting_user_id = room.query_user_id(self.connection)?;
             room.join_user(receiver.matrix_user_id.clone(), inviting_user_id)?;
         }
 
@@ -161,7 +716,7 @@ impl<'a> Forwarder<'a> {
 
     fn prepare_room_for_channel(&self, server: &RocketchatServer, message: &WebhookMessage) -> Result<Option<Room>> {
         let channel = RocketchatRoom::new(self.config, self.logger, self.matrix_api, message.channel_id.clone(), &server.id);
-        let room_id = match channel.matrix_id()? {
+        let room_id = match channel.matrix_id(self.connection)? {
             Some(room_id) => room_id,
             None => return Ok(None),
         };
@@ -195,20 +750,17 @@ impl<'a> Forwarder<'a> {
 
         if rocketchat_api.dm_list()?.iter().any(|dm| dm.id == message.channel_id) {
             let sender_id = self.virtual_user.find_or_register(&server.id, &message.user_id, &message.user_name)?;
-
-            let room_display_name_suffix = t!(["defaults", "direct_message_room_display_name_suffix"]).l(DEFAULT_LANGUAGE);
-            let room_display_name = format!("{} {}", message.user_name, room_display_name_suffix);
-
-            let display_name = Some(room_display_name);
-            let room_id = Room::create(self.matrix_api, None, &display_name, &sender_id, &receiver.matrix_user_id)?;
-
-            // invite the bot user into the direct message room to be able to read the room state
-            // the bot will leave as soon as the AS gets the join event
-            let invitee_id = self.config.matrix_bot_user_id()?;
-            self.matrix_api.invite(room_id.clone(), invitee_id.clone(), sender_id.clone())?;
-            info!(self.logger, "Direct message room {} successfully created", &room_id);
-
-            let room = Room::new(self.config, self.logger, self.matrix_api, room_id);
+            let room = Room::create_dm(
+                self.config,
+                self.connection,
+                self.logger,
+                self.matrix_api,
+                &server.id,
+                message.channel_id.clone(),
+                &sender_id,
+                &message.user_name,
+                &receiver.matrix_user_id,
+            )?;
             Ok(Some(room))
         } else {
             debug!(
@@ -222,33 +774,6 @@ impl<'a> Forwarder<'a> {
         }
     }
 
-    // this is a pretty hacky way to find a Matrix user that could be the recipient for this
-    // message. The message itself doesn't contain any information about the recipient so the
-    // channel ID has to be checked against all users that use the application service and are
-    // logged in on the sending Rocket.Chat server, because direct message channel IDs consist of
-    // the `user_id`s of the two participants.
-    fn find_matching_user_for_direct_message(
-        &self,
-        server: &RocketchatServer,
-        message: &WebhookMessage,
-    ) -> Result<Option<UserOnRocketchatServer>> {
-        for user_on_rocketchatserver in server.logged_in_users_on_rocketchat_server(self.connection)? {
-            if let Some(rocketchat_user_id) = user_on_rocketchatserver.rocketchat_user_id.clone() {
-                if message.channel_id.contains(&rocketchat_user_id) && rocketchat_user_id != message.user_id {
-                    debug!(
-                        self.logger,
-                        "Matching user with rocketchat_user_id `{}` for channel_id `{}` found.",
-                        rocketchat_user_id,
-                        &message.channel_id
-                    );
-                    return Ok(Some(user_on_rocketchatserver));
-                }
-            }
-        }
-
-        Ok(None)
-    }
-
     fn forward_file(&self, server: &RocketchatServer, message: &WebhookMessage, room: &Room, sender_id: &UserId) -> Result<()> {
         debug!(self.logger, "Forwarding file, room {}", room.id);
 
@@ -274,17 +799,14 @@ impl<'a> Forwarder<'a> {
 
         let files = rocketchat_api.attachments(&message.message_id)?;
 
+        // the description is used as body, so that it's displayed as caption of the file
+        let description = message.file_description();
         for file in files {
             let file_url = self.matrix_api.upload(file.data.to_vec(), file.content_type.clone())?;
             let message_type = self.message_type(&file.content_type);
             debug!(self.logger, "Uploaded file, URL is {}", file_url);
-            self.matrix_api.send_data_message(
-                room.id.clone(),
-                sender_id.clone(),
-                file.title.clone(),
-                file_url,
-                message_type,
-            )?;
+            let body = description.map(|description| description.to_string()).unwrap_or_else(|| file.title.clone());
+            self.matrix_api.send_data_message(room.id.clone(), sender_id.clone(), body, file_url, message_type)?;
         }
 
         Ok(())
@@ -0,0 +1,66 @@
+use diesel::sqlite::SqliteConnection;
+use slog::Logger;
+
+use api::rocketchat::{MembershipEvent, WebhookMembership};
+use api::MatrixApi;
+use config::Config;
+use errors::*;
+use models::{RocketchatRoom, RocketchatServer, Room, VirtualUser};
+
+/// Handles users that joined or left a Rocket.Chat channel
+pub struct MembershipHandler<'a> {
+    config: &'a Config,
+    connection: &'a SqliteConnection,
+    logger: &'a Logger,
+    matrix_api: &'a MatrixApi,
+    virtual_user: &'a VirtualUser<'a>,
+}
+
+impl<'a> MembershipHandler<'a> {
+    /// Create a new `MembershipHandler`.
+    pub fn new(
+        config: &'a Config,
+        connection: &'a SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+        virtual_user: &'a VirtualUser,
+    ) -> MembershipHandler<'a> {
+        MembershipHandler { config, connection, logger, matrix_api, virtual_user }
+    }
+
+    /// Lets the virtual user of the Rocket.Chat user join or leave the bridged Matrix room.
+    pub fn process(&self, server: &RocketchatServer, membership: &WebhookMembership) -> Result<()> {
+        let channel = RocketchatRoom::new(self.config, self.logger, self.matrix_api, membership.channel_id.clone(), &server.id);
+        let room_id = match channel.matrix_id(self.connection)? {
+            Some(room_id) => room_id,
+            None => {
+                debug!(
+                    self.logger,
+                    "Ignoring membership change in Rocket.Chat channel `{}`, because the channel is not bridged.",
+                    membership.channel_id
+                );
+                return Ok(());
+            }
+        };
+
+        let room = Room::new(self.config, self.logger, self.matrix_api, room_id);
+        match membership.event {
+            MembershipEvent::RoomJoined => {
+                let user_id = self.virtual_user.find_or_register(&server.id, &membership.user_id, &membership.user_name)?;
+                let bot_user_id = self.config.matrix_bot_user_id()?;
+                room.join_user(user_id, bot_user_id)
+            }
+            MembershipEvent::RoomLeft => {
+                let user_id = self.virtual_user.build_user_id(&membership.user_id, &server.id)?;
+                let bot_user_id = self.config.matrix_bot_user_id()?;
+                if !room.user_ids(Some(bot_user_id))?.iter().any(|id| id == &user_id) {
+                    debug!(self.logger, "Virtual user {} already left the room {}", user_id, room.id);
+                    return Ok(());
+                }
+
+                debug!(self.logger, "Removing virtual user {} from room {}", user_id, room.id);
+                self.matrix_api.leave_room(room.id.clone(), user_id)
+            }
+        }
+    }
+}
@@ -2,5 +2,8 @@
 
 /// Forwards message from Rocket.Chat to Matrix
 mod forwarder;
+/// Handles membership changes in bridged Rocket.Chat channels
+mod membership_handler;
 
 pub use self::forwarder::Forwarder;
+pub use self::membership_handler::MembershipHandler;
@@ -54,10 +54,14 @@ pub mod errors;
 pub mod api;
 /// Helpers to interact with the application service configuration.
 pub mod config;
+/// Conversion between unicode emoji and Rocket.Chat emoji shortcodes
+pub mod emoji;
 /// Iron handlers
 pub mod handlers;
 /// Logging helpers
 pub mod log;
+/// Conversion between the Rocket.Chat markdown dialect and Matrix HTML
+pub mod markdown;
 /// Iron middleware
 pub mod middleware;
 /// Models used by the application service
@@ -0,0 +1,375 @@
+use std::char;
+use std::ops::Range;
+
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use regex::{Captures, Regex};
+
+/// Opening tag that is used for strikethrough text
+const STRIKE_OPEN_TAG: &str = "<del>";
+/// Closing tag that is used for strikethrough text
+const STRIKE_CLOSE_TAG: &str = "</del>";
+/// Fence that starts and ends a code block
+const CODE_FENCE: &str = "```";
+/// Schemes that are allowed in links, links with other schemes are rendered as text
+const ALLOWED_LINK_SCHEMES: &[&str] = &["http://", "https://", "mailto:"];
+
+lazy_static! {
+    static ref ROCKETCHAT_LINK: Regex = Regex::new(r"<((?:https?://|mailto:)[^|>\s]+)\|([^>]+)>").unwrap();
+    static ref HTML_TAG: Regex = Regex::new(r"<(/?)([a-zA-Z][a-zA-Z0-9-]*)([^>]*)>").unwrap();
+    static ref HTML_HREF: Regex = Regex::new(r#"href\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    static ref HTML_ENTITY: Regex = Regex::new(r"&(#[xX][0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);").unwrap();
+    static ref MULTIPLE_EMPTY_LINES: Regex = Regex::new(r"\n{3,}").unwrap();
+}
+
+/// Convert a message that uses the Rocket.Chat markdown dialect to HTML that can be used as
+/// `formatted_body` of a Matrix message. HTML that is part of the message is escaped.
+pub fn rocketchat_to_html(text: &str) -> String {
+    let commonmark = rocketchat_to_commonmark(text);
+    let parser = Parser::new_ext(&commonmark, Options::empty());
+
+    let mut in_link = Vec::new();
+    let events = parser.filter_map(|event| match event {
+        Event::InlineHtml(tag) => {
+            if tag == STRIKE_OPEN_TAG || tag == STRIKE_CLOSE_TAG {
+                Some(Event::InlineHtml(tag))
+            } else {
+                Some(Event::Text(tag))
+            }
+        }
+        Event::Html(content) => Some(Event::Text(content)),
+        Event::SoftBreak => Some(Event::HardBreak),
+        Event::Start(Tag::Link(url, title)) | Event::Start(Tag::Image(url, title)) => {
+            let is_allowed = is_allowed_link(&url);
+            in_link.push(is_allowed);
+            if is_allowed {
+                Some(Event::Start(Tag::Link(url, title)))
+            } else {
+                None
+            }
+        }
+        Event::End(Tag::Link(url, title)) | Event::End(Tag::Image(url, title)) => {
+            if in_link.pop().unwrap_or(false) {
+                Some(Event::End(Tag::Link(url, title)))
+            } else {
+                None
+            }
+        }
+        _ => Some(event),
+    });
+
+    let mut output = String::with_capacity(text.len());
+    html::push_html(&mut output, events);
+    output.trim_right().to_string()
+}
+
+// Rocket.Chat uses a single asterisk for bold text and a single tilde for strikethrough text,
+// those are replaced by their CommonMark (or HTML) equivalent. Code is left untouched.
+fn rocketchat_to_commonmark(text: &str) -> String {
+    let mut in_code_block = false;
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        let fences = line.matches(CODE_FENCE).count();
+        if in_code_block || (line.trim_left().starts_with(CODE_FENCE) && fences % 2 == 1) {
+            if fences % 2 == 1 {
+                in_code_block = !in_code_block;
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let segments: Vec<String> = line
+            .split('`')
+            .enumerate()
+            .map(|(i, segment)| if i % 2 == 0 { convert_inline_markup(segment) } else { segment.to_string() })
+            .collect();
+        lines.push(segments.join("`"));
+    }
+
+    lines.join("\n")
+}
+
+/// Byte ranges of the code blocks and the inline code of a message that uses the Rocket.Chat
+/// markdown dialect. The content of those ranges is not interpreted as markup.
+pub fn rocketchat_code_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut in_code_block = false;
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+
+    for line in text.split('\n') {
+        let fences = line.matches(CODE_FENCE).count();
+        if in_code_block || (line.trim_left().starts_with(CODE_FENCE) && fences % 2 == 1) {
+            if fences % 2 == 1 {
+                in_code_block = !in_code_block;
+            }
+            ranges.push(offset..offset + line.len());
+        } else {
+            let mut position = offset;
+            for (i, segment) in line.split('`').enumerate() {
+                if i % 2 == 1 {
+                    ranges.push(position..position + segment.len());
+                }
+                position += segment.len() + 1;
+            }
+        }
+        offset += line.len() + 1;
+    }
+
+    ranges
+}
+
+/// Escape text so that it is displayed as it is when it is used in a message that uses the
+/// Rocket.Chat markdown dialect (or CommonMark).
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn convert_inline_markup(text: &str) -> String {
+    let text = ROCKETCHAT_LINK.replace_all(text, "[$2]($1)");
+    let text = replace_delimited(&text, '*', "**", "**");
+    replace_delimited(&text, '~', STRIKE_OPEN_TAG, STRIKE_CLOSE_TAG)
+}
+
+// Replaces text that is enclosed by a single delimiter (for example `*bold*`). Doubled
+// delimiters, escaped delimiters, delimiters within words and delimiters that are followed (or
+// preceded in case of the closing delimiter) by whitespace are kept as they are.
+fn replace_delimited(text: &str, delimiter: char, open: &str, close: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let is_boundary = |i: usize| chars.get(i).map(|c| !c.is_alphanumeric() && *c != delimiter).unwrap_or(true);
+    let is_escaped = |i: usize| chars[..i].iter().rev().take_while(|c| **c == '\\').count() % 2 == 1;
+
+    let mut output = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let is_opening = chars[i] == delimiter
+            && !is_escaped(i)
+            && (i == 0 || is_boundary(i - 1))
+            && chars.get(i + 1).map(|c| *c != delimiter && !c.is_whitespace()).unwrap_or(false);
+
+        if is_opening {
+            let closing = (i + 1..chars.len()).find(|&j| {
+                chars[j] == delimiter
+                    && !is_escaped(j)
+                    && !chars[j - 1].is_whitespace()
+                    && chars[j - 1] != delimiter
+                    && is_boundary(j + 1)
+            });
+
+            if let Some(j) = closing {
+                output.push_str(open);
+                output.extend(&chars[i + 1..j]);
+                output.push_str(close);
+                i = j + 1;
+                continue;
+            }
+        }
+
+        output.push(chars[i]);
+        i += 1;
+    }
+
+    output
+}
+
+fn is_allowed_link(url: &str) -> bool {
+    let url = url.to_lowercase();
+    ALLOWED_LINK_SCHEMES.iter().any(|scheme| url.starts_with(scheme))
+}
+
+/// Convert the HTML `formatted_body` of a Matrix message to text that uses the Rocket.Chat
+/// markdown dialect. Tags that have no markdown equivalent are dropped, their text is kept.
+pub fn html_to_rocketchat(html: &str) -> String {
+    let mut converter = HtmlConverter::default();
+
+    let mut position = 0;
+    for captures in HTML_TAG.captures_iter(html) {
+        let tag = captures.get(0).expect("The whole match is always present");
+        converter.text(&decode_entities(&html[position..tag.start()]));
+        position = tag.end();
+
+        let name = captures[2].to_lowercase();
+        if &captures[1] == "/" {
+            converter.end_tag(&name);
+        } else {
+            let href = HTML_HREF.captures(&captures[3]).and_then(|href| href.get(1).or_else(|| href.get(2)));
+            converter.start_tag(&name, href.map(|href| decode_entities(href.as_str())));
+        }
+    }
+    converter.text(&decode_entities(&html[position..]));
+
+    converter.finish()
+}
+
+#[derive(Default)]
+struct HtmlConverter {
+    /// Stack of output buffers, elements that need their whole content to be rendered (links and
+    /// quotes) are written into their own buffer.
+    buffers: Vec<String>,
+    /// The targets of the links that are currently open
+    links: Vec<Option<String>>,
+    /// The lists that are currently open, ordered lists contain the number of the next item
+    lists: Vec<Option<usize>>,
+    /// Whether the converter is inside of a preformatted block
+    in_pre: bool,
+}
+
+impl HtmlConverter {
+    fn start_tag(&mut self, name: &str, href: Option<String>) {
+        match name {
+            "strong" | "b" => self.push("*"),
+            "em" | "i" => self.push("_"),
+            "del" | "s" | "strike" => self.push("~"),
+            "code" if !self.in_pre => self.push("`"),
+            "pre" => {
+                self.ensure_newline();
+                self.push(&format!("{}\n", CODE_FENCE));
+                self.in_pre = true;
+            }
+            "a" => {
+                self.links.push(href);
+                self.buffers.push(String::new());
+            }
+            "blockquote" => {
+                self.ensure_newline();
+                self.buffers.push(String::new());
+            }
+            "ul" => {
+                self.ensure_newline();
+                self.lists.push(None);
+            }
+            "ol" => {
+                self.ensure_newline();
+                self.lists.push(Some(1));
+            }
+            "li" => {
+                self.ensure_newline();
+                let indentation = "  ".repeat(self.lists.len().saturating_sub(1));
+                let marker = match self.lists.last_mut() {
+                    Some(&mut Some(ref mut number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.push(&format!("{}{}", indentation, marker));
+            }
+            "br" => self.push("\n"),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.ensure_newline();
+                self.push("*");
+            }
+            "p" | "div" => self.ensure_newline(),
+            _ => {}
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        match name {
+            "strong" | "b" => self.push("*"),
+            "em" | "i" => self.push("_"),
+            "del" | "s" | "strike" => self.push("~"),
+            "code" if !self.in_pre => self.push("`"),
+            "pre" => {
+                self.ensure_newline();
+                self.push(&format!("{}\n", CODE_FENCE));
+                self.in_pre = false;
+            }
+            "a" => {
+                let text = self.buffers.pop().unwrap_or_default();
+                match self.links.pop() {
+                    Some(Some(ref href)) if !text.is_empty() && &text != href => self.push(&format!("[{}]({})", text, href)),
+                    _ => self.push(&text),
+                }
+            }
+            "blockquote" => {
+                let content = self.buffers.pop().unwrap_or_default();
+                let quote: Vec<String> = content.trim().lines().map(|line| format!("> {}", line)).collect();
+                self.push(&quote.join("\n"));
+                self.push("\n");
+            }
+            "ul" | "ol" => {
+                self.lists.pop();
+                self.ensure_newline();
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.push("*");
+                self.ensure_newline();
+            }
+            "p" | "div" | "li" => self.ensure_newline(),
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.in_pre {
+            self.push(text);
+            return;
+        }
+
+        // newlines in HTML are not significant, line breaks are created by tags
+        let text = text.replace('\n', " ");
+        let at_line_start = self.current().map(|buffer| buffer.is_empty() || buffer.ends_with('\n')).unwrap_or(true);
+        if at_line_start {
+            self.push(text.trim_left());
+        } else {
+            self.push(&text);
+        }
+    }
+
+    fn ensure_newline(&mut self) {
+        let at_line_start = self.current().map(|buffer| buffer.is_empty() || buffer.ends_with('\n')).unwrap_or(true);
+        if !at_line_start {
+            self.push("\n");
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        if self.buffers.is_empty() {
+            self.buffers.push(String::new());
+        }
+
+        if let Some(buffer) = self.buffers.last_mut() {
+            buffer.push_str(text);
+        }
+    }
+
+    fn current(&self) -> Option<&String> {
+        self.buffers.last()
+    }
+
+    fn finish(self) -> String {
+        // buffers that are still open belong to tags that were not closed
+        let output = self.buffers.concat();
+        let lines: Vec<&str> = output.lines().map(|line| line.trim_right()).collect();
+        MULTIPLE_EMPTY_LINES.replace_all(&lines.join("\n"), "\n\n").trim().to_string()
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    HTML_ENTITY
+        .replace_all(text, |captures: &Captures| {
+            let entity = &captures[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            decoded.map(|c| c.to_string()).unwrap_or_else(|| captures[0].to_string())
+        })
+        .to_string()
+}
@@ -3,7 +3,7 @@ use std::io::Read;
 use iron::{BeforeMiddleware, IronResult, Request};
 use serde_json;
 
-use api::rocketchat::WebhookMessage;
+use api::rocketchat::WebhookEvent;
 use errors::*;
 use log::*;
 use models::{ConnectionPool, RocketchatServer};
@@ -16,8 +16,8 @@ impl BeforeMiddleware for RocketchatToken {
         let logger = IronLogger::from_request(request)?;
         let mut payload = String::new();
         request.body.read_to_string(&mut payload).chain_err(|| ErrorKind::InternalServerError).map_err(Error::from)?;
-        let message = match serde_json::from_str::<WebhookMessage>(&payload) {
-            Ok(message) => message,
+        let event = match serde_json::from_str::<WebhookEvent>(&payload) {
+            Ok(event) => event,
             Err(err) => {
                 let msg = format!("Could not deserialize message that was sent to the rocketchat endpoint: `{}`", payload);
                 let json_err = simple_error!(ErrorKind::InvalidJSON(msg));
@@ -26,7 +26,7 @@ impl BeforeMiddleware for RocketchatToken {
             }
         };
 
-        let token = match message.token.clone() {
+        let token = match event.token().cloned() {
             Some(token) => token,
             None => {
                 let err = simple_error!(ErrorKind::MissingRocketchatToken);
@@ -45,7 +45,7 @@ impl BeforeMiddleware for RocketchatToken {
             }
         };
 
-        request.extensions.insert::<WebhookMessage>(message);
+        request.extensions.insert::<WebhookEvent>(event);
         request.extensions.insert::<RocketchatServer>(server);
 
         Ok(())
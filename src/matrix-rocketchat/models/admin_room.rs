@@ -0,0 +1,91 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::{RoomId, UserId};
+
+use errors::*;
+use models::schema::admin_rooms;
+
+/// An admin room in which a Matrix user is connected to a Rocket.Chat server.
+#[derive(Associations, Debug, Identifiable, Queryable)]
+#[primary_key(matrix_room_id)]
+#[table_name = "admin_rooms"]
+pub struct AdminRoom {
+    /// The ID of the Matrix admin room
+    pub matrix_room_id: RoomId,
+    /// The Matrix user that uses the admin room
+    pub matrix_user_id: UserId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// created timestamp
+    pub created_at: String,
+}
+
+/// A new `AdminRoom`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "admin_rooms"]
+pub struct NewAdminRoom<'a> {
+    /// The ID of the Matrix admin room
+    pub matrix_room_id: &'a RoomId,
+    /// The Matrix user that uses the admin room
+    pub matrix_user_id: &'a UserId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+}
+
+impl AdminRoom {
+    /// Insert an `AdminRoom`.
+    pub fn insert(connection: &SqliteConnection, new_admin_room: &NewAdminRoom) -> Result<AdminRoom> {
+        diesel::insert_into(admin_rooms::table)
+            .values(new_admin_room)
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+
+        let admin_room =
+            admin_rooms::table.find(new_admin_room.matrix_room_id).first(connection).chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(admin_room)
+    }
+
+    /// Find an `AdminRoom` by the ID of the Matrix room. Returns `None` if the room is not
+    /// connected to a Rocket.Chat server.
+    pub fn find_by_matrix_room_id(connection: &SqliteConnection, matrix_room_id: &RoomId) -> Result<Option<AdminRoom>> {
+        let admin_rooms = admin_rooms::table
+            .filter(admin_rooms::matrix_room_id.eq(matrix_room_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(admin_rooms.into_iter().next())
+    }
+
+    /// Find the `AdminRoom` in which a Matrix user is connected to a Rocket.Chat server. Returns
+    /// `None` if the user has no admin room for the server.
+    pub fn find_by_matrix_user_id(
+        connection: &SqliteConnection,
+        matrix_user_id: &UserId,
+        rocketchat_server_id: &str,
+    ) -> Result<Option<AdminRoom>> {
+        let admin_rooms = admin_rooms::table
+            .filter(
+                admin_rooms::matrix_user_id.eq(matrix_user_id).and(admin_rooms::rocketchat_server_id.eq(rocketchat_server_id)),
+            )
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(admin_rooms.into_iter().next())
+    }
+
+    /// Find all `AdminRoom`s that are connected to a Rocket.Chat server.
+    pub fn find_by_rocketchat_server_id(connection: &SqliteConnection, rocketchat_server_id: &str) -> Result<Vec<AdminRoom>> {
+        let admin_rooms = admin_rooms::table
+            .filter(admin_rooms::rocketchat_server_id.eq(rocketchat_server_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(admin_rooms)
+    }
+
+    /// Delete an admin room.
+    pub fn delete(&self, connection: &SqliteConnection) -> Result<()> {
+        diesel::delete(admin_rooms::table.find(&self.matrix_room_id))
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBDeleteError)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use diesel;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::{EventId, RoomId};
+
+use errors::*;
+use models::schema::bridged_messages;
+
+/// A message that was forwarded from Matrix to Rocket.Chat.
+#[derive(Associations, Debug, Identifiable, Queryable)]
+#[primary_key(matrix_event_id)]
+#[table_name = "bridged_messages"]
+pub struct BridgedMessage {
+    /// The ID of the event on the Matrix homeserver
+    pub matrix_event_id: EventId,
+    /// The ID of the Matrix room in which the event was sent
+    pub matrix_room_id: RoomId,
+    /// The ID of the message on the Rocket.Chat server
+    pub rocketchat_message_id: String,
+    /// The ID of the Rocket.Chat channel, group or direct message in which the message was posted
+    pub rocketchat_room_id: String,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// created timestamp
+    pub created_at: String,
+    /// updated timestamp
+    pub updated_at: String,
+    /// The ID of the first message of the Rocket.Chat thread the message was posted in
+    pub rocketchat_thread_id: Option<String>,
+}
+
+/// A new `BridgedMessage`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "bridged_messages"]
+pub struct NewBridgedMessage<'a> {
+    /// The ID of the event on the Matrix homeserver
+    pub matrix_event_id: &'a EventId,
+    /// The ID of the Matrix room in which the event was sent
+    pub matrix_room_id: &'a RoomId,
+    /// The ID of the message on the Rocket.Chat server
+    pub rocketchat_message_id: &'a str,
+    /// The ID of the Rocket.Chat channel, group or direct message in which the message was posted
+    pub rocketchat_room_id: &'a str,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ID of the first message of the Rocket.Chat thread the message was posted in
+    pub rocketchat_thread_id: Option<&'a str>,
+}
+
+impl BridgedMessage {
+    /// Insert a `BridgedMessage`.
+    pub fn insert(connection: &SqliteConnection, new_bridged_message: &NewBridgedMessage) -> Result<BridgedMessage> {
+        diesel::insert_into(bridged_messages::table)
+            .values(new_bridged_message)
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+
+        let bridged_message = bridged_messages::table
+            .find(new_bridged_message.matrix_event_id)
+            .first(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_message)
+    }
+
+    /// Find a `BridgedMessage` by the ID of the Matrix event. Returns `None` if the message was
+    /// not forwarded to Rocket.Chat.
+    pub fn find_by_matrix_event_id(connection: &SqliteConnection, matrix_event_id: &EventId) -> Result<Option<BridgedMessage>> {
+        let bridged_messages = bridged_messages::table
+            .filter(bridged_messages::matrix_event_id.eq(matrix_event_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_messages.into_iter().next())
+    }
+
+    /// Find a `BridgedMessage` by the ID of the Rocket.Chat message and the Rocket.Chat server ID.
+    /// Returns `None` if no Matrix event is associated with the message.
+    pub fn find_by_rocketchat_message_id(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_message_id: &str,
+    ) -> Result<Option<BridgedMessage>> {
+        let bridged_messages = bridged_messages::table
+            .filter(
+                bridged_messages::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(bridged_messages::rocketchat_message_id.eq(rocketchat_message_id)),
+            )
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_messages.into_iter().next())
+    }
+
+    /// Find the most recent `BridgedMessage` that was posted in a Rocket.Chat thread. Returns
+    /// `None` if no message of the thread was bridged.
+    pub fn find_latest_in_thread(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_thread_id: &str,
+    ) -> Result<Option<BridgedMessage>> {
+        let bridged_messages = bridged_messages::table
+            .filter(
+                bridged_messages::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(bridged_messages::rocketchat_thread_id.eq(rocketchat_thread_id)),
+            )
+            .order(bridged_messages::created_at.desc())
+            // the timestamp only has a precision of seconds, the insertion order decides between
+            // messages that were bridged within the same second
+            .then_order_by(sql::<BigInt>("rowid").desc())
+            .limit(1)
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_messages.into_iter().next())
+    }
+
+    /// The ID of the Rocket.Chat thread the message belongs to. A message that started a thread
+    /// belongs to the thread as well. Returns `None` if the message is not part of a thread.
+    pub fn thread_id(&self, connection: &SqliteConnection) -> Result<Option<String>> {
+        if let Some(ref rocketchat_thread_id) = self.rocketchat_thread_id {
+            return Ok(Some(rocketchat_thread_id.clone()));
+        }
+
+        let reply = BridgedMessage::find_latest_in_thread(connection, &self.rocketchat_server_id, &self.rocketchat_message_id)?;
+        Ok(reply.map(|_| self.rocketchat_message_id.clone()))
+    }
+
+    /// Delete a `BridgedMessage`.
+    pub fn delete(&self, connection: &SqliteConnection) -> Result<()> {
+        diesel::delete(bridged_messages::table.find(&self.matrix_event_id))
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBDeleteError)?;
+        Ok(())
+    }
+}
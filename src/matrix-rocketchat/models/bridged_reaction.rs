@@ -0,0 +1,102 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::{EventId, RoomId};
+
+use errors::*;
+use models::schema::bridged_reactions;
+
+/// A reaction to a message that is bridged between Matrix and Rocket.Chat.
+#[derive(Associations, Debug, Identifiable, Queryable)]
+#[primary_key(matrix_event_id)]
+#[table_name = "bridged_reactions"]
+pub struct BridgedReaction {
+    /// The ID of the reaction event on the Matrix homeserver
+    pub matrix_event_id: EventId,
+    /// The ID of the Matrix room in which the reaction was sent
+    pub matrix_room_id: RoomId,
+    /// The ID of the Rocket.Chat message the reaction belongs to
+    pub rocketchat_message_id: String,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// The ID of the Rocket.Chat user that reacted to the message
+    pub rocketchat_user_id: String,
+    /// The Rocket.Chat shortcode of the emoji (for example `:thumbsup:`)
+    pub emoji: String,
+    /// created timestamp
+    pub created_at: String,
+    /// updated timestamp
+    pub updated_at: String,
+}
+
+/// A new `BridgedReaction`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "bridged_reactions"]
+pub struct NewBridgedReaction<'a> {
+    /// The ID of the reaction event on the Matrix homeserver
+    pub matrix_event_id: &'a EventId,
+    /// The ID of the Matrix room in which the reaction was sent
+    pub matrix_room_id: &'a RoomId,
+    /// The ID of the Rocket.Chat message the reaction belongs to
+    pub rocketchat_message_id: &'a str,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ID of the Rocket.Chat user that reacted to the message
+    pub rocketchat_user_id: &'a str,
+    /// The Rocket.Chat shortcode of the emoji (for example `:thumbsup:`)
+    pub emoji: &'a str,
+}
+
+impl BridgedReaction {
+    /// Insert a `BridgedReaction`.
+    pub fn insert(connection: &SqliteConnection, new_bridged_reaction: &NewBridgedReaction) -> Result<BridgedReaction> {
+        diesel::insert_into(bridged_reactions::table)
+            .values(new_bridged_reaction)
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+
+        let bridged_reaction = bridged_reactions::table
+            .find(new_bridged_reaction.matrix_event_id)
+            .first(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_reaction)
+    }
+
+    /// Find a `BridgedReaction` by the ID of the Matrix reaction event. Returns `None` if the
+    /// reaction is not bridged.
+    pub fn find_by_matrix_event_id(
+        connection: &SqliteConnection,
+        matrix_event_id: &EventId,
+    ) -> Result<Option<BridgedReaction>> {
+        let bridged_reactions = bridged_reactions::table
+            .filter(bridged_reactions::matrix_event_id.eq(matrix_event_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_reactions.into_iter().next())
+    }
+
+    /// Find all the bridged reactions to a Rocket.Chat message.
+    pub fn find_by_rocketchat_message_id(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_message_id: &str,
+    ) -> Result<Vec<BridgedReaction>> {
+        let bridged_reactions = bridged_reactions::table
+            .filter(
+                bridged_reactions::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(bridged_reactions::rocketchat_message_id.eq(rocketchat_message_id)),
+            )
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_reactions)
+    }
+
+    /// Delete a `BridgedReaction`.
+    pub fn delete(&self, connection: &SqliteConnection) -> Result<()> {
+        diesel::delete(bridged_reactions::table.find(&self.matrix_event_id))
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBDeleteError)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::{RoomId, UserId};
+
+use errors::*;
+use models::schema::bridged_rooms;
+
+/// Kind of a bridged public Rocket.Chat channel
+pub const CHANNEL_KIND: &str = "channel";
+/// Kind of a bridged private Rocket.Chat group
+pub const GROUP_KIND: &str = "group";
+
+/// A Matrix room that is bridged to a Rocket.Chat channel or group.
+#[derive(Associations, Debug, Identifiable, Queryable)]
+#[primary_key(matrix_room_id)]
+#[table_name = "bridged_rooms"]
+pub struct BridgedRoom {
+    /// The ID of the Matrix room
+    pub matrix_room_id: RoomId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// The ID of the Rocket.Chat channel or group
+    pub rocketchat_channel_id: String,
+    /// Either a channel or a group, unknown for rooms that were bridged before the mapping was stored
+    pub kind: Option<String>,
+    /// The Matrix user that bridged the room, unknown for rooms that were bridged before the mapping was stored
+    pub created_by: Option<UserId>,
+    /// created timestamp
+    pub created_at: String,
+}
+
+/// A new `BridgedRoom`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "bridged_rooms"]
+pub struct NewBridgedRoom<'a> {
+    /// The ID of the Matrix room
+    pub matrix_room_id: &'a RoomId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ID of the Rocket.Chat channel or group
+    pub rocketchat_channel_id: &'a str,
+    /// Either a channel or a group
+    pub kind: Option<&'a str>,
+    /// The Matrix user that bridged the room
+    pub created_by: Option<&'a UserId>,
+}
+
+impl BridgedRoom {
+    /// Insert a `BridgedRoom`.
+    pub fn insert(connection: &SqliteConnection, new_bridged_room: &NewBridgedRoom) -> Result<BridgedRoom> {
+        diesel::insert_into(bridged_rooms::table)
+            .values(new_bridged_room)
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+
+        let bridged_room = bridged_rooms::table
+            .find(new_bridged_room.matrix_room_id)
+            .first(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_room)
+    }
+
+    /// Find a `BridgedRoom` by the ID of the Matrix room. Returns `None` if the room is not bridged.
+    pub fn find_by_matrix_room_id(connection: &SqliteConnection, matrix_room_id: &RoomId) -> Result<Option<BridgedRoom>> {
+        let bridged_rooms = bridged_rooms::table
+            .filter(bridged_rooms::matrix_room_id.eq(matrix_room_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_rooms.into_iter().next())
+    }
+
+    /// Find a `BridgedRoom` by the ID of the Rocket.Chat channel or group and the Rocket.Chat
+    /// server ID. Returns `None` if the channel or group is not bridged.
+    pub fn find_by_rocketchat_channel_id(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_channel_id: &str,
+    ) -> Result<Option<BridgedRoom>> {
+        let bridged_rooms = bridged_rooms::table
+            .filter(
+                bridged_rooms::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(bridged_rooms::rocketchat_channel_id.eq(rocketchat_channel_id)),
+            )
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_rooms.into_iter().next())
+    }
+
+    /// Find all `BridgedRoom`s that are bridged to a Rocket.Chat server.
+    pub fn find_by_rocketchat_server_id(connection: &SqliteConnection, rocketchat_server_id: &str) -> Result<Vec<BridgedRoom>> {
+        let bridged_rooms = bridged_rooms::table
+            .filter(bridged_rooms::rocketchat_server_id.eq(rocketchat_server_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(bridged_rooms)
+    }
+
+    /// Delete a bridged room.
+    pub fn delete(&self, connection: &SqliteConnection) -> Result<()> {
+        diesel::delete(bridged_rooms::table.find(&self.matrix_room_id))
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBDeleteError)?;
+        Ok(())
+    }
+}
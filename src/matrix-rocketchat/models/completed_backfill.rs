@@ -0,0 +1,44 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+use errors::*;
+use models::schema::completed_backfills;
+
+/// A backfill of data that was not stored in the database by earlier versions, it only has to
+/// run once.
+#[derive(Debug, Queryable)]
+pub struct CompletedBackfill {
+    /// The name of the backfill
+    pub name: String,
+    /// created timestamp
+    pub created_at: String,
+}
+
+/// A new `CompletedBackfill`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "completed_backfills"]
+struct NewCompletedBackfill<'a> {
+    name: &'a str,
+}
+
+impl CompletedBackfill {
+    /// Mark the backfill with the given name as completed.
+    pub fn insert(connection: &SqliteConnection, name: &str) -> Result<()> {
+        diesel::replace_into(completed_backfills::table)
+            .values(&NewCompletedBackfill { name })
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+        Ok(())
+    }
+
+    /// Returns true if the backfill with the given name was already completed.
+    pub fn is_completed(connection: &SqliteConnection, name: &str) -> Result<bool> {
+        let completed_backfills: i64 = completed_backfills::table
+            .filter(completed_backfills::name.eq(name))
+            .count()
+            .get_result(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(completed_backfills > 0)
+    }
+}
@@ -0,0 +1,121 @@
+use diesel;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel::sqlite::SqliteConnection;
+
+use api::rocketchat::User;
+use errors::*;
+use models::schema::direct_message_members;
+
+/// A participant of a Rocket.Chat direct message channel.
+#[derive(Debug, Queryable)]
+pub struct DirectMessageMember {
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// The ID of the Rocket.Chat direct message channel
+    pub rocketchat_channel_id: String,
+    /// The ID of the participant on the Rocket.Chat server
+    pub rocketchat_user_id: String,
+    /// The username of the participant on the Rocket.Chat server
+    pub rocketchat_username: String,
+    /// created timestamp
+    pub created_at: String,
+}
+
+/// A new `DirectMessageMember`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "direct_message_members"]
+pub struct NewDirectMessageMember<'a> {
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ID of the Rocket.Chat direct message channel
+    pub rocketchat_channel_id: &'a str,
+    /// The ID of the participant on the Rocket.Chat server
+    pub rocketchat_user_id: &'a str,
+    /// The username of the participant on the Rocket.Chat server
+    pub rocketchat_username: &'a str,
+}
+
+impl DirectMessageMember {
+    /// Replace the stored participants of a direct message channel with the given Rocket.Chat users.
+    pub fn replace_all(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_channel_id: &str,
+        rocketchat_users: &[User],
+    ) -> Result<()> {
+        let new_direct_message_members: Vec<NewDirectMessageMember> = rocketchat_users
+            .iter()
+            .map(|rocketchat_user| NewDirectMessageMember {
+                rocketchat_server_id,
+                rocketchat_channel_id,
+                rocketchat_user_id: &rocketchat_user.id,
+                rocketchat_username: &rocketchat_user.username,
+            })
+            .collect();
+
+        connection.transaction(|| {
+            diesel::delete(
+                direct_message_members::table.filter(
+                    direct_message_members::rocketchat_server_id
+                        .eq(rocketchat_server_id)
+                        .and(direct_message_members::rocketchat_channel_id.eq(rocketchat_channel_id)),
+                ),
+            )
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBDeleteError)?;
+
+            diesel::insert_into(direct_message_members::table)
+                .values(&new_direct_message_members)
+                .execute(connection)
+                .chain_err(|| ErrorKind::DBInsertError)?;
+
+            Ok(())
+        })
+    }
+
+    /// Find all stored participants of a direct message channel. Returns an empty list if the
+    /// participants were not looked up yet.
+    pub fn find_by_rocketchat_channel_id(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_channel_id: &str,
+    ) -> Result<Vec<DirectMessageMember>> {
+        let direct_message_members = direct_message_members::table
+            .filter(
+                direct_message_members::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(direct_message_members::rocketchat_channel_id.eq(rocketchat_channel_id)),
+            )
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(direct_message_members)
+    }
+
+    /// Returns true if the stored participants of a direct message channel were looked up more
+    /// than the given number of seconds ago.
+    pub fn are_outdated(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_channel_id: &str,
+        max_age_in_seconds: i64,
+    ) -> Result<bool> {
+        let outdated_members: i64 = direct_message_members::table
+            .filter(
+                direct_message_members::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(direct_message_members::rocketchat_channel_id.eq(rocketchat_channel_id)),
+            )
+            .filter(sql::<Bool>(&format!("created_at < datetime('now', '-{} seconds')", max_age_in_seconds)))
+            .count()
+            .get_result(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(outdated_members > 0)
+    }
+
+    /// The participant as Rocket.Chat user.
+    pub fn rocketchat_user(&self) -> User {
+        User { id: self.rocketchat_user_id.clone(), username: self.rocketchat_username.clone() }
+    }
+}
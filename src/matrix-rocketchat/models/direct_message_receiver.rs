@@ -0,0 +1,98 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::{RoomId, UserId};
+
+use errors::*;
+use models::schema::direct_message_receivers;
+
+/// A Matrix user that receives the messages of a Rocket.Chat direct message channel in a room.
+#[derive(Debug, Queryable)]
+pub struct DirectMessageReceiver {
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// The ID of the Rocket.Chat direct message channel
+    pub rocketchat_channel_id: String,
+    /// The Matrix user that receives the messages
+    pub matrix_user_id: UserId,
+    /// The ID of the Matrix room in which the messages are received
+    pub matrix_room_id: RoomId,
+    /// created timestamp
+    pub created_at: String,
+}
+
+/// A new `DirectMessageReceiver`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "direct_message_receivers"]
+pub struct NewDirectMessageReceiver<'a> {
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ID of the Rocket.Chat direct message channel
+    pub rocketchat_channel_id: &'a str,
+    /// The Matrix user that receives the messages
+    pub matrix_user_id: &'a UserId,
+    /// The ID of the Matrix room in which the messages are received
+    pub matrix_room_id: &'a RoomId,
+}
+
+impl DirectMessageReceiver {
+    /// Insert a `DirectMessageReceiver`, an existing entry for the same channel and user is replaced.
+    pub fn upsert(connection: &SqliteConnection, new_direct_message_receiver: &NewDirectMessageReceiver) -> Result<()> {
+        diesel::replace_into(direct_message_receivers::table)
+            .values(new_direct_message_receiver)
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+        Ok(())
+    }
+
+    /// Find the `DirectMessageReceiver` of a Matrix user for a Rocket.Chat direct message channel.
+    /// Returns `None` if the room in which the user receives the messages is not known.
+    pub fn find(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_channel_id: &str,
+        matrix_user_id: &UserId,
+    ) -> Result<Option<DirectMessageReceiver>> {
+        let direct_message_receivers = direct_message_receivers::table
+            .find((rocketchat_server_id, rocketchat_channel_id, matrix_user_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(direct_message_receivers.into_iter().next())
+    }
+
+    /// Find all `DirectMessageReceiver`s of a Matrix user on a Rocket.Chat server.
+    pub fn find_by_matrix_user_id(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        matrix_user_id: &UserId,
+    ) -> Result<Vec<DirectMessageReceiver>> {
+        let direct_message_receivers = direct_message_receivers::table
+            .filter(
+                direct_message_receivers::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(direct_message_receivers::matrix_user_id.eq(matrix_user_id)),
+            )
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(direct_message_receivers)
+    }
+
+    /// Delete the entries of a Matrix user for a room, the user no longer receives direct messages
+    /// in that room.
+    pub fn delete_by_matrix_room_id(
+        connection: &SqliteConnection,
+        matrix_room_id: &RoomId,
+        matrix_user_id: &UserId,
+    ) -> Result<()> {
+        diesel::delete(
+            direct_message_receivers::table.filter(
+                direct_message_receivers::matrix_room_id
+                    .eq(matrix_room_id)
+                    .and(direct_message_receivers::matrix_user_id.eq(matrix_user_id)),
+            ),
+        )
+        .execute(connection)
+        .chain_err(|| ErrorKind::DBDeleteError)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::{RoomId, UserId};
+
+use errors::*;
+use models::schema::direct_message_rooms;
+
+/// A Matrix room that is used for a Rocket.Chat direct message.
+#[derive(Associations, Debug, Identifiable, Queryable)]
+#[primary_key(matrix_room_id)]
+#[table_name = "direct_message_rooms"]
+pub struct DirectMessageRoom {
+    /// The ID of the Matrix room
+    pub matrix_room_id: RoomId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// The ID of the Rocket.Chat direct message channel
+    pub rocketchat_channel_id: String,
+    /// The user of the application service that is used to query the room
+    pub matrix_query_user_id: UserId,
+    /// created timestamp
+    pub created_at: String,
+}
+
+/// A new `DirectMessageRoom`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "direct_message_rooms"]
+pub struct NewDirectMessageRoom<'a> {
+    /// The ID of the Matrix room
+    pub matrix_room_id: &'a RoomId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ID of the Rocket.Chat direct message channel
+    pub rocketchat_channel_id: &'a str,
+    /// The user of the application service that is used to query the room
+    pub matrix_query_user_id: &'a UserId,
+}
+
+impl DirectMessageRoom {
+    /// Insert a `DirectMessageRoom`.
+    pub fn insert(connection: &SqliteConnection, new_direct_message_room: &NewDirectMessageRoom) -> Result<DirectMessageRoom> {
+        diesel::insert_into(direct_message_rooms::table)
+            .values(new_direct_message_room)
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+
+        let direct_message_room = direct_message_rooms::table
+            .find(new_direct_message_room.matrix_room_id)
+            .first(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(direct_message_room)
+    }
+
+    /// Find a `DirectMessageRoom` by the ID of the Matrix room. Returns `None` if the room is not
+    /// a known direct message room.
+    pub fn find_by_matrix_room_id(connection: &SqliteConnection, matrix_room_id: &RoomId) -> Result<Option<DirectMessageRoom>> {
+        let direct_message_rooms = direct_message_rooms::table
+            .filter(direct_message_rooms::matrix_room_id.eq(matrix_room_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(direct_message_rooms.into_iter().next())
+    }
+
+    /// Find all `DirectMessageRoom`s for a Rocket.Chat direct message channel. There is one room
+    /// per Matrix receiver for direct messages between two users and a single room for direct
+    /// messages with more than two participants.
+    pub fn find_by_rocketchat_channel_id(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_channel_id: &str,
+    ) -> Result<Vec<DirectMessageRoom>> {
+        let direct_message_rooms = direct_message_rooms::table
+            .filter(
+                direct_message_rooms::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(direct_message_rooms::rocketchat_channel_id.eq(rocketchat_channel_id)),
+            )
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(direct_message_rooms)
+    }
+
+    /// Find all `DirectMessageRoom`s of a Rocket.Chat server.
+    pub fn find_by_rocketchat_server_id(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+    ) -> Result<Vec<DirectMessageRoom>> {
+        let direct_message_rooms = direct_message_rooms::table
+            .filter(direct_message_rooms::rocketchat_server_id.eq(rocketchat_server_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(direct_message_rooms)
+    }
+
+    /// Delete a direct message room.
+    pub fn delete(&self, connection: &SqliteConnection) -> Result<()> {
+        diesel::delete(direct_message_rooms::table.find(&self.matrix_room_id))
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBDeleteError)?;
+        Ok(())
+    }
+}
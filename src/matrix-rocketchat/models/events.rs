@@ -1,4 +1,14 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
 use ruma_events::collections::all::Event;
+use ruma_identifiers::EventId;
+use serde_json::{self, Value};
+
+use errors::*;
+
+/// The format of a formatted body that contains HTML
+const HTML_FORMAT: &str = "org.matrix.custom.html";
 
 /// A collection of Matrix events.
 #[derive(Serialize, Deserialize)]
@@ -6,3 +16,147 @@ pub struct Events {
     /// Matrix events
     pub events: Vec<Box<Event>>,
 }
+
+/// Parts of a message content that are not covered by the `ruma_events` message types,
+/// for example the relation to another event.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MessageContentExtensions {
+    /// The format of the formatted body (for example `org.matrix.custom.html`)
+    pub format: Option<String>,
+    /// The formatted version of the message body
+    pub formatted_body: Option<String>,
+    /// The new content of the message, only present if the message replaces another message
+    #[serde(rename = "m.new_content")]
+    pub new_content: Option<NewContent>,
+    /// The relation of the message to another event
+    #[serde(rename = "m.relates_to")]
+    pub relates_to: Option<RelatesTo>,
+}
+
+/// The content that replaces the content of an edited message
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewContent {
+    /// The new text of the message
+    pub body: String,
+    /// The format of the new formatted body (for example `org.matrix.custom.html`)
+    pub format: Option<String>,
+    /// The new formatted version of the message body
+    pub formatted_body: Option<String>,
+}
+
+impl NewContent {
+    /// Returns the formatted body if it is HTML.
+    pub fn html_body(&self) -> Option<&str> {
+        html_body(&self.format, &self.formatted_body)
+    }
+}
+
+/// A relation to another event
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RelatesTo {
+    /// The ID of the event the message relates to
+    pub event_id: Option<EventId>,
+    /// The type of the relation (for example `m.replace`)
+    pub rel_type: Option<String>,
+    /// The key of an annotation, for reactions this is the emoji
+    pub key: Option<String>,
+    /// The event the message replies to
+    #[serde(rename = "m.in_reply_to")]
+    pub in_reply_to: Option<InReplyTo>,
+}
+
+/// The event a message replies to
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InReplyTo {
+    /// The ID of the event the message replies to
+    pub event_id: EventId,
+}
+
+#[derive(Deserialize)]
+struct RawEvents {
+    events: Vec<RawEvent>,
+}
+
+#[derive(Deserialize)]
+struct RawEvent {
+    event_id: Option<String>,
+    #[serde(default)]
+    content: Value,
+}
+
+impl MessageContentExtensions {
+    /// Extract the message content extensions of all the events in a transaction payload. The
+    /// extensions are indexed by the ID of the event they belong to.
+    pub fn from_payload(payload: &str) -> Result<HashMap<EventId, MessageContentExtensions>> {
+        let raw_events: RawEvents = serde_json::from_str(payload)
+            .chain_err(|| ErrorKind::InvalidJSON(format!("Could not deserialize the content of the events: `{}`", payload)))?;
+
+        let mut extensions = HashMap::new();
+        for raw_event in raw_events.events {
+            let event_id = match raw_event.event_id.and_then(|event_id| EventId::try_from(event_id.as_ref()).ok()) {
+                Some(event_id) => event_id,
+                None => continue,
+            };
+
+            // the content of other events than messages can have a completely different structure
+            if let Ok(extension) = serde_json::from_value::<MessageContentExtensions>(raw_event.content) {
+                extensions.insert(event_id, extension);
+            }
+        }
+
+        Ok(extensions)
+    }
+
+    /// Returns the formatted body if it is HTML.
+    pub fn html_body(&self) -> Option<&str> {
+        html_body(&self.format, &self.formatted_body)
+    }
+
+    /// Returns the ID of the edited event if the message replaces the content of another message.
+    pub fn replaced_event_id(&self) -> Option<&EventId> {
+        match self.relates_to {
+            Some(RelatesTo { event_id: Some(ref event_id), rel_type: Some(ref rel_type), .. }) if rel_type == "m.replace" => {
+                Some(event_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the ID of the annotated event and the key of the annotation if the event is an
+    /// annotation (for example a reaction) to another event.
+    pub fn annotation(&self) -> Option<(&EventId, &str)> {
+        match self.relates_to {
+            Some(RelatesTo { event_id: Some(ref event_id), rel_type: Some(ref rel_type), key: Some(ref key), .. })
+                if rel_type == "m.annotation" =>
+            {
+                Some((event_id, key))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the ID of the first event of the thread if the message is part of a thread.
+    pub fn thread_root_event_id(&self) -> Option<&EventId> {
+        match self.relates_to {
+            Some(RelatesTo { event_id: Some(ref event_id), rel_type: Some(ref rel_type), .. }) if rel_type == "m.thread" => {
+                Some(event_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the ID of the event the message replies to.
+    pub fn in_reply_to_event_id(&self) -> Option<&EventId> {
+        match self.relates_to {
+            Some(RelatesTo { in_reply_to: Some(ref in_reply_to), .. }) => Some(&in_reply_to.event_id),
+            _ => None,
+        }
+    }
+}
+
+fn html_body<'a>(format: &Option<String>, formatted_body: &'a Option<String>) -> Option<&'a str> {
+    match (format, formatted_body) {
+        (&Some(ref format), &Some(ref formatted_body)) if format == HTML_FORMAT => Some(formatted_body),
+        _ => None,
+    }
+}
@@ -1,9 +1,27 @@
 //! Models that manage data and logic used by the application service.
 
+/// `AdminRoom` entry
+mod admin_room;
+/// `BridgedMessage` entry
+mod bridged_message;
+/// `BridgedReaction` entry
+mod bridged_reaction;
+/// `BridgedRoom` entry
+mod bridged_room;
+/// `CompletedBackfill` entry
+mod completed_backfill;
 /// The database connection pool
 mod connection_pool;
+/// `DirectMessageMember` entry
+mod direct_message_member;
+/// `DirectMessageReceiver` entry
+mod direct_message_receiver;
+/// `DirectMessageRoom` entry
+mod direct_message_room;
 /// A list of Events that are received from the Matirx homeserver.
 mod events;
+/// `NonDirectMessageChannel` entry
+mod non_direct_message_channel;
 /// A Rocket.Chat channel or group
 mod rocketchat_room;
 /// `RocketchatServer` entry
@@ -16,11 +34,23 @@ mod schema;
 mod user_on_rocketchat_server;
 /// A virtual user on the Matrix homeserver that represents a Rocket.Chat user.
 mod virtual_user;
+/// `VirtualUserAvatar` entry
+mod virtual_user_avatar;
 
+pub use self::admin_room::{AdminRoom, NewAdminRoom};
+pub use self::bridged_message::{BridgedMessage, NewBridgedMessage};
+pub use self::bridged_reaction::{BridgedReaction, NewBridgedReaction};
+pub use self::bridged_room::{BridgedRoom, NewBridgedRoom, CHANNEL_KIND, GROUP_KIND};
+pub use self::completed_backfill::CompletedBackfill;
 pub use self::connection_pool::ConnectionPool;
-pub use self::events::Events;
+pub use self::direct_message_member::{DirectMessageMember, NewDirectMessageMember};
+pub use self::direct_message_receiver::{DirectMessageReceiver, NewDirectMessageReceiver};
+pub use self::direct_message_room::{DirectMessageRoom, NewDirectMessageRoom};
+pub use self::events::{Events, InReplyTo, MessageContentExtensions, NewContent, RelatesTo};
+pub use self::non_direct_message_channel::{NewNonDirectMessageChannel, NonDirectMessageChannel};
 pub use self::rocketchat_room::RocketchatRoom;
-pub use self::rocketchat_server::{Credentials, NewRocketchatServer, RocketchatServer};
+pub use self::rocketchat_server::{Credentials, NewRocketchatServer, RocketchatServer, TokenCredentials};
 pub use self::room::Room;
 pub use self::user_on_rocketchat_server::{NewUserOnRocketchatServer, UserOnRocketchatServer};
 pub use self::virtual_user::VirtualUser;
+pub use self::virtual_user_avatar::{NewVirtualUserAvatar, VirtualUserAvatar};
@@ -0,0 +1,69 @@
+use diesel;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel::sqlite::SqliteConnection;
+
+use errors::*;
+use models::schema::non_direct_message_channels;
+
+/// A Rocket.Chat channel that is not a direct message with a participant that uses Matrix.
+#[derive(Debug, Queryable)]
+pub struct NonDirectMessageChannel {
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// The ID of the Rocket.Chat channel
+    pub rocketchat_channel_id: String,
+    /// created timestamp
+    pub created_at: String,
+}
+
+/// A new `NonDirectMessageChannel`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "non_direct_message_channels"]
+pub struct NewNonDirectMessageChannel<'a> {
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ID of the Rocket.Chat channel
+    pub rocketchat_channel_id: &'a str,
+}
+
+impl NonDirectMessageChannel {
+    /// Insert a `NonDirectMessageChannel`, an existing entry for the channel is replaced.
+    pub fn insert(connection: &SqliteConnection, new_non_direct_message_channel: &NewNonDirectMessageChannel) -> Result<()> {
+        diesel::replace_into(non_direct_message_channels::table)
+            .values(new_non_direct_message_channel)
+            .execute(connection)
+            .chain_err(|| ErrorKind::DBInsertError)?;
+        Ok(())
+    }
+
+    /// Returns true if the channel was found not to be a direct message less than the given number
+    /// of seconds ago.
+    pub fn is_known(
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_channel_id: &str,
+        max_age_in_seconds: i64,
+    ) -> Result<bool> {
+        let non_direct_message_channels: i64 = non_direct_message_channels::table
+            .filter(
+                non_direct_message_channels::rocketchat_server_id
+                    .eq(rocketchat_server_id)
+                    .and(non_direct_message_channels::rocketchat_channel_id.eq(rocketchat_channel_id)),
+            )
+            .filter(sql::<Bool>(&format!("created_at >= datetime('now', '-{} seconds')", max_age_in_seconds)))
+            .count()
+            .get_result(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(non_direct_message_channels > 0)
+    }
+
+    /// Delete all `NonDirectMessageChannel`s of a Rocket.Chat server.
+    pub fn delete_by_rocketchat_server_id(connection: &SqliteConnection, rocketchat_server_id: &str) -> Result<()> {
+        let non_direct_message_channels = non_direct_message_channels::table
+            .filter(non_direct_message_channels::rocketchat_server_id.eq(rocketchat_server_id));
+        diesel::delete(non_direct_message_channels).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+        Ok(())
+    }
+}
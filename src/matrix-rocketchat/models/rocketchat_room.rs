@@ -1,12 +1,13 @@
 use std::convert::TryFrom;
 
+use diesel::sqlite::SqliteConnection;
 use ruma_identifiers::{RoomAliasId, RoomId, UserId};
 use slog::Logger;
 
 use api::{MatrixApi, RocketchatApi};
 use config::Config;
 use errors::*;
-use models::Room;
+use models::{BridgedRoom, NewBridgedRoom, Room};
 
 /// A channel or group on a Rocket.Chat server.
 pub struct RocketchatRoom<'a> {
@@ -67,21 +68,32 @@ impl<'a> RocketchatRoom<'a> {
     /// homeserver and manages the rooms virtual users.
     pub fn bridge(
         &self,
+        connection: &SqliteConnection,
         rocketchat_api: &RocketchatApi,
         name: &Option<String>,
+        kind: &str,
         userlist: &[String],
-        creator_id: &UserId,
         invited_user_id: &UserId,
     ) -> Result<RoomId> {
         debug!(self.logger, "Briding new room, Rocket.Chat channel/group: {}", name.clone().unwrap_or_default());
 
+        let creator_id = self.config.matrix_bot_user_id()?;
         let matrix_room_alias = self.build_room_alias_name();
         let alias = Some(matrix_room_alias);
-        let room_id = Room::create(self.matrix_api, alias, name, creator_id, invited_user_id)?;
+        let room_id = Room::create(self.matrix_api, alias, name, &creator_id, invited_user_id)?;
         let matrix_room_alias_id = self.build_room_alias_id()?;
         let alias_id = Some(matrix_room_alias_id);
         self.matrix_api.put_canonical_room_alias(room_id.clone(), alias_id)?;
 
+        let new_bridged_room = NewBridgedRoom {
+            matrix_room_id: &room_id,
+            rocketchat_server_id: self.server_id,
+            rocketchat_channel_id: &self.id,
+            kind: Some(kind),
+            created_by: Some(invited_user_id),
+        };
+        BridgedRoom::insert(connection, &new_bridged_room)?;
+
         let room = Room::new(self.config, self.logger, self.matrix_api, room_id.clone());
         room.join_all_rocketchat_users(rocketchat_api, userlist, self.server_id)?;
 
@@ -89,10 +101,8 @@ impl<'a> RocketchatRoom<'a> {
     }
 
     /// Indicates if the channel or group is bridged for a given user.
-    pub fn is_bridged_for_user(&self, user_id: &UserId) -> Result<bool> {
-        let room_alias_id = self.build_room_alias_id()?;
-
-        match self.matrix_api.get_room_alias(room_alias_id)? {
+    pub fn is_bridged_for_user(&self, connection: &SqliteConnection, user_id: &UserId) -> Result<bool> {
+        match self.matrix_id(connection)? {
             Some(room_id) => {
                 let room = Room::new(self.config, self.logger, self.matrix_api, room_id);
                 let is_user_in_room = room.user_ids(None)?.iter().any(|id| id == user_id);
@@ -117,8 +127,8 @@ impl<'a> RocketchatRoom<'a> {
     }
 
     /// Gets the Matrix room ID for a Rocket.Chat channel or group ID and a Rocket.Chat server.
-    pub fn matrix_id(&self) -> Result<Option<RoomId>> {
-        let room_alias_id = self.build_room_alias_id()?;
-        self.matrix_api.get_room_alias(room_alias_id)
+    pub fn matrix_id(&self, connection: &SqliteConnection) -> Result<Option<RoomId>> {
+        let bridged_room = BridgedRoom::find_by_rocketchat_channel_id(connection, self.server_id, &self.id)?;
+        Ok(bridged_room.map(|bridged_room| bridged_room.matrix_room_id))
     }
 }
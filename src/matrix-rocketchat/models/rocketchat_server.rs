@@ -9,8 +9,11 @@ use api::{MatrixApi, RocketchatApi};
 use config::Config;
 use errors::*;
 use handlers::matrix::CommandHandler;
-use models::schema::{rocketchat_servers, users_on_rocketchat_servers};
-use models::{Room, UserOnRocketchatServer};
+use models::schema::{
+    bridged_messages, bridged_reactions, direct_message_members, direct_message_receivers, non_direct_message_channels,
+    rocketchat_servers, users_on_rocketchat_servers, virtual_user_avatars,
+};
+use models::{NonDirectMessageChannel, Room, UserOnRocketchatServer};
 
 /// A Rocket.Chat server.
 #[derive(Associations, Debug, Identifiable, Queryable)]
@@ -54,6 +57,20 @@ pub struct Credentials {
     pub rocketchat_url: String,
 }
 
+/// Credentials to login on the Rocket.Chat server with a personal access token instead of a
+/// password. The `user_id` is used to find the corresponding matrix user.
+#[derive(Serialize, Deserialize)]
+pub struct TokenCredentials {
+    /// The users unique id on the Matrix homeserver
+    pub user_id: UserId,
+    /// The users unique id on the Rocket.Chat server
+    pub rocketchat_user_id: String,
+    /// The personal access token that was created on the Rocket.Chat server
+    pub personal_access_token: String,
+    /// The URL of the Rocket.Chat server on which the user wants to login
+    pub rocketchat_url: String,
+}
+
 impl RocketchatServer {
     /// Insert a `RocketchatServer`.
     pub fn insert(connection: &SqliteConnection, new_rocketchat_server: &NewRocketchatServer) -> Result<RocketchatServer> {
@@ -127,14 +144,10 @@ impl RocketchatServer {
         let rocketchat_api = RocketchatApi::new(self.rocketchat_url.clone(), logger.clone())?;
 
         let (user_id, auth_token) = rocketchat_api.login(&credentials.rocketchat_username, &credentials.password)?;
-        user_on_rocketchat_server.set_credentials(connection, Some(user_id.clone()), Some(auth_token.clone()))?;
+        self.store_credentials(connection, &mut user_on_rocketchat_server, user_id, auth_token)?;
 
         if let Some(room_id) = admin_room_id {
-            let room = Room::new(config, logger, matrix_api, room_id.clone());
-            let bot_user_id = config.matrix_bot_user_id()?;
-            let as_url = config.as_url.clone();
-            let message = CommandHandler::build_help_message(connection, &room, as_url, &credentials.user_id)?;
-            matrix_api.send_text_message(room_id, bot_user_id, message)?;
+            RocketchatServer::send_help_message(config, connection, logger, matrix_api, &credentials.user_id, room_id)?;
         }
 
         info!(logger, "Successfully executed login command on Rocket.Chat server {}", self.rocketchat_url);
@@ -142,6 +155,71 @@ impl RocketchatServer {
         Ok(())
     }
 
+    /// Login on the Rocket.Chat server with a personal access token. The token is validated on the
+    /// Rocket.Chat server before the credentials are stored.
+    pub fn login_with_token(
+        &self,
+        config: &Config,
+        connection: &SqliteConnection,
+        logger: &Logger,
+        matrix_api: &MatrixApi,
+        credentials: &TokenCredentials,
+        admin_room_id: Option<RoomId>,
+    ) -> Result<()> {
+        let mut user_on_rocketchat_server = UserOnRocketchatServer::find(connection, &credentials.user_id, self.id.clone())?;
+        let rocketchat_api = RocketchatApi::new(self.rocketchat_url.clone(), logger.clone())?
+            .with_credentials(credentials.rocketchat_user_id.clone(), credentials.personal_access_token.clone());
+
+        // the Rocket.Chat server only returns the user when the token belongs to the user ID
+        rocketchat_api.me()?;
+        self.store_credentials(
+            connection,
+            &mut user_on_rocketchat_server,
+            credentials.rocketchat_user_id.clone(),
+            credentials.personal_access_token.clone(),
+        )?;
+
+        if let Some(room_id) = admin_room_id {
+            RocketchatServer::send_help_message(config, connection, logger, matrix_api, &credentials.user_id, room_id)?;
+        }
+
+        info!(logger, "Successfully executed login-token command on Rocket.Chat server {}", self.rocketchat_url);
+
+        Ok(())
+    }
+
+    /// Delete the Rocket.Chat server, the users that were connected to it and everything that was
+    /// stored for messages, reactions, avatars and direct message participants on the server. All
+    /// entries are deleted in one transaction. The server must not be used by direct message rooms
+    /// anymore.
+    pub fn delete(&self, connection: &SqliteConnection) -> Result<()> {
+        connection.transaction(|| {
+            let bridged_messages = bridged_messages::table.filter(bridged_messages::rocketchat_server_id.eq(&self.id));
+            diesel::delete(bridged_messages).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+            let bridged_reactions = bridged_reactions::table.filter(bridged_reactions::rocketchat_server_id.eq(&self.id));
+            diesel::delete(bridged_reactions).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+            let virtual_user_avatars =
+                virtual_user_avatars::table.filter(virtual_user_avatars::rocketchat_server_id.eq(&self.id));
+            diesel::delete(virtual_user_avatars).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+            let direct_message_members =
+                direct_message_members::table.filter(direct_message_members::rocketchat_server_id.eq(&self.id));
+            diesel::delete(direct_message_members).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+            let direct_message_receivers =
+                direct_message_receivers::table.filter(direct_message_receivers::rocketchat_server_id.eq(&self.id));
+            diesel::delete(direct_message_receivers).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+            let non_direct_message_channels =
+                non_direct_message_channels::table.filter(non_direct_message_channels::rocketchat_server_id.eq(&self.id));
+            diesel::delete(non_direct_message_channels).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+            let users_on_rocketchat_server =
+                users_on_rocketchat_servers::table.filter(users_on_rocketchat_servers::rocketchat_server_id.eq(&self.id));
+            diesel::delete(users_on_rocketchat_server).execute(connection).chain_err(|| ErrorKind::DBDeleteError)?;
+            diesel::delete(rocketchat_servers::table.find(&self.id))
+                .execute(connection)
+                .chain_err(|| ErrorKind::DBDeleteError)?;
+            Ok(())
+        })
+    }
+
     /// Get all users that are connected to this Rocket.Chat server.
     pub fn logged_in_users_on_rocketchat_server(&self, connection: &SqliteConnection) -> Result<Vec<UserOnRocketchatServer>> {
         let users_on_rocketchat_server: Vec<UserOnRocketchatServer> = users_on_rocketchat_servers::table
@@ -151,6 +229,34 @@ impl RocketchatServer {
             .chain_err(|| ErrorKind::DBSelectError)?;
         Ok(users_on_rocketchat_server)
     }
+
+    // Channels that were found not to be direct messages are checked again, because the user that
+    // logged in might participate in direct messages that could not be looked up before.
+    fn store_credentials(
+        &self,
+        connection: &SqliteConnection,
+        user_on_rocketchat_server: &mut UserOnRocketchatServer,
+        rocketchat_user_id: String,
+        rocketchat_auth_token: String,
+    ) -> Result<()> {
+        user_on_rocketchat_server.set_credentials(connection, Some(rocketchat_user_id), Some(rocketchat_auth_token))?;
+        NonDirectMessageChannel::delete_by_rocketchat_server_id(connection, &self.id)
+    }
+
+    fn send_help_message(
+        config: &Config,
+        connection: &SqliteConnection,
+        logger: &Logger,
+        matrix_api: &MatrixApi,
+        user_id: &UserId,
+        room_id: RoomId,
+    ) -> Result<()> {
+        let room = Room::new(config, logger, matrix_api, room_id.clone());
+        let bot_user_id = config.matrix_bot_user_id()?;
+        let message = CommandHandler::build_help_message(connection, &room, config.as_url.clone(), user_id)?;
+        matrix_api.send_text_message(room_id, bot_user_id, message)?;
+        Ok(())
+    }
 }
 
 impl Key for RocketchatServer {
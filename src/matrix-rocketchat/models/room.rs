@@ -1,6 +1,4 @@
-use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
@@ -9,20 +7,19 @@ use ruma_events::room::member::MembershipState;
 use ruma_identifiers::{RoomAliasId, RoomId, UserId};
 use slog::Logger;
 
+use api::rocketchat::User;
 use api::{MatrixApi, RocketchatApi};
 use config::Config;
 use errors::*;
 use i18n::*;
-use models::{RocketchatServer, UserOnRocketchatServer, VirtualUser};
+use models::{
+    AdminRoom, BridgedRoom, DirectMessageReceiver, DirectMessageRoom, NewDirectMessageReceiver, NewDirectMessageRoom,
+    RocketchatServer, UserOnRocketchatServer, VirtualUser,
+};
 
 /// The delay in milliseconds between two API requests (to not DOS the server)
 pub const API_QUERY_DELAY: u64 = 500;
 
-lazy_static! {
-    /// Direct room cache
-    static ref DM_ROOMS: Mutex<HashMap<(String, String), RoomId>> = { Mutex::new(HashMap::new()) };
-}
-
 /// A room that is managed by the application service. This can be either a bridged room or an
 /// admin room.
 pub struct Room<'a> {
@@ -57,11 +54,70 @@ impl<'a> Room<'a> {
         Ok(room_id)
     }
 
+    /// Create a direct message room on the Matrix homeserver. The room is created by the virtual
+    /// user of the Rocket.Chat user that is on the other end of the direct message.
+    pub fn create_dm(
+        config: &'a Config,
+        connection: &SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+        rocketchat_server_id: &str,
+        channel_id: String,
+        sender_id: &UserId,
+        sender_name: &str,
+        receiver_id: &UserId,
+    ) -> Result<Room<'a>> {
+        let room_display_name_suffix = t!(["defaults", "direct_message_room_display_name_suffix"]).l(DEFAULT_LANGUAGE);
+        let display_name = Some(format!("{} {}", sender_name, room_display_name_suffix));
+        let room_id = Room::create(matrix_api, None, &display_name, sender_id, receiver_id)?;
+
+        // invite the bot user into the direct message room to be able to read the room state
+        // the bot will leave as soon as the AS gets the join event
+        let invitee_id = config.matrix_bot_user_id()?;
+        matrix_api.invite(room_id.clone(), invitee_id, sender_id.clone())?;
+        info!(logger, "Direct message room {} successfully created", &room_id);
+
+        let room = Room::new(config, logger, matrix_api, room_id);
+        room.register_dm(connection, rocketchat_server_id, channel_id, sender_id.clone(), &[receiver_id.clone()])?;
+        Ok(room)
+    }
+
+    /// Create a room on the Matrix homeserver for a direct message with more than two
+    /// participants. The members are added with `sync_dm_members`.
+    pub fn create_group_dm(
+        config: &'a Config,
+        connection: &SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+        rocketchat_server_id: &str,
+        channel_id: String,
+        creator_id: &UserId,
+        usernames: &[String],
+        receiver_ids: &[UserId],
+    ) -> Result<Room<'a>> {
+        let room_display_name_suffix = t!(["defaults", "direct_message_room_display_name_suffix"]).l(DEFAULT_LANGUAGE);
+        let display_name = Some(format!("{} {}", usernames.join(", "), room_display_name_suffix));
+        let room_id = matrix_api.create_room(display_name, None, creator_id)?;
+        matrix_api.set_default_powerlevels(room_id.clone(), creator_id.clone())?;
+
+        // invite the bot user into the direct message room to be able to read the room state
+        // the bot will leave as soon as the AS gets the join event
+        let invitee_id = config.matrix_bot_user_id()?;
+        matrix_api.invite(room_id.clone(), invitee_id, creator_id.clone())?;
+        info!(logger, "Group direct message room {} successfully created", &room_id);
+
+        let room = Room::new(config, logger, matrix_api, room_id);
+        room.register_dm(connection, rocketchat_server_id, channel_id, creator_id.clone(), receiver_ids)?;
+        Ok(room)
+    }
+
     /// Get an existing direct message room.
     pub fn get_dm(
         config: &'a Config,
+        connection: &SqliteConnection,
         logger: &'a Logger,
         matrix_api: &'a MatrixApi,
+        rocketchat_server_id: &str,
         channel_id: String,
         sender_id: &UserId,
         receiver_id: &UserId,
@@ -71,27 +127,16 @@ impl<'a> Room<'a> {
             return Ok(None);
         }
 
-        match DM_ROOMS.lock() {
-            Ok(dm_rooms) => match dm_rooms.get(&(channel_id.clone(), receiver_id.to_string())) {
-                Some(room_id) => {
-                    debug!(logger, "Found room {} for receiver {} in cache", channel_id, receiver_id);
-                    let room = Room::new(config, logger, matrix_api, room_id.clone());
-                    return Ok(Some(room));
-                }
-                None => {
-                    debug!(logger, "Room {} for receiver {} not found in cache", channel_id, receiver_id);
-                }
-            },
-            Err(err) => {
-                warn!(logger, "Could lock DM cache to get room {} with receiver {}: {}", channel_id, receiver_id, err);
-            }
+        let cached_room_id = Room::get_cached_dm_room_id(connection, logger, rocketchat_server_id, &channel_id, receiver_id)?;
+        if let Some(room_id) = cached_room_id {
+            return Ok(Some(Room::new(config, logger, matrix_api, room_id)));
         }
 
         for room_id in matrix_api.get_joined_rooms(sender_id.clone())? {
             let room = Room::new(config, logger, matrix_api, room_id);
             let user_ids = room.user_ids(Some(sender_id.clone()))?;
             if user_ids.iter().all(|id| id == sender_id || id == receiver_id) {
-                room.add_to_cache(channel_id, &receiver_id);
+                room.add_to_cache(connection, rocketchat_server_id, channel_id, &receiver_id)?;
                 return Ok(Some(room));
             }
         }
@@ -99,6 +144,37 @@ impl<'a> Room<'a> {
         Ok(None)
     }
 
+    /// Get an existing direct message room with more than two participants. There is only one
+    /// room for all participants, it is looked up by the stored direct message channel.
+    pub fn get_group_dm(
+        config: &'a Config,
+        connection: &SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+        rocketchat_server_id: &str,
+        channel_id: &str,
+    ) -> Result<Option<Room<'a>>> {
+        let direct_message_rooms =
+            DirectMessageRoom::find_by_rocketchat_channel_id(connection, rocketchat_server_id, channel_id)?;
+        Ok(direct_message_rooms
+            .into_iter()
+            .next()
+            .map(|direct_message_room| Room::new(config, logger, matrix_api, direct_message_room.matrix_room_id)))
+    }
+
+    /// Get the admin room in which a user is connected to a Rocket.Chat server.
+    pub fn get_admin_room(
+        config: &'a Config,
+        connection: &SqliteConnection,
+        logger: &'a Logger,
+        matrix_api: &'a MatrixApi,
+        user_id: &UserId,
+        server: &RocketchatServer,
+    ) -> Result<Option<Room<'a>>> {
+        let admin_room = AdminRoom::find_by_matrix_user_id(connection, user_id, &server.id)?;
+        Ok(admin_room.map(|admin_room| Room::new(config, logger, matrix_api, admin_room.matrix_room_id)))
+    }
+
     /// Bridges a room that is already bridged (for other users) for a new user.
     pub fn bridge_for_user(&self, user_id: UserId, rocketchat_channel_name: String) -> Result<()> {
         debug!(self.logger, "Briding existing room, Rocket.Chat channel: {}", rocketchat_channel_name);
@@ -117,16 +193,16 @@ impl<'a> Room<'a> {
 
     /// Get the Rocket.Chat server this room is connected to, if any.
     pub fn rocketchat_server(&self, connection: &SqliteConnection) -> Result<Option<RocketchatServer>> {
-        let alias = self.matrix_api.get_room_canonical_alias(self.id.clone())?.map(|a| a.to_string()).unwrap_or_default();
-        let rocketchat_server_id = alias.split('#').nth(2).unwrap_or_default();
-        RocketchatServer::find_by_id(connection, rocketchat_server_id)
+        match BridgedRoom::find_by_matrix_room_id(connection, &self.id)? {
+            Some(bridged_room) => RocketchatServer::find_by_id(connection, &bridged_room.rocketchat_server_id),
+            None => Ok(None),
+        }
     }
 
     /// Get the Rocket.Chat server for an admin room.
     pub fn rocketchat_server_for_admin_room(&self, connection: &SqliteConnection) -> Result<Option<RocketchatServer>> {
-        let rocketchat_server_url = self.matrix_api.get_room_topic(self.id.clone())?;
-        match rocketchat_server_url {
-            Some(rocketchat_server_url) => RocketchatServer::find_by_url(connection, &rocketchat_server_url),
+        match AdminRoom::find_by_matrix_room_id(connection, &self.id)? {
+            Some(admin_room) => RocketchatServer::find_by_id(connection, &admin_room.rocketchat_server_id),
             None => Ok(None),
         }
     }
@@ -151,13 +227,22 @@ impl<'a> Room<'a> {
     }
 
     /// Check if the room is a direct message room.
-    pub fn is_direct_message_room(&self) -> Result<bool> {
+    pub fn is_direct_message_room(&self, connection: &SqliteConnection) -> Result<bool> {
+        if self.registered_dm(connection)?.is_some() {
+            return Ok(true);
+        }
+
         let room_creator_id = self.matrix_api.get_room_creator(self.id.clone())?;
         Ok(self.config.is_application_service_virtual_user(&room_creator_id))
     }
 
     /// Checks if a room is an admin room.
-    pub fn is_admin_room(&self) -> Result<bool> {
+    pub fn is_admin_room(&self, connection: &SqliteConnection) -> Result<bool> {
+        // direct message rooms that were started on Matrix are created by a Matrix user as well
+        if self.registered_dm(connection)?.is_some() {
+            return Ok(false);
+        }
+
         // it cannot be an admin room if the bot user does not have access to it
         if !self.is_accessible_by_bot()? {
             return Ok(false);
@@ -172,37 +257,20 @@ impl<'a> Room<'a> {
     }
 
     /// Gets the Rocket.Chat channel id for a room that is bridged to Matrix.
-    pub fn rocketchat_channel_id(&self) -> Result<Option<String>> {
-        let room_canonical_alias = match self.matrix_api.get_room_canonical_alias(self.id.clone())? {
-            Some(room_canonical_alias) => room_canonical_alias.alias().to_string(),
-            None => return Ok(None),
-        };
-
-        let rocketchat_channel_id = room_canonical_alias.split('#').nth(2).unwrap_or_default();
-        Ok(Some(rocketchat_channel_id.to_string()))
+    pub fn rocketchat_channel_id(&self, connection: &SqliteConnection) -> Result<Option<String>> {
+        let bridged_room = BridgedRoom::find_by_matrix_room_id(connection, &self.id)?;
+        Ok(bridged_room.map(|bridged_room| bridged_room.rocketchat_channel_id))
     }
 
     /// Checks if an admin room is connected to a Rocket.Chat server.
     pub fn is_connected(&self, connection: &SqliteConnection) -> Result<bool> {
-        match self.matrix_api.get_room_topic(self.id.clone())? {
-            Some(rocketchat_server_url) => {
-                let server = RocketchatServer::find_by_url(connection, &rocketchat_server_url)?;
-                Ok(server.is_some())
-            }
-            None => Ok(false),
-        }
+        Ok(self.rocketchat_server_for_admin_room(connection)?.is_some())
     }
 
-    /// Find the Matrix user in a direct message room
-    pub fn direct_message_matrix_user(&self) -> Result<Option<UserId>> {
-        let room_creator_id = self.matrix_api.get_room_creator(self.id.clone())?;
-        let user_ids = self.user_ids(Some(room_creator_id))?;
-        if user_ids.len() > 2 {
-            bail_error!(ErrorKind::GettingMatrixUserForDirectMessageRoomError);
-        }
-
-        let user_id = user_ids.into_iter().find(|id| !self.config.is_application_service_virtual_user(id));
-        Ok(user_id)
+    /// Find the Matrix users in a direct message room
+    pub fn direct_message_matrix_users(&self, connection: &SqliteConnection) -> Result<Vec<UserId>> {
+        let user_ids = self.user_ids(Some(self.query_user_id(connection)?))?;
+        Ok(user_ids.into_iter().filter(|id| !self.config.is_application_service_virtual_user(id)).collect())
     }
 
     /// Determine if the bot user has access to a room.
@@ -211,17 +279,29 @@ impl<'a> Room<'a> {
     }
 
     /// Find the Rocket.Chat server and channel for a direct message room on Matrix.
-    /// It uses the virtual users ID for determine which server is used and queries the Rocket.Chat
-    /// server to find the matching direct message room.
+    /// The stored direct message channel is used if the room is known. Otherwise the virtual users
+    /// ID is used to determine which server is used and the Rocket.Chat server is queried to find
+    /// the matching direct message room, which is stored for subsequent messages.
     /// This is done to avoid the usage of aliases in direct message rooms.
     pub fn rocketchat_for_direct_room(&self, conn: &SqliteConnection) -> Result<Option<(RocketchatServer, String)>> {
-        if !self.is_direct_message_room()? {
+        if let Some(direct_message_room) = self.registered_dm(conn)? {
+            let user_ids = self.user_ids(Some(direct_message_room.matrix_query_user_id.clone()))?;
+            if !user_ids.iter().any(|id| self.config.is_application_service_virtual_user(id)) {
+                debug!(self.logger, "No existing virtual user found for this direct message");
+                return Ok(None);
+            }
+
+            let server = RocketchatServer::find_by_id(conn, &direct_message_room.rocketchat_server_id)?;
+            return Ok(server.map(|server| (server, direct_message_room.rocketchat_channel_id)));
+        }
+
+        if !self.is_direct_message_room(conn)? {
             debug!(self.logger, "Room is not a direct message room, will not continue to find a matching DM user.");
             return Ok(None);
         }
 
         let room_creator = self.matrix_api.get_room_creator(self.id.clone())?;
-        let user_ids = self.user_ids(Some(room_creator))?;
+        let user_ids = self.user_ids(Some(room_creator.clone()))?;
 
         let user_matrix_id = match user_ids.iter().find(|id| !self.config.is_application_service_virtual_user(id)) {
             Some(user_id) => user_id,
@@ -260,7 +340,9 @@ impl<'a> Room<'a> {
         let direct_message_channels = rocketchat_api.dm_list()?;
         for direct_message_channel in direct_message_channels {
             if direct_message_channel.id.to_lowercase().contains(&virtual_user_id) {
-                return Ok(Some((server, direct_message_channel.id.clone())));
+                let receiver_ids = [user_matrix_id.clone()];
+                self.register_dm(conn, &server.id, direct_message_channel.id.clone(), room_creator, &receiver_ids)?;
+                return Ok(Some((server, direct_message_channel.id)));
             }
         }
 
@@ -307,6 +389,59 @@ impl<'a> Room<'a> {
         Ok(())
     }
 
+    /// Keep the members of a direct message room with more than two participants in sync with
+    /// the Rocket.Chat channel. The virtual users of all Rocket.Chat users join the room and the
+    /// Matrix users are invited, virtual users of Rocket.Chat users that left the channel leave it.
+    pub fn sync_dm_members(
+        &self,
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        rocketchat_users: &[User],
+        matrix_user_ids: &[UserId],
+    ) -> Result<()> {
+        let query_user_id = self.query_user_id(connection)?;
+        let mut member_ids = Vec::new();
+        for member_event in self.matrix_api.get_room_members(self.id.clone(), Some(query_user_id.clone()))? {
+            match member_event.content.membership {
+                MembershipState::Join | MembershipState::Invite => {
+                    let state_key = member_event.state_key.clone();
+                    let user_id = UserId::try_from(state_key.as_ref()).chain_err(|| ErrorKind::InvalidUserId(state_key))?;
+                    member_ids.push(user_id)
+                }
+                _ => continue,
+            }
+        }
+
+        let virtual_user = VirtualUser::new(self.config, self.logger, self.matrix_api);
+        let mut virtual_user_ids = Vec::new();
+        for rocketchat_user in rocketchat_users {
+            let user_id = virtual_user.build_user_id(&rocketchat_user.id, rocketchat_server_id)?;
+            if !member_ids.contains(&user_id) {
+                virtual_user.find_or_register(rocketchat_server_id, &rocketchat_user.id, &rocketchat_user.username)?;
+                debug!(self.logger, "Adding virtual user {} to direct message room {}", user_id, self.id);
+                self.matrix_api.invite(self.id.clone(), user_id.clone(), query_user_id.clone())?;
+                self.matrix_api.join(self.id.clone(), user_id.clone())?;
+            }
+            virtual_user_ids.push(user_id);
+        }
+
+        for matrix_user_id in matrix_user_ids.iter().filter(|id| !member_ids.contains(id)) {
+            debug!(self.logger, "Inviting user {} into direct message room {}", matrix_user_id, self.id);
+            self.matrix_api.invite(self.id.clone(), matrix_user_id.clone(), query_user_id.clone())?;
+        }
+
+        for user_id in member_ids {
+            let left_channel =
+                self.config.is_application_service_virtual_user(&user_id) && !virtual_user_ids.contains(&user_id);
+            if left_channel && user_id != query_user_id {
+                debug!(self.logger, "Virtual user {} left the direct message, leaving room {}", user_id, self.id);
+                self.matrix_api.leave_room(self.id.clone(), user_id)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get all aliases fro a room.
     pub fn aliases(&self) -> Result<Vec<RoomAliasId>> {
         let bot_user_id = self.config.matrix_bot_user_id()?;
@@ -325,27 +460,92 @@ impl<'a> Room<'a> {
         connection: &SqliteConnection,
         rocketchat_server_id: String,
     ) -> Result<Vec<UserOnRocketchatServer>> {
-        let room_creator = self.matrix_api.get_room_creator(self.id.clone())?;
         let user_ids = self
-            .user_ids(Some(room_creator))?
+            .user_ids(Some(self.query_user_id(connection)?))?
             .into_iter()
             .filter(|id| !self.config.is_application_service_virtual_user(id))
             .collect();
         UserOnRocketchatServer::find_by_matrix_user_ids(connection, user_ids, rocketchat_server_id)
     }
 
-    /// Add a room to the cache.
-    /// This will speed-up future direct messages because the direct message room lookup is done via
-    /// cache instead of going through the users rooms.
-    fn add_to_cache(&self, channel_id: String, receiver_id: &UserId) {
-        match DM_ROOMS.lock() {
-            Ok(mut dm_rooms) => {
-                debug!(self.logger, "Adding DM room {} with receiver {} to cache", channel_id, receiver_id);
-                dm_rooms.insert((channel_id, receiver_id.to_string()), self.id.clone());
+    /// A user of the application service that is a member of the room and can be used to query
+    /// the room members. This is the room creator, except for direct message rooms that were
+    /// started on Matrix, there the virtual user that was invited into the room is used.
+    pub fn query_user_id(&self, connection: &SqliteConnection) -> Result<UserId> {
+        match self.registered_dm(connection)? {
+            Some(direct_message_room) => Ok(direct_message_room.matrix_query_user_id),
+            None => self.matrix_api.get_room_creator(self.id.clone()),
+        }
+    }
+
+    /// Store the Rocket.Chat channel of a direct message room and the user that is used to query
+    /// the room. Messages of the Rocket.Chat channel are forwarded to it for all receivers.
+    pub fn register_dm(
+        &self,
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        channel_id: String,
+        query_user_id: UserId,
+        receiver_ids: &[UserId],
+    ) -> Result<()> {
+        if self.registered_dm(connection)?.is_none() {
+            debug!(self.logger, "Registering DM room {} for channel {}", self.id, channel_id);
+            let new_direct_message_room = NewDirectMessageRoom {
+                matrix_room_id: &self.id,
+                rocketchat_server_id,
+                rocketchat_channel_id: &channel_id,
+                matrix_query_user_id: &query_user_id,
+            };
+            DirectMessageRoom::insert(connection, &new_direct_message_room)?;
+        }
+
+        for receiver_id in receiver_ids {
+            self.add_to_cache(connection, rocketchat_server_id, channel_id.clone(), receiver_id)?;
+        }
+
+        Ok(())
+    }
+
+    fn registered_dm(&self, connection: &SqliteConnection) -> Result<Option<DirectMessageRoom>> {
+        DirectMessageRoom::find_by_matrix_room_id(connection, &self.id)
+    }
+
+    fn get_cached_dm_room_id(
+        connection: &SqliteConnection,
+        logger: &Logger,
+        rocketchat_server_id: &str,
+        channel_id: &str,
+        receiver_id: &UserId,
+    ) -> Result<Option<RoomId>> {
+        match DirectMessageReceiver::find(connection, rocketchat_server_id, channel_id, receiver_id)? {
+            Some(direct_message_receiver) => {
+                debug!(logger, "Found room {} for receiver {} in cache", channel_id, receiver_id);
+                Ok(Some(direct_message_receiver.matrix_room_id))
             }
-            Err(err) => {
-                warn!(self.logger, "Could not add DM room {} with receiver {} to cache: {}", channel_id, receiver_id, err);
+            None => {
+                debug!(logger, "Room {} for receiver {} not found in cache", channel_id, receiver_id);
+                Ok(None)
             }
         }
     }
+
+    /// Add a room to the cache, which is stored in the database to survive restarts.
+    /// This will speed-up future direct messages because the direct message room lookup is done via
+    /// cache instead of going through the users rooms.
+    fn add_to_cache(
+        &self,
+        connection: &SqliteConnection,
+        rocketchat_server_id: &str,
+        channel_id: String,
+        receiver_id: &UserId,
+    ) -> Result<()> {
+        debug!(self.logger, "Adding DM room {} with receiver {} to cache", channel_id, receiver_id);
+        let new_direct_message_receiver = NewDirectMessageReceiver {
+            rocketchat_server_id,
+            rocketchat_channel_id: &channel_id,
+            matrix_user_id: receiver_id,
+            matrix_room_id: &self.id,
+        };
+        DirectMessageReceiver::upsert(connection, &new_direct_message_receiver)
+    }
 }
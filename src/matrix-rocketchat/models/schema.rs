@@ -1,5 +1,96 @@
 #![allow(missing_docs)]
 
+table! {
+    admin_rooms (matrix_room_id) {
+        matrix_room_id -> Text,
+        matrix_user_id -> Text,
+        rocketchat_server_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    bridged_messages (matrix_event_id) {
+        matrix_event_id -> Text,
+        matrix_room_id -> Text,
+        rocketchat_message_id -> Text,
+        rocketchat_room_id -> Text,
+        rocketchat_server_id -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        rocketchat_thread_id -> Nullable<Text>,
+    }
+}
+
+table! {
+    bridged_reactions (matrix_event_id) {
+        matrix_event_id -> Text,
+        matrix_room_id -> Text,
+        rocketchat_message_id -> Text,
+        rocketchat_server_id -> Text,
+        rocketchat_user_id -> Text,
+        emoji -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    bridged_rooms (matrix_room_id) {
+        matrix_room_id -> Text,
+        rocketchat_server_id -> Text,
+        rocketchat_channel_id -> Text,
+        kind -> Nullable<Text>,
+        created_by -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    completed_backfills (name) {
+        name -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    direct_message_members (rocketchat_server_id, rocketchat_channel_id, rocketchat_user_id) {
+        rocketchat_server_id -> Text,
+        rocketchat_channel_id -> Text,
+        rocketchat_user_id -> Text,
+        rocketchat_username -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    direct_message_receivers (rocketchat_server_id, rocketchat_channel_id, matrix_user_id) {
+        rocketchat_server_id -> Text,
+        rocketchat_channel_id -> Text,
+        matrix_user_id -> Text,
+        matrix_room_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    direct_message_rooms (matrix_room_id) {
+        matrix_room_id -> Text,
+        rocketchat_server_id -> Text,
+        rocketchat_channel_id -> Text,
+        matrix_query_user_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    non_direct_message_channels (rocketchat_server_id, rocketchat_channel_id) {
+        rocketchat_server_id -> Text,
+        rocketchat_channel_id -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     rocketchat_servers (id) {
         id -> Text,
@@ -21,3 +112,14 @@ table! {
         updated_at -> Timestamp,
     }
 }
+
+table! {
+    virtual_user_avatars (matrix_user_id) {
+        matrix_user_id -> Text,
+        rocketchat_server_id -> Text,
+        avatar_etag -> Text,
+        avatar_url -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
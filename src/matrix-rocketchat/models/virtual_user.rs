@@ -1,11 +1,13 @@
 use std::convert::TryFrom;
 
+use diesel::sqlite::SqliteConnection;
 use ruma_identifiers::UserId;
 use slog::Logger;
 
-use api::MatrixApi;
+use api::{MatrixApi, RocketchatApi};
 use config::Config;
 use errors::*;
+use models::{NewVirtualUserAvatar, VirtualUserAvatar};
 
 /// Provides helper methods to manage virtual users.
 pub struct VirtualUser<'a> {
@@ -56,6 +58,42 @@ impl<'a> VirtualUser<'a> {
         Ok(user_id)
     }
 
+    /// Set the avatar of the Rocket.Chat user as avatar of the virtual user. The avatar is only
+    /// uploaded to the homeserver if it changed since the last sync.
+    pub fn sync_avatar(
+        &self,
+        connection: &SqliteConnection,
+        rocketchat_api: &RocketchatApi,
+        rocketchat_server_id: &str,
+        user_id: &UserId,
+        rocketchat_user_name: &str,
+    ) -> Result<()> {
+        let virtual_user_avatar = VirtualUserAvatar::find_by_matrix_user_id(connection, user_id)?;
+        let known_etag = virtual_user_avatar.as_ref().map(|virtual_user_avatar| virtual_user_avatar.avatar_etag.as_str());
+
+        let avatar = match rocketchat_api.users_avatar(rocketchat_user_name, known_etag)? {
+            Some(avatar) => avatar,
+            None => return Ok(()),
+        };
+
+        // not all Rocket.Chat versions send an ETag, in that case the content is compared
+        let etag = avatar.etag.clone().unwrap_or_else(|| format!("{:x}", content_hash(&avatar.data)));
+        if known_etag == Some(etag.as_str()) {
+            debug!(self.logger, "Avatar of user {} did not change", user_id);
+            return Ok(());
+        }
+
+        debug!(self.logger, "Avatar of user {} changed, uploading the new avatar", user_id);
+        let avatar_url = self.matrix_api.upload(avatar.data, avatar.content_type)?;
+        self.matrix_api.set_avatar_url(user_id.clone(), avatar_url.clone())?;
+
+        let new_virtual_user_avatar =
+            NewVirtualUserAvatar { matrix_user_id: user_id, rocketchat_server_id, avatar_etag: &etag, avatar_url: &avatar_url };
+        VirtualUserAvatar::upsert(connection, &new_virtual_user_avatar)?;
+
+        Ok(())
+    }
+
     /// Build the matrix user ID based on the Rocket.Chat user ID and the Rocket.Chat server ID.
     pub fn build_user_id(&self, rocketchat_user_id: &str, rocketchat_server_id: &str) -> Result<UserId> {
         let user_id_local_part = format!("{}_{}_{}", self.config.sender_localpart, rocketchat_server_id, rocketchat_user_id);
@@ -74,3 +112,12 @@ impl<'a> VirtualUser<'a> {
         (server_id, rocketchat_user_id.to_owned())
     }
 }
+
+/// FNV-1a hash of the content. The hash is stored in the database, so unlike the hasher of the
+/// standard library it has to be stable across Rust releases.
+fn content_hash(data: &[u8]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    data.iter().fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME))
+}
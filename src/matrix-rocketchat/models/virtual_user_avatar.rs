@@ -0,0 +1,79 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use ruma_identifiers::UserId;
+
+use errors::*;
+use models::schema::virtual_user_avatars;
+
+/// The avatar that was set for a virtual user, based on the avatar of the Rocket.Chat user.
+#[derive(Associations, Debug, Identifiable, Queryable)]
+#[primary_key(matrix_user_id)]
+#[table_name = "virtual_user_avatars"]
+pub struct VirtualUserAvatar {
+    /// The Matrix ID of the virtual user
+    pub matrix_user_id: UserId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: String,
+    /// The ETag of the Rocket.Chat avatar or a hash of its content if the server didn't send one
+    pub avatar_etag: String,
+    /// The MXC URL of the avatar on the Matrix homeserver
+    pub avatar_url: String,
+    /// created timestamp
+    pub created_at: String,
+    /// updated timestamp
+    pub updated_at: String,
+}
+
+/// A new `VirtualUserAvatar`, not yet saved.
+#[derive(Insertable)]
+#[table_name = "virtual_user_avatars"]
+pub struct NewVirtualUserAvatar<'a> {
+    /// The Matrix ID of the virtual user
+    pub matrix_user_id: &'a UserId,
+    /// The unique id for the Rocket.Chat server
+    pub rocketchat_server_id: &'a str,
+    /// The ETag of the Rocket.Chat avatar or a hash of its content if the server didn't send one
+    pub avatar_etag: &'a str,
+    /// The MXC URL of the avatar on the Matrix homeserver
+    pub avatar_url: &'a str,
+}
+
+impl VirtualUserAvatar {
+    /// Insert or update a `VirtualUserAvatar`.
+    pub fn upsert(connection: &SqliteConnection, new_virtual_user_avatar: &NewVirtualUserAvatar) -> Result<VirtualUserAvatar> {
+        match VirtualUserAvatar::find_by_matrix_user_id(connection, new_virtual_user_avatar.matrix_user_id)? {
+            Some(_) => {
+                diesel::update(virtual_user_avatars::table.find(new_virtual_user_avatar.matrix_user_id))
+                    .set((
+                        virtual_user_avatars::avatar_etag.eq(new_virtual_user_avatar.avatar_etag),
+                        virtual_user_avatars::avatar_url.eq(new_virtual_user_avatar.avatar_url),
+                    ))
+                    .execute(connection)
+                    .chain_err(|| ErrorKind::DBUpdateError)?;
+            }
+            None => {
+                diesel::insert_into(virtual_user_avatars::table)
+                    .values(new_virtual_user_avatar)
+                    .execute(connection)
+                    .chain_err(|| ErrorKind::DBInsertError)?;
+            }
+        }
+
+        let virtual_user_avatar = virtual_user_avatars::table
+            .find(new_virtual_user_avatar.matrix_user_id)
+            .first(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(virtual_user_avatar)
+    }
+
+    /// Find the `VirtualUserAvatar` of a virtual user. Returns `None` if no avatar was set for the
+    /// user yet.
+    pub fn find_by_matrix_user_id(connection: &SqliteConnection, matrix_user_id: &UserId) -> Result<Option<VirtualUserAvatar>> {
+        let virtual_user_avatars = virtual_user_avatars::table
+            .filter(virtual_user_avatars::matrix_user_id.eq(matrix_user_id))
+            .load(connection)
+            .chain_err(|| ErrorKind::DBSelectError)?;
+        Ok(virtual_user_avatars.into_iter().next())
+    }
+}
@@ -4,17 +4,24 @@ use hyper_native_tls::NativeTlsServer;
 use iron::{Chain, Iron, Listening};
 use persistent::{State, Write};
 use router::Router;
+use ruma_identifiers::RoomId;
 use slog::Logger;
 
 use api::MatrixApi;
 use config::Config;
 use errors::*;
-use handlers::iron::{Rocketchat, RocketchatLogin, Transactions, Welcome};
-use log::IronLogger;
-use models::ConnectionPool;
+use handlers::iron::{Rocketchat, RocketchatLogin, Rooms, Transactions, Users, Welcome};
+use log::{self, IronLogger};
+use models::{
+    AdminRoom, BridgedRoom, CompletedBackfill, ConnectionPool, DirectMessageRoom, NewAdminRoom, NewBridgedRoom,
+    RocketchatServer, Room,
+};
 
 embed_migrations!("migrations");
 
+const ADMIN_ROOMS_BACKFILL: &str = "admin_rooms";
+const BRIDGED_ROOMS_BACKFILL: &str = "bridged_rooms";
+
 /// The application service server
 pub struct Server<'a> {
     /// Application service configuration
@@ -36,6 +43,12 @@ impl<'a> Server<'a> {
 
         let matrix_api = MatrixApi::new(self.config, self.logger.clone())?;
         self.setup_bot_user(matrix_api.as_ref())?;
+        if let Err(err) = self.backfill(matrix_api.as_ref(), BRIDGED_ROOMS_BACKFILL, Server::backfill_bridged_room) {
+            log::log_error(&self.logger, &err);
+        }
+        if let Err(err) = self.backfill(matrix_api.as_ref(), ADMIN_ROOMS_BACKFILL, Server::backfill_admin_room) {
+            log::log_error(&self.logger, &err);
+        }
 
         let router = self.setup_routes(matrix_api);
         let mut chain = Chain::new(router);
@@ -62,11 +75,38 @@ impl<'a> Server<'a> {
         listener.chain_err(|| ErrorKind::ServerStartupError).map_err(Error::from)
     }
 
+    /// Removes a Rocket.Chat server that is neither connected to an admin room nor bridged to a
+    /// Matrix room or used for direct messages anymore.
+    pub fn remove_rocketchat_server(&self, rocketchat_server_id: &str) -> Result<()> {
+        self.prepare_database()?;
+        let connection = SqliteConnection::establish(&self.config.database_url).chain_err(|| ErrorKind::DBConnectionError)?;
+
+        let server = match RocketchatServer::find_by_id(&connection, rocketchat_server_id)? {
+            Some(server) => server,
+            None => {
+                bail_error!(ErrorKind::RocketchatServerNotFound(rocketchat_server_id.to_string()));
+            }
+        };
+
+        if !AdminRoom::find_by_rocketchat_server_id(&connection, &server.id)?.is_empty()
+            || !BridgedRoom::find_by_rocketchat_server_id(&connection, &server.id)?.is_empty()
+            || !DirectMessageRoom::find_by_rocketchat_server_id(&connection, &server.id)?.is_empty()
+        {
+            bail_error!(ErrorKind::RocketchatServerInUse(server.id.clone()));
+        }
+
+        server.delete(&connection)?;
+        info!(self.logger, "Removed Rocket.Chat server {} ({})", server.id, server.rocketchat_url);
+        Ok(())
+    }
+
     fn setup_routes(&self, matrix_api: Box<MatrixApi>) -> Router {
         debug!(self.logger, "Setting up routes");
         let mut router = Router::new();
         router.get("/", Welcome {}, "welcome");
         router.put("/transactions/:txn_id", Transactions::chain(self.config.clone(), matrix_api.clone()), "transactions");
+        router.get("/users/:user_id", Users::chain(self.config.clone(), matrix_api.clone()), "users");
+        router.get("/rooms/:room_alias", Rooms::chain(self.config.clone(), matrix_api.clone()), "rooms");
         router.post("/rocketchat", Rocketchat::chain(self.config, matrix_api.clone()), "rocketchat");
         router.post("/rocketchat/login", RocketchatLogin { config: self.config.clone(), matrix_api }, "rocketchat_login");
         router
@@ -91,4 +131,102 @@ impl<'a> Server<'a> {
         info!(self.logger, "Bot user {} successfully registered", matrix_bot_user_id);
         Ok(())
     }
+
+    // Rooms that were bridged or connected before they were stored in the database are backfilled
+    // from the rooms that the bot user joined. A room that cannot be backfilled does not prevent
+    // the startup, it is skipped and the backfill runs again on the next startup.
+    fn backfill<F>(&self, matrix_api: &MatrixApi, name: &str, backfill_room: F) -> Result<()>
+    where
+        F: Fn(&Self, &SqliteConnection, &MatrixApi, &RoomId) -> Result<()>,
+    {
+        let connection = SqliteConnection::establish(&self.config.database_url).chain_err(|| ErrorKind::DBConnectionError)?;
+        if CompletedBackfill::is_completed(&connection, name)? {
+            debug!(self.logger, "Skipping backfill of {}, it was already completed", name);
+            return Ok(());
+        }
+
+        let matrix_bot_user_id = self.config.matrix_bot_user_id()?;
+        debug!(self.logger, "Backfilling {} of bot user {}", name, matrix_bot_user_id);
+
+        let mut is_complete = true;
+        for room_id in matrix_api.get_joined_rooms(matrix_bot_user_id)? {
+            if let Err(err) = backfill_room(self, &connection, matrix_api, &room_id) {
+                info!(self.logger, "Could not backfill room {} for {}, skipping it", room_id, name);
+                log::log_error(&self.logger, &err);
+                is_complete = false;
+            }
+        }
+
+        if is_complete {
+            CompletedBackfill::insert(&connection, name)?;
+        }
+
+        Ok(())
+    }
+
+    // Bridged rooms are only identified by their canonical alias
+    // `#sender_localpart#server_id#channel_id:hs_domain`.
+    fn backfill_bridged_room(&self, connection: &SqliteConnection, matrix_api: &MatrixApi, room_id: &RoomId) -> Result<()> {
+        if BridgedRoom::find_by_matrix_room_id(connection, room_id)?.is_some() {
+            return Ok(());
+        }
+
+        let alias = match matrix_api.get_room_canonical_alias(room_id.clone())? {
+            Some(alias) => alias.alias().to_string(),
+            None => return Ok(()),
+        };
+
+        let alias_parts: Vec<&str> = alias.split('#').collect();
+        if alias_parts.len() != 3 || alias_parts[0] != self.config.sender_localpart {
+            return Ok(());
+        }
+
+        let (rocketchat_server_id, rocketchat_channel_id) = (alias_parts[1], alias_parts[2]);
+        if RocketchatServer::find_by_id(connection, rocketchat_server_id)?.is_none() {
+            debug!(self.logger, "Skipping room {}, the Rocket.Chat server {} does not exist", room_id, rocketchat_server_id);
+            return Ok(());
+        }
+
+        let new_bridged_room = NewBridgedRoom {
+            matrix_room_id: room_id,
+            rocketchat_server_id,
+            rocketchat_channel_id,
+            kind: None,
+            created_by: None,
+        };
+        BridgedRoom::insert(connection, &new_bridged_room)?;
+        info!(self.logger, "Backfilled bridged room {} for Rocket.Chat channel {}", room_id, rocketchat_channel_id);
+
+        Ok(())
+    }
+
+    // Admin rooms are only identified by their topic, which contains the URL of the Rocket.Chat
+    // server.
+    fn backfill_admin_room(&self, connection: &SqliteConnection, matrix_api: &MatrixApi, room_id: &RoomId) -> Result<()> {
+        if AdminRoom::find_by_matrix_room_id(connection, room_id)?.is_some() {
+            return Ok(());
+        }
+
+        let room = Room::new(self.config, &self.logger, matrix_api, room_id.clone());
+        if !room.is_admin_room(connection)? {
+            return Ok(());
+        }
+
+        let server = match matrix_api.get_room_topic(room_id.clone())? {
+            Some(rocketchat_url) => RocketchatServer::find_by_url(connection, &rocketchat_url)?,
+            None => None,
+        };
+        let server = match server {
+            Some(server) => server,
+            None => return Ok(()),
+        };
+
+        let matrix_user_id = matrix_api.get_room_creator(room_id.clone())?;
+        let new_admin_room =
+            NewAdminRoom { matrix_room_id: room_id, matrix_user_id: &matrix_user_id, rocketchat_server_id: &server.id };
+        AdminRoom::insert(connection, &new_admin_room)?;
+        info!(self.logger, "Backfilled admin room {} of user {} for Rocket.Chat server {}", room_id, matrix_user_id, server.id);
+
+        Ok(())
+    }
 }
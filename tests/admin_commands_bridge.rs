@@ -13,9 +13,8 @@ use std::convert::TryFrom;
 use iron::{status, Chain};
 use matrix_rocketchat::api::rocketchat::v1::USERS_INFO_PATH;
 use matrix_rocketchat::api::MatrixApi;
-use matrix_rocketchat::models::Room;
+use matrix_rocketchat::models::{BridgedRoom, Room};
 use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, DEFAULT_LOGGER};
-use ruma_client_api::r0::alias::get_alias::Endpoint as GetAliasEndpoint;
 use ruma_client_api::r0::membership::invite_user::{self, Endpoint as InviteEndpoint};
 use ruma_client_api::r0::room::create_room::Endpoint as CreateRoomEndpoint;
 use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
@@ -47,7 +46,8 @@ fn successfully_bridge_a_rocketchat_room() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -112,6 +112,12 @@ fn successfully_bridge_a_rocketchat_room() {
     assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_user_1_id:localhost").unwrap()));
     assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_user_2_id:localhost").unwrap()));
     assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_user_3_id:localhost").unwrap()));
+
+    let connection = test.connection_pool.get().unwrap();
+    let bridged_room = BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "joined_channel_id").unwrap().unwrap();
+    assert_eq!(bridged_room.matrix_room_id, RoomId::try_from("!joined_channel_id:localhost").unwrap());
+    assert_eq!(bridged_room.kind, Some("channel".to_string()));
+    assert_eq!(bridged_room.created_by, Some(UserId::try_from("@spec_user:localhost").unwrap()));
 }
 
 #[test]
@@ -137,7 +143,8 @@ fn successfully_bridge_a_rocketchat_group() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -202,6 +209,12 @@ fn successfully_bridge_a_rocketchat_group() {
     assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_user_1_id:localhost").unwrap()));
     assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_user_2_id:localhost").unwrap()));
     assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_user_3_id:localhost").unwrap()));
+
+    let connection = test.connection_pool.get().unwrap();
+    let bridged_room = BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "joined_group_id").unwrap().unwrap();
+    assert_eq!(bridged_room.matrix_room_id, RoomId::try_from("!joined_group_id:localhost").unwrap());
+    assert_eq!(bridged_room.kind, Some("group".to_string()));
+    assert_eq!(bridged_room.created_by, Some(UserId::try_from("@spec_user:localhost").unwrap()));
 }
 
 #[test]
@@ -273,14 +286,16 @@ fn successfully_bridge_a_rocketchat_room_that_an_other_user_already_bridged() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for spec user
+    // discard login messages for spec user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     // discard welcome message for other user
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for other user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for other user
+    // discard login messages for other user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     // spec user received success message
@@ -368,7 +383,8 @@ fn susccessfully_bridge_a_rocketchat_room_that_was_unbridged_before() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridge message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -402,7 +418,8 @@ fn successfully_bridge_two_different_rocketchat_rooms() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -476,7 +493,8 @@ fn do_not_allow_to_bridge_channels_that_the_user_has_not_joined_on_the_rocketcha
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -508,7 +526,8 @@ fn attempting_to_bridge_a_non_existing_channel_returns_an_error() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -537,7 +556,8 @@ fn attempting_to_bridge_an_already_bridged_channel_returns_an_error() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -593,7 +613,8 @@ fn the_room_is_not_bridged_when_setting_the_canonical_room_alias_failes() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -633,7 +654,8 @@ fn the_user_gets_a_message_when_creating_the_room_failes() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -675,7 +697,8 @@ fn the_user_gets_a_message_when_setting_the_powerlevels_failes() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -710,7 +733,8 @@ fn the_user_gets_a_message_when_inviting_the_user_failes() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -762,43 +786,10 @@ fn the_user_gets_a_message_when_getting_the_users_info_failes() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-
-    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(message_received_by_matrix.contains("An internal error occurred"));
-}
-
-#[test]
-fn the_user_gets_a_message_when_getting_the_room_alias_failes() {
-    let test = Test::new();
-    let (message_forwarder, receiver) = MessageForwarder::new();
-    let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
-    matrix_router.get(
-        GetAliasEndpoint::router_path(),
-        handlers::MatrixErrorResponder { status: status::InternalServerError, message: "Could not get room alias".to_string() },
-        "get_room_alias",
-    );
-    let channels = test.channel_list();
-    channels.lock().unwrap().insert("joined_channel", vec!["spec_user"]);
-    let test =
-        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
-
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
+    // discard login messages
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
     receiver.recv_timeout(default_timeout()).unwrap();
 
-    helpers::send_room_message_from_matrix(
-        &test.config.as_url,
-        RoomId::try_from("!admin_room_id:localhost").unwrap(),
-        UserId::try_from("@spec_user:localhost").unwrap(),
-        "bridge joined_channel".to_string(),
-    );
-
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(message_received_by_matrix.contains("An internal error occurred"));
 }
@@ -825,7 +816,8 @@ fn the_user_gets_a_message_when_the_create_room_response_cannot_be_deserialized(
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -876,39 +868,10 @@ fn the_user_gets_a_message_when_the_users_info_response_cannot_be_deserialized()
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-
-    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(message_received_by_matrix.contains("An internal error occurred"));
-}
-
-#[test]
-fn the_user_gets_a_message_when_getting_the_room_alias_response_cannot_be_deserialized() {
-    let test = Test::new();
-    let (message_forwarder, receiver) = MessageForwarder::new();
-    let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
-    matrix_router.get(GetAliasEndpoint::router_path(), handlers::InvalidJsonResponse { status: status::Ok }, "get_room_alias");
-    let channels = test.channel_list();
-    channels.lock().unwrap().insert("joined_channel", vec!["spec_user"]);
-    let test =
-        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
-
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
+    // discard login messages
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
     receiver.recv_timeout(default_timeout()).unwrap();
 
-    helpers::send_room_message_from_matrix(
-        &test.config.as_url,
-        RoomId::try_from("!admin_room_id:localhost").unwrap(),
-        UserId::try_from("@spec_user:localhost").unwrap(),
-        "bridge joined_channel".to_string(),
-    );
-
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(message_received_by_matrix.contains("An internal error occurred"));
 }
@@ -14,7 +14,7 @@ use std::thread;
 
 use iron::{status, Iron, Listening};
 use matrix_rocketchat::api::MatrixApi;
-use matrix_rocketchat::models::{RocketchatServer, UserOnRocketchatServer};
+use matrix_rocketchat::models::{AdminRoom, RocketchatServer, UserOnRocketchatServer};
 use matrix_rocketchat_test::{
     default_timeout, get_free_socket_addr, handlers, helpers, MessageForwarder, Test, DEFAULT_LOGGER,
     DEFAULT_ROCKETCHAT_VERSION, IRON_THREADS, RS_TOKEN,
@@ -587,3 +587,44 @@ fn connecting_a_room_failes_when_the_room_topic_failes() {
         RocketchatServer::find_by_url(&connection, &test.rocketchat_mock_url.clone().unwrap()).unwrap();
     assert!(rocketchat_server_option.is_none());
 }
+
+#[test]
+fn changing_the_topic_does_not_disconnect_the_admin_room() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    matrix_api
+        .set_room_topic(
+            RoomId::try_from("!admin_room_id:localhost").unwrap(),
+            UserId::try_from("@rocketchat:localhost").unwrap(),
+            "spec topic".to_string(),
+        )
+        .unwrap();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        format!("connect {}", test.rocketchat_mock_url.clone().unwrap()),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard first connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is already connected"));
+
+    let connection = test.connection_pool.get().unwrap();
+    let admin_room = AdminRoom::find_by_matrix_room_id(&connection, &RoomId::try_from("!admin_room_id:localhost").unwrap())
+        .unwrap()
+        .unwrap();
+    assert_eq!(admin_room.matrix_user_id, UserId::try_from("@spec_user:localhost").unwrap());
+    assert_eq!(admin_room.rocketchat_server_id, "rcid");
+}
@@ -0,0 +1,305 @@
+#![feature(try_from)]
+
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+
+use std::convert::TryFrom;
+
+use matrix_rocketchat::api::rocketchat::User;
+use matrix_rocketchat::api::MatrixApi;
+use matrix_rocketchat::models::{
+    AdminRoom, BridgedMessage, BridgedReaction, BridgedRoom, DirectMessageMember, DirectMessageReceiver, DirectMessageRoom,
+    NewBridgedMessage, NewBridgedReaction, NewDirectMessageReceiver, NewDirectMessageRoom, NewVirtualUserAvatar,
+    RocketchatServer, UserOnRocketchatServer, VirtualUserAvatar,
+};
+use matrix_rocketchat::Server;
+use matrix_rocketchat_test::{default_timeout, helpers, MessageForwarder, Test, DEFAULT_LOGGER};
+use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{EventId, RoomId, UserId};
+
+#[test]
+fn successfully_disconnect_the_admin_room() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test =
+        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "disconnect".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is now disconnected from"));
+
+    let connection = test.connection_pool.get().unwrap();
+    let admin_room_id = RoomId::try_from("!admin_room_id:localhost").unwrap();
+    assert!(AdminRoom::find_by_matrix_room_id(&connection, &admin_room_id).unwrap().is_none());
+    let user_on_rocketchat_server =
+        UserOnRocketchatServer::find(&connection, &UserId::try_from("@spec_user:localhost").unwrap(), "rcid".to_string())
+            .unwrap();
+    assert!(!user_on_rocketchat_server.is_logged_in());
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "list".to_string(),
+    );
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is not connected to a Rocket.Chat server"));
+}
+
+#[test]
+fn disconnect_keeps_bridged_rooms_by_default() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "disconnect".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard bridge message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is now disconnected from"));
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "spec_channel_id").unwrap().is_some());
+}
+
+#[test]
+fn disconnect_unbridges_rooms_that_only_the_user_is_using() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .with_bridged_group(("spec_group", vec!["spec_user"]))
+        .run();
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    matrix_api.register("other_user".to_string()).unwrap();
+    helpers::invite(
+        &test.config,
+        RoomId::try_from("!spec_group_id:localhost").unwrap(),
+        UserId::try_from("@other_user:localhost").unwrap(),
+        UserId::try_from("@rocketchat:localhost").unwrap(),
+    );
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!spec_group_id:localhost").unwrap(),
+        UserId::try_from("@other_user:localhost").unwrap(),
+    );
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "disconnect --unbridge".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard bridge messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is now disconnected from"));
+    assert!(message_received_by_matrix.contains("!spec_channel_id:localhost"));
+    assert!(!message_received_by_matrix.contains("!spec_group_id:localhost"));
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "spec_channel_id").unwrap().is_none());
+    assert!(BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "spec_group_id").unwrap().is_some());
+}
+
+#[test]
+fn the_operator_can_remove_a_rocketchat_server_that_is_no_longer_used() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    let server = Server::new(&test.config, DEFAULT_LOGGER.clone());
+    let err = server.remove_rocketchat_server("rcid").unwrap_err();
+    assert!(err.to_string().contains("still in use"));
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "disconnect".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard disconnect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    server.remove_rocketchat_server("rcid").unwrap();
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(RocketchatServer::find_by_id(&connection, "rcid").unwrap().is_none());
+    let user_on_rocketchat_server = UserOnRocketchatServer::find_by_matrix_user_id(
+        &connection,
+        &UserId::try_from("@spec_user:localhost").unwrap(),
+        "rcid".to_string(),
+    )
+    .unwrap();
+    assert!(user_on_rocketchat_server.is_none());
+}
+
+#[test]
+fn removing_a_rocketchat_server_deletes_the_stored_messages_and_direct_messages() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "disconnect".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard disconnect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let connection = test.connection_pool.get().unwrap();
+    let dm_room_id = RoomId::try_from("!dm_room_id:localhost").unwrap();
+    let spec_user_id = UserId::try_from("@spec_user:localhost").unwrap();
+    let other_user_id = UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap();
+    let message_event_id = EventId::try_from("$message_id:localhost").unwrap();
+    let reaction_event_id = EventId::try_from("$reaction_id:localhost").unwrap();
+
+    let new_direct_message_room = NewDirectMessageRoom {
+        matrix_room_id: &dm_room_id,
+        rocketchat_server_id: "rcid",
+        rocketchat_channel_id: "Kf2nT7wRz4PqX9vLc",
+        matrix_query_user_id: &other_user_id,
+    };
+    let direct_message_room = DirectMessageRoom::insert(&connection, &new_direct_message_room).unwrap();
+    let new_direct_message_receiver = NewDirectMessageReceiver {
+        rocketchat_server_id: "rcid",
+        rocketchat_channel_id: "Kf2nT7wRz4PqX9vLc",
+        matrix_user_id: &spec_user_id,
+        matrix_room_id: &dm_room_id,
+    };
+    DirectMessageReceiver::upsert(&connection, &new_direct_message_receiver).unwrap();
+    let members = vec![
+        User { id: "spec_user_id".to_string(), username: "spec_user".to_string() },
+        User { id: "other_user_id".to_string(), username: "other_user".to_string() },
+    ];
+    DirectMessageMember::replace_all(&connection, "rcid", "Kf2nT7wRz4PqX9vLc", &members).unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &message_event_id,
+        matrix_room_id: &dm_room_id,
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "Kf2nT7wRz4PqX9vLc",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+    let new_bridged_reaction = NewBridgedReaction {
+        matrix_event_id: &reaction_event_id,
+        matrix_room_id: &dm_room_id,
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_user_id: "other_user_id",
+        emoji: ":thumbsup:",
+    };
+    BridgedReaction::insert(&connection, &new_bridged_reaction).unwrap();
+    let new_virtual_user_avatar = NewVirtualUserAvatar {
+        matrix_user_id: &other_user_id,
+        rocketchat_server_id: "rcid",
+        avatar_etag: "spec_etag",
+        avatar_url: "mxc://localhost/spec_avatar",
+    };
+    VirtualUserAvatar::upsert(&connection, &new_virtual_user_avatar).unwrap();
+
+    // the server cannot be removed as long as it is used for direct messages
+    let server = Server::new(&test.config, DEFAULT_LOGGER.clone());
+    let err = server.remove_rocketchat_server("rcid").unwrap_err();
+    assert!(err.to_string().contains("still in use"));
+    assert!(RocketchatServer::find_by_id(&connection, "rcid").unwrap().is_some());
+
+    direct_message_room.delete(&connection).unwrap();
+    server.remove_rocketchat_server("rcid").unwrap();
+
+    assert!(RocketchatServer::find_by_id(&connection, "rcid").unwrap().is_none());
+    assert!(DirectMessageReceiver::find(&connection, "rcid", "Kf2nT7wRz4PqX9vLc", &spec_user_id).unwrap().is_none());
+    assert!(DirectMessageMember::find_by_rocketchat_channel_id(&connection, "rcid", "Kf2nT7wRz4PqX9vLc").unwrap().is_empty());
+    assert!(BridgedMessage::find_by_rocketchat_message_id(&connection, "rcid", "spec_message_id").unwrap().is_none());
+    assert!(BridgedReaction::find_by_matrix_event_id(&connection, &reaction_event_id).unwrap().is_none());
+    assert!(VirtualUserAvatar::find_by_matrix_user_id(&connection, &other_user_id).unwrap().is_none());
+}
+
+#[test]
+fn the_operator_cannot_remove_a_rocketchat_server_that_does_not_exist() {
+    let test = Test::new().run();
+
+    let err = Server::new(&test.config, DEFAULT_LOGGER.clone()).remove_rocketchat_server("unknown").unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+}
@@ -0,0 +1,176 @@
+#![feature(try_from)]
+
+extern crate iron;
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate router;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+extern crate serde_json;
+
+use std::convert::TryFrom;
+
+use iron::Chain;
+use matrix_rocketchat::api::rocketchat::v1::IM_CREATE_PATH;
+use matrix_rocketchat::api::rocketchat::WebhookMessage;
+use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, RS_TOKEN};
+use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomId, UserId};
+use serde_json::to_string;
+
+#[test]
+fn successfully_start_a_direct_message_with_the_dm_command() {
+    let test = Test::new();
+    let mut matrix_router = test.default_matrix_routes();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("admin_room_id");
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let (im_create_forwarder, im_create_receiver) = MessageForwarder::new();
+    let mut im_create_chain = Chain::new(handlers::RocketchatImCreate { direct_messages: test.direct_message_list() });
+    im_create_chain.link_before(im_create_forwarder);
+    rocketchat_router.post(IM_CREATE_PATH, im_create_chain, "im_create");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "dm other_user".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("The direct message with other_user is in room"));
+    assert!(message_received_by_matrix.contains("!other_userDMRocketChat_id:localhost"));
+
+    let im_create_message = im_create_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(im_create_message.contains("other_user"));
+}
+
+#[test]
+fn rocketchat_messages_are_forwarded_to_the_room_created_by_the_dm_command() {
+    let test = Test::new();
+    let mut matrix_router = test.default_matrix_routes();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("other_userDMRocketChat_id");
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test =
+        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "dm other_user".to_string(),
+    );
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let direct_message_from_rocketchat = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_from_rocketchat_payload = to_string(&direct_message_from_rocketchat).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_from_rocketchat_payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Hey there"));
+}
+
+#[test]
+fn the_dm_command_reuses_an_existing_direct_message_room() {
+    let test = Test::new();
+    let mut matrix_router = test.default_matrix_routes();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("admin_room_id");
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test =
+        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "dm other_user".to_string(),
+    );
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "dm other_user".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard first dm message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("The direct message with other_user is in room"));
+    assert!(message_received_by_matrix.contains("!other_userDMRocketChat_id:localhost"));
+}
+
+#[test]
+fn attempt_to_start_a_direct_message_when_the_room_is_not_connected() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "dm other_user".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is not connected to a Rocket.Chat server"));
+}
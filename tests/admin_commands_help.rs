@@ -156,7 +156,8 @@ fn help_command_when_logged_in() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
@@ -167,3 +168,48 @@ fn help_command_when_logged_in() {
          (messages are no longer forwarded)",
     ));
 }
+
+#[test]
+fn help_command_for_a_single_command() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "help connect".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix
+        .contains("`connect <rocketchaturl> [token] [rocketchat_server_id]` Connect this room to a Rocket.Chat server"));
+}
+
+#[test]
+fn help_command_for_an_unknown_command() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "help logut".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Unknown command `logut`, did you mean `logout`?"));
+}
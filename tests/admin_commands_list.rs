@@ -56,7 +56,8 @@ fn sucessfully_list_rocketchat_rooms() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridge message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -98,7 +99,8 @@ fn the_user_gets_a_message_when_getting_room_list_failes() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -132,7 +134,8 @@ fn the_user_gets_a_message_when_the_room_list_cannot_be_deserialized() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -214,7 +217,8 @@ fn the_user_gets_a_message_when_the_me_endpoint_returns_an_error() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
@@ -263,7 +267,8 @@ fn the_user_gets_a_message_when_the_me_response_cannot_be_deserialized() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
@@ -58,6 +58,120 @@ fn sucessfully_login_via_chat_mesage() {
     assert!(message_received_by_matrix.contains("You are logged in."));
 }
 
+#[test]
+fn the_login_message_is_redacted() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let (redact_forwarder, redact_receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    matrix_router.put("/_matrix/client/r0/rooms/:room_id/redact/:event_id/:txn_id", redact_forwarder, "redact");
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "login spec_user secret".to_string(),
+    );
+
+    assert!(redact_receiver.recv_timeout(default_timeout()).is_ok());
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Your message with the credentials was removed from this room."));
+}
+
+#[test]
+fn the_login_message_is_redacted_when_the_login_fails() {
+    let test = Test::new();
+    let (redact_forwarder, redact_receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put("/_matrix/client/r0/rooms/:room_id/redact/:event_id/:txn_id", redact_forwarder, "redact");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(
+        LOGIN_PATH,
+        handlers::RocketchatLogin { successful: false, rocketchat_user_id: Arc::new(Mutex::new(None)) },
+        "login",
+    );
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "login spec_user wrong_password".to_string(),
+    );
+
+    assert!(redact_receiver.recv_timeout(default_timeout()).is_ok());
+}
+
+#[test]
+fn a_capitalized_login_message_is_redacted_even_if_the_command_cannot_be_parsed() {
+    let test = Test::new();
+    let (redact_forwarder, redact_receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put("/_matrix/client/r0/rooms/:room_id/redact/:event_id/:txn_id", redact_forwarder, "redact");
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "  Login spec_user secret".to_string(),
+    );
+
+    assert!(redact_receiver.recv_timeout(default_timeout()).is_ok());
+}
+
+#[test]
+fn the_user_gets_a_message_when_the_login_message_cannot_be_redacted() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    matrix_router.put(
+        "/_matrix/client/r0/rooms/:room_id/redact/:event_id/:txn_id",
+        handlers::MatrixErrorResponder { status: status::Forbidden, message: "Not allowed to redact".to_string() },
+        "redact",
+    );
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "login spec_user secret".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Your message with the credentials could not be removed from this room"));
+
+    let connection = test.connection_pool.get().unwrap();
+    let user_on_rocketchat_server =
+        UserOnRocketchatServer::find(&connection, &UserId::try_from("@spec_user:localhost").unwrap(), "rcid".to_string())
+            .unwrap();
+    assert!(user_on_rocketchat_server.is_logged_in());
+}
+
 #[test]
 fn wrong_password_when_logging_in_via_chat_message() {
     let test = Test::new();
@@ -88,6 +202,8 @@ fn wrong_password_when_logging_in_via_chat_message() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
+    // discard redaction message
+    receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(message_received_by_matrix.contains("Authentication failed!"));
@@ -116,6 +232,9 @@ fn login_multiple_times_via_chat_message() {
 
         let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
         assert!(message_received_by_matrix.contains("You are logged in."));
+
+        // discard redaction message
+        receiver.recv_timeout(default_timeout()).unwrap();
     }
 }
 
@@ -310,7 +429,8 @@ fn the_user_can_login_again_on_the_same_server_with_a_new_admin_room() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard first connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard first login message
+    // discard first login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard second welcome message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -347,6 +467,8 @@ fn server_does_not_respond_when_logging_in_via_chat_mesage() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
+    // discard redaction message
+    receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(message_received_by_matrix.contains("An internal error occurred"));
@@ -378,6 +500,8 @@ fn the_user_gets_a_message_when_the_login_response_cannot_be_deserialized() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
+    // discard redaction message
+    receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(message_received_by_matrix.contains("An internal error occurred"));
@@ -414,6 +538,8 @@ fn the_user_gets_a_message_when_the_login_returns_an_error() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
+    // discard redaction message
+    receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(message_received_by_matrix.contains("An internal error occurred"));
@@ -0,0 +1,167 @@
+#![feature(try_from)]
+
+extern crate http;
+extern crate iron;
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate reqwest;
+extern crate router;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+extern crate serde_json;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use http::Method;
+use iron::status;
+use matrix_rocketchat::api::rocketchat::v1::ME_PATH;
+use matrix_rocketchat::api::{RequestData, RestApi};
+use matrix_rocketchat::models::{RocketchatServer, TokenCredentials, UserOnRocketchatServer};
+use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test};
+use reqwest::StatusCode;
+use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomId, UserId};
+use serde_json::to_string;
+
+#[test]
+fn sucessfully_login_with_a_personal_access_token_via_chat_mesage() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "login-token spec_user_id spec_personal_access_token".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("You are logged in."));
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Your message with the credentials was removed from this room."));
+
+    let connection = test.connection_pool.get().unwrap();
+    let user_on_rocketchat_server =
+        UserOnRocketchatServer::find(&connection, &UserId::try_from("@spec_user:localhost").unwrap(), "rcid".to_string())
+            .unwrap();
+    assert_eq!(user_on_rocketchat_server.rocketchat_user_id.unwrap(), "spec_user_id");
+    assert_eq!(user_on_rocketchat_server.rocketchat_auth_token.unwrap(), "spec_personal_access_token");
+}
+
+#[test]
+fn attempt_to_login_with_an_invalid_personal_access_token_via_chat_message() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        ME_PATH,
+        handlers::RocketchatErrorResponder { message: "Unauthorized".to_string(), status: status::Unauthorized },
+        "me",
+    );
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "login-token spec_user_id invalid_token".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard redaction message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Authentication failed!"));
+
+    let connection = test.connection_pool.get().unwrap();
+    let user_on_rocketchat_server =
+        UserOnRocketchatServer::find(&connection, &UserId::try_from("@spec_user:localhost").unwrap(), "rcid".to_string())
+            .unwrap();
+    assert!(!user_on_rocketchat_server.is_logged_in());
+}
+
+#[test]
+fn sucessfully_login_with_a_personal_access_token_via_rest_api() {
+    let test = Test::new().with_rocketchat_mock().with_connected_admin_room().run();
+
+    let login_request = TokenCredentials {
+        user_id: UserId::try_from("@spec_user:localhost").unwrap(),
+        rocketchat_user_id: "spec_user_id".to_string(),
+        personal_access_token: "spec_personal_access_token".to_string(),
+        rocketchat_url: test.rocketchat_mock_url.clone().unwrap(),
+    };
+    let payload = to_string(&login_request).unwrap();
+    let (response, status_code) = RestApi::call(
+        &Method::POST,
+        &format!("http://{}/rocketchat/login", test.as_listening.as_ref().unwrap().socket),
+        RequestData::Body(payload),
+        &HashMap::new(),
+        None,
+    )
+    .unwrap();
+
+    assert!(response.contains(
+        "You are logged in. Return to your Matrix client and \
+         enter help in the admin room for more instructions.",
+    ));
+    assert!(status_code.is_success());
+
+    let connection = test.connection_pool.get().unwrap();
+    let rocketchat_server = RocketchatServer::find(&connection, &test.rocketchat_mock_url.clone().unwrap()).unwrap();
+    let user_on_rocketchat_server =
+        UserOnRocketchatServer::find(&connection, &UserId::try_from("@spec_user:localhost").unwrap(), rocketchat_server.id)
+            .unwrap();
+    assert_eq!(user_on_rocketchat_server.rocketchat_auth_token.unwrap(), "spec_personal_access_token");
+}
+
+#[test]
+fn attempt_to_login_with_an_invalid_personal_access_token_via_rest_api() {
+    let test = Test::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        ME_PATH,
+        handlers::RocketchatErrorResponder { message: "Unauthorized".to_string(), status: status::Unauthorized },
+        "me",
+    );
+    let test = test.with_custom_rocketchat_routes(rocketchat_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    let login_request = TokenCredentials {
+        user_id: UserId::try_from("@spec_user:localhost").unwrap(),
+        rocketchat_user_id: "spec_user_id".to_string(),
+        personal_access_token: "invalid_token".to_string(),
+        rocketchat_url: test.rocketchat_mock_url.clone().unwrap(),
+    };
+    let payload = to_string(&login_request).unwrap();
+    let (response, status_code) = RestApi::call(
+        &Method::POST,
+        &format!("http://{}/rocketchat/login", test.as_listening.as_ref().unwrap().socket),
+        RequestData::Body(payload),
+        &HashMap::new(),
+        None,
+    )
+    .unwrap();
+    assert!(response.contains("Authentication failed!"));
+    assert_eq!(status_code, StatusCode::UNAUTHORIZED);
+}
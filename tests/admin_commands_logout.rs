@@ -0,0 +1,158 @@
+#![feature(try_from)]
+
+extern crate iron;
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate router;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+
+use std::convert::TryFrom;
+
+use iron::status;
+use matrix_rocketchat::api::rocketchat::v1::LOGOUT_PATH;
+use matrix_rocketchat::models::UserOnRocketchatServer;
+use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test};
+use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomId, UserId};
+
+#[test]
+fn successfully_logout_from_the_rocketchat_server() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let (logout_forwarder, logout_receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(LOGOUT_PATH, logout_forwarder, "logout");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "logout".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("You are logged out from"));
+
+    assert!(logout_receiver.recv_timeout(default_timeout()).is_ok());
+
+    let connection = test.connection_pool.get().unwrap();
+    let user_on_rocketchat_server =
+        UserOnRocketchatServer::find(&connection, &UserId::try_from("@spec_user:localhost").unwrap(), "rcid".to_string())
+            .unwrap();
+    assert!(!user_on_rocketchat_server.is_logged_in());
+    assert_eq!(user_on_rocketchat_server.rocketchat_user_id, Some("spec_user_id".to_string()));
+}
+
+#[test]
+fn the_credentials_are_removed_when_the_rocketchat_logout_failes() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(
+        LOGOUT_PATH,
+        handlers::RocketchatErrorResponder { message: "Unauthorized".to_string(), status: status::Unauthorized },
+        "logout",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "logout".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("You are logged out from"));
+
+    let connection = test.connection_pool.get().unwrap();
+    let user_on_rocketchat_server =
+        UserOnRocketchatServer::find(&connection, &UserId::try_from("@spec_user:localhost").unwrap(), "rcid".to_string())
+            .unwrap();
+    assert!(!user_on_rocketchat_server.is_logged_in());
+}
+
+#[test]
+fn attempt_to_logout_when_the_user_is_not_logged_in() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "logout".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("You are not logged in on"));
+}
+
+#[test]
+fn attempt_to_logout_when_the_room_is_not_connected() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "logout".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is not connected to a Rocket.Chat server"));
+}
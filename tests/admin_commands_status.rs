@@ -0,0 +1,294 @@
+#![feature(try_from)]
+
+extern crate diesel;
+extern crate iron;
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate router;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use diesel::sqlite::SqliteConnection;
+use iron::status;
+use matrix_rocketchat::api::rocketchat::v1::{CHANNELS_LIST_JOINED_PATH, ME_PATH};
+use matrix_rocketchat::api::rocketchat::User;
+use matrix_rocketchat::models::{
+    DirectMessageMember, DirectMessageReceiver, DirectMessageRoom, NewDirectMessageReceiver, NewDirectMessageRoom,
+};
+use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test};
+use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomId, UserId};
+
+#[test]
+fn status_command_when_logged_in() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let mut users_in_rooms = HashMap::new();
+    users_in_rooms.insert("spec_user_id", vec!["spec_channel"]);
+    rocketchat_router.get(
+        CHANNELS_LIST_JOINED_PATH,
+        handlers::RocketchatJoinedRooms { users_in_rooms: users_in_rooms },
+        "joined_channels",
+    );
+    let dm_room_id = RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap();
+    let spec_user_id = UserId::try_from("@spec_user:localhost").unwrap();
+    let other_user_id = UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap();
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .with_bridge_dm((dm_room_id.clone(), vec![spec_user_id.clone(), other_user_id.clone()]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    store_direct_message_room(&connection, &dm_room_id, "Gd7kQ2xPw9LmZ4rTa", &other_user_id, &spec_user_id);
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "status".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard bridge message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("(Rocket.Chat server ID `rcid`)"));
+    assert!(message_received_by_matrix.contains("You are logged in as `spec_user`, your Rocket.Chat token is valid."));
+    assert!(message_received_by_matrix.contains("spec_channel (!spec_channel_id:localhost)"));
+    assert!(
+        message_received_by_matrix.contains("@rocketchat_rcid_other_user_id:localhost (!other_userDMRocketChat_id:localhost)")
+    );
+}
+
+#[test]
+fn status_command_lists_all_other_participants_of_a_direct_message_with_multiple_users() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        CHANNELS_LIST_JOINED_PATH,
+        handlers::RocketchatJoinedRooms { users_in_rooms: HashMap::new() },
+        "joined_channels",
+    );
+    let dm_room_id = RoomId::try_from("!group_dm_id:localhost").unwrap();
+    let spec_user_id = UserId::try_from("@spec_user:localhost").unwrap();
+    let other_user_id = UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap();
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    store_direct_message_room(&connection, &dm_room_id, "Hs8mR3yQx0NnA5sUb", &other_user_id, &spec_user_id);
+    let members = vec![
+        User { id: "spec_user_id".to_string(), username: "spec_user".to_string() },
+        User { id: "other_user_id".to_string(), username: "other_user".to_string() },
+        User { id: "third_user_id".to_string(), username: "third_user".to_string() },
+    ];
+    DirectMessageMember::replace_all(&connection, "rcid", "Hs8mR3yQx0NnA5sUb", &members).unwrap();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "status".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains(
+        "@rocketchat_rcid_other_user_id:localhost, @rocketchat_rcid_third_user_id:localhost (!group_dm_id:localhost)"
+    ));
+}
+
+#[test]
+fn status_command_only_lists_the_bridged_rooms_that_the_user_joined_on_rocketchat() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let mut users_in_rooms = HashMap::new();
+    users_in_rooms.insert("spec_user_id", vec!["other_channel"]);
+    rocketchat_router.get(
+        CHANNELS_LIST_JOINED_PATH,
+        handlers::RocketchatJoinedRooms { users_in_rooms: users_in_rooms },
+        "joined_channels",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "status".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard bridge message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("You are logged in as `spec_user`, your Rocket.Chat token is valid."));
+    assert!(!message_received_by_matrix.contains("!spec_channel_id:localhost"));
+}
+
+#[test]
+fn status_command_when_not_logged_in() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "whoami".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("(Rocket.Chat server ID `rcid`)"));
+    assert!(message_received_by_matrix.contains("You are not logged in."));
+    assert!(message_received_by_matrix.contains("None"));
+}
+
+#[test]
+fn status_command_when_the_rocketchat_token_is_not_valid_anymore() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        ME_PATH,
+        handlers::RocketchatErrorResponder { message: "Unauthorized".to_string(), status: status::Unauthorized },
+        "me",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "status".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Your Rocket.Chat token is not valid anymore"));
+}
+
+#[test]
+fn attempt_to_get_the_status_when_the_room_is_not_connected() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "status".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("This room is not connected to a Rocket.Chat server"));
+}
+
+fn store_direct_message_room(
+    connection: &SqliteConnection,
+    room_id: &RoomId,
+    channel_id: &str,
+    query_user_id: &UserId,
+    receiver_id: &UserId,
+) {
+    let new_direct_message_room = NewDirectMessageRoom {
+        matrix_room_id: room_id,
+        rocketchat_server_id: "rcid",
+        rocketchat_channel_id: channel_id,
+        matrix_query_user_id: query_user_id,
+    };
+    DirectMessageRoom::insert(connection, &new_direct_message_room).unwrap();
+    let new_direct_message_receiver = NewDirectMessageReceiver {
+        rocketchat_server_id: "rcid",
+        rocketchat_channel_id: channel_id,
+        matrix_user_id: receiver_id,
+        matrix_room_id: room_id,
+    };
+    DirectMessageReceiver::upsert(connection, &new_direct_message_receiver).unwrap();
+}
@@ -0,0 +1,163 @@
+#![feature(try_from)]
+
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate router;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+
+use std::convert::TryFrom;
+
+use matrix_rocketchat::api::rocketchat::v1::LOGIN_PATH;
+use matrix_rocketchat_test::{default_timeout, helpers, MessageForwarder, Test};
+use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomId, UserId};
+
+#[test]
+fn quoted_arguments_can_contain_whitespace() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        r#"help "no such command""#.to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Unknown command `no such command`"));
+}
+
+#[test]
+fn the_password_is_taken_verbatim_from_the_login_command() {
+    let test = Test::new();
+    let (login_forwarder, login_receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(LOGIN_PATH, login_forwarder, "login");
+
+    let test = test.with_custom_rocketchat_routes(rocketchat_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        r#"login spec_user --my  "secret' \password"#.to_string(),
+    );
+
+    let login_request = login_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(login_request.contains(r#""username":"spec_user""#));
+    assert!(login_request.contains(r#""password":"--my  \"secret' \\password""#));
+}
+
+#[test]
+fn a_password_with_spaces_is_used_without_quotes() {
+    let test = Test::new();
+    let (login_forwarder, login_receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(LOGIN_PATH, login_forwarder, "login");
+
+    let test = test.with_custom_rocketchat_routes(rocketchat_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "login spec_user my secret password".to_string(),
+    );
+
+    let login_request = login_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(login_request.contains(r#""username":"spec_user""#));
+    assert!(login_request.contains(r#""password":"my secret password""#));
+}
+
+#[test]
+fn attempt_to_execute_a_command_with_the_wrong_number_of_arguments() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "login spec_user".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard redaction message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains(
+        "Wrong number of arguments for `login`, usage: `login <rocketchatusername> <password>`. \
+         Everything after the first argument is used as it is, it must not be quoted."
+    ));
+}
+
+#[test]
+fn attempt_to_execute_a_command_with_an_unterminated_quote() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "bridge 'spec channel".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("The command contains an unterminated quote, close it with a matching '."));
+}
+
+#[test]
+fn attempt_to_execute_a_command_with_an_unknown_flag() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "disconnect --force".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix
+        .contains("`disconnect` does not support the flag `--force`, usage: `disconnect [--unbridge]`"));
+}
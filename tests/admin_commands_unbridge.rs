@@ -14,6 +14,7 @@ use std::convert::TryFrom;
 use iron::status;
 use matrix_rocketchat::api::rocketchat::WebhookMessage;
 use matrix_rocketchat::api::MatrixApi;
+use matrix_rocketchat::models::BridgedRoom;
 use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, DEFAULT_LOGGER, RS_TOKEN};
 use ruma_client_api::r0::alias::delete_alias::Endpoint as DeleteAliasEndpoint;
 use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
@@ -47,6 +48,11 @@ fn successfully_unbridge_a_rocketchat_room() {
         user_id: "new_user_id".to_string(),
         user_name: "new_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -69,7 +75,8 @@ fn successfully_unbridge_a_rocketchat_room() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for spec user
+    // discard login messages for spec user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -78,6 +85,9 @@ fn successfully_unbridge_a_rocketchat_room() {
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(message_received_by_matrix.contains("bridged_channel is now unbridged."));
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "bridged_channel_id").unwrap().is_none());
 }
 
 #[test]
@@ -104,6 +114,11 @@ fn successfully_unbridge_a_private_group() {
         user_id: "new_user_id".to_string(),
         user_name: "new_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -126,7 +141,8 @@ fn successfully_unbridge_a_private_group() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for spec user
+    // discard login messages for spec user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -212,7 +228,8 @@ fn do_not_allow_to_unbridge_a_channel_with_other_matrix_users() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for spec user
+    // discard login messages for spec user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridged message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -221,7 +238,8 @@ fn do_not_allow_to_unbridge_a_channel_with_other_matrix_users() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for other user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for other user
+    // discard login messages for other user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridged message for other user
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -318,7 +336,8 @@ fn do_not_allow_to_unbridge_a_private_group_with_other_matrix_users() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for spec user
+    // discard login messages for spec user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridged message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -327,7 +346,8 @@ fn do_not_allow_to_unbridge_a_private_group_with_other_matrix_users() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for other user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for other user
+    // discard login messages for other user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridged message for other user
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -373,6 +393,11 @@ fn do_not_allow_to_unbridge_a_channel_with_remaining_room_aliases() {
         user_id: "new_user_id".to_string(),
         user_name: "new_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -403,7 +428,8 @@ fn do_not_allow_to_unbridge_a_channel_with_remaining_room_aliases() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message for spec user
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message for spec user
+    // discard login messages for spec user
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -432,7 +458,8 @@ fn attempting_to_unbridge_a_non_existing_channel_returns_an_error() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -463,7 +490,8 @@ fn attempting_to_unbridge_an_channel_that_is_not_bridged_returns_an_error() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     helpers::send_room_message_from_matrix(
@@ -509,7 +537,8 @@ fn room_is_not_unbridged_when_deleting_the_room_alias_failes() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridge message
     receiver.recv_timeout(default_timeout()).unwrap();
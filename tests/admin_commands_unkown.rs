@@ -13,7 +13,7 @@ use ruma_client_api::Endpoint;
 use ruma_identifiers::{RoomId, UserId};
 
 #[test]
-fn unknown_commands_from_the_admin_room_are_ignored() {
+fn unknown_commands_from_the_admin_room_get_a_reply() {
     let test = Test::new();
     let (message_forwarder, receiver) = MessageForwarder::new();
     let mut matrix_router = test.default_matrix_routes();
@@ -28,8 +28,44 @@ fn unknown_commands_from_the_admin_room_are_ignored() {
         "bogus command".to_string(),
     );
 
-    // we don't get a message, because the command is ignored and no error occurs
-    receiver.recv_timeout(default_timeout()).is_err();
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Unknown command `bogus`, type `help` to see all available commands."));
+}
+
+#[test]
+fn a_similar_command_is_suggested_for_unknown_commands() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test.with_matrix_routes(matrix_router).with_admin_room().run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "brdige spec_channel".to_string(),
+    );
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "connectfoo".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Unknown command `brdige`, did you mean `bridge`?"));
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Unknown command `connectfoo`, did you mean `connect`?"));
 }
 
 #[test]
@@ -335,6 +335,71 @@ fn the_bot_user_does_not_leave_the_admin_room_just_because_the_get_topic_respons
     assert!(members.iter().any(|id| id == &UserId::try_from("@rocketchat:localhost").unwrap()));
 }
 
+#[test]
+fn the_user_gets_a_message_when_the_bot_user_cannot_redact_messages_in_the_admin_room() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let power_levels_params = get_state_events_for_empty_key::PathParams {
+        room_id: RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        event_type: EventType::RoomPowerLevels.to_string(),
+    };
+    matrix_router.get(
+        GetStateEventsForEmptyKey::request_path(power_levels_params),
+        handlers::RoomStatePowerLevels { bot_power_level: 0 },
+        "get_room_power_levels",
+    );
+    let test = test.with_matrix_routes(matrix_router).with_admin_room().run();
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix
+        .contains("I don't have the permission to redact messages in this room. Please give me a power level of at least 50"));
+
+    // the bot doesn't leave the room
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room_id = RoomId::try_from("!admin_room_id:localhost").unwrap();
+    let room = Room::new(&test.config, &DEFAULT_LOGGER, &(*matrix_api), room_id);
+    let members = room.user_ids(None).unwrap();
+    assert_eq!(members.len(), 2);
+}
+
+#[test]
+fn the_user_does_not_get_a_message_when_getting_the_power_levels_of_the_admin_room_fails() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let power_levels_params = get_state_events_for_empty_key::PathParams {
+        room_id: RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        event_type: EventType::RoomPowerLevels.to_string(),
+    };
+    matrix_router.get(
+        GetStateEventsForEmptyKey::request_path(power_levels_params),
+        handlers::MatrixErrorResponder {
+            status: status::InternalServerError,
+            message: "Could not get power levels.".to_string(),
+        },
+        "get_room_power_levels",
+    );
+    let test = test.with_matrix_routes(matrix_router).with_admin_room().run();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Hi, I'm the Rocket.Chat application service"));
+
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+
+    // the bot doesn't leave the room
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room_id = RoomId::try_from("!admin_room_id:localhost").unwrap();
+    let room = Room::new(&test.config, &DEFAULT_LOGGER, &(*matrix_api), room_id);
+    let members = room.user_ids(None).unwrap();
+    assert_eq!(members.len(), 2);
+}
+
 #[test]
 fn the_user_does_not_get_a_message_when_an_leaving_the_room_failes_for_the_bot_user() {
     let test = Test::new();
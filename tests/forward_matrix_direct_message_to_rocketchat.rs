@@ -12,7 +12,7 @@ extern crate serde_json;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
-use iron::status;
+use iron::{status, Chain};
 use matrix_rocketchat::api::rocketchat::v1::{CHAT_POST_MESSAGE_PATH, DM_LIST_PATH};
 use matrix_rocketchat::api::rocketchat::WebhookMessage;
 use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, RS_TOKEN};
@@ -42,6 +42,7 @@ fn successfully_forwards_a_direct_message_to_rocketchat() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     // direct message from Rocket.Chat to trigger the room creation
@@ -53,6 +54,11 @@ fn successfully_forwards_a_direct_message_to_rocketchat() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_from_rocketchat_payload = to_string(&direct_message_from_rocketchat).unwrap();
 
@@ -68,7 +74,8 @@ fn successfully_forwards_a_direct_message_to_rocketchat() {
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    matrix_receiver.recv_timeout(default_timeout()).unwrap();
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = matrix_receiver.recv_timeout(default_timeout()).unwrap();
@@ -85,6 +92,78 @@ fn successfully_forwards_a_direct_message_to_rocketchat() {
     assert!(message_received_by_rocketchat.contains("It's so nice to hear from you after such a long time"));
 }
 
+#[test]
+fn the_stored_direct_message_channel_is_used_to_forward_direct_messages() {
+    let test = Test::new();
+    let (matrix_message_forwarder, matrix_receiver) = MessageForwarder::with_path_filter("other_userDMRocketChat_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), matrix_message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let mut direct_messages = HashMap::new();
+    direct_messages.insert("spec_user_id_other_user_id", vec!["spec_user", "other_user"]);
+    let direct_messages_list_handler =
+        handlers::RocketchatDirectMessagesList { direct_messages: direct_messages, status: status::Ok };
+    let (dm_list_forwarder, dm_list_receiver) = MessageForwarder::new();
+    let mut dm_list_chain = Chain::new(direct_messages_list_handler);
+    dm_list_chain.link_before(dm_list_forwarder);
+    rocketchat_router.get(DM_LIST_PATH, dm_list_chain, "direct_messages_list");
+    let (rocketchat_message_forwarder, rocketchat_receiver) = MessageForwarder::new();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, rocketchat_message_forwarder, "post_text_message");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
+        .run();
+
+    // direct message from Rocket.Chat to trigger the room creation
+    let direct_message_from_rocketchat = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_from_rocketchat_payload = to_string(&direct_message_from_rocketchat).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_from_rocketchat_payload);
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let message_received_by_matrix = matrix_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Hey there"));
+
+    // the direct message list is only used to check the access when the room is created
+    dm_list_receiver.recv_timeout(default_timeout()).unwrap();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "It's so nice to hear from you after such a long time".to_string(),
+    );
+
+    let message_received_by_rocketchat = rocketchat_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("It's so nice to hear from you after such a long time"));
+    assert!(message_received_by_rocketchat.contains("spec_user_id_other_user_id"));
+
+    assert!(dm_list_receiver.recv_timeout(default_timeout()).is_err());
+}
+
 #[test]
 fn direct_messages_are_not_forwarded_if_no_matching_matrix_user_is_found() {
     let test = Test::new();
@@ -106,6 +185,7 @@ fn direct_messages_are_not_forwarded_if_no_matching_matrix_user_is_found() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     // direct message from Rocket.Chat to trigger the room creation
@@ -117,6 +197,11 @@ fn direct_messages_are_not_forwarded_if_no_matching_matrix_user_is_found() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_from_rocketchat_payload = to_string(&direct_message_from_rocketchat).unwrap();
 
@@ -126,7 +211,8 @@ fn direct_messages_are_not_forwarded_if_no_matching_matrix_user_is_found() {
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    matrix_receiver.recv_timeout(default_timeout()).unwrap();
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = matrix_receiver.recv_timeout(default_timeout()).unwrap();
@@ -165,6 +251,7 @@ fn direct_messages_are_not_forwarded_if_no_matching_virtual_user_is_found() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     // direct message from Rocket.Chat to trigger the room creation
@@ -176,6 +263,11 @@ fn direct_messages_are_not_forwarded_if_no_matching_virtual_user_is_found() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_from_rocketchat_payload = to_string(&direct_message_from_rocketchat).unwrap();
 
@@ -191,7 +283,8 @@ fn direct_messages_are_not_forwarded_if_no_matching_virtual_user_is_found() {
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    matrix_receiver.recv_timeout(default_timeout()).unwrap();
     matrix_receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = matrix_receiver.recv_timeout(default_timeout()).unwrap();
@@ -7,21 +7,31 @@ extern crate reqwest;
 extern crate router;
 extern crate ruma_client_api;
 extern crate ruma_identifiers;
+#[macro_use]
 extern crate serde_json;
+extern crate tempdir;
 
 use std::collections::HashMap;
 use std::convert::TryFrom;
 
 use iron::status;
-use matrix_rocketchat::api::rocketchat::v1::{CHAT_POST_MESSAGE_PATH, ROOMS_UPLOAD_PATH};
+use matrix_rocketchat::api::rocketchat::v1::{
+    CHANNELS_RENAME_PATH, CHANNELS_SET_TOPIC_PATH, CHAT_DELETE_PATH, CHAT_POST_MESSAGE_PATH, CHAT_REACT_PATH, CHAT_UPDATE_PATH,
+    GROUPS_SET_TOPIC_PATH, ROOMS_UPLOAD_PATH,
+};
 use matrix_rocketchat::api::rocketchat::WebhookMessage;
 use matrix_rocketchat::api::MatrixApi;
-use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, DEFAULT_LOGGER, RS_TOKEN};
+use matrix_rocketchat::models::{BridgedMessage, BridgedReaction, NewBridgedMessage, NewBridgedReaction};
+use matrix_rocketchat_test::{
+    build_test_config, default_timeout, handlers, helpers, MessageForwarder, Test, DEFAULT_LOGGER, RS_TOKEN, TEMP_DIR_NAME,
+};
 use ruma_client_api::r0::media::get_content::Endpoint as GetContentEndpoint;
 use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::r0::send::send_state_event_for_empty_key::Endpoint as SendStateEventForEmptyKeyEndpoint;
 use ruma_client_api::Endpoint;
-use ruma_identifiers::{RoomId, UserId};
+use ruma_identifiers::{EventId, RoomId, UserId};
 use serde_json::to_string;
+use tempdir::TempDir;
 
 #[test]
 fn successfully_forwards_a_text_message_from_matrix_to_rocketchat() {
@@ -50,6 +60,86 @@ fn successfully_forwards_a_text_message_from_matrix_to_rocketchat() {
     assert!(message_received_by_rocketchat.contains("spec_channel"));
 }
 
+#[test]
+fn successfully_forwards_a_formatted_message_from_matrix_to_rocketchat() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_chat_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_formatted_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "bold and code".to_string(),
+        "<strong>bold</strong> and <code>code</code>".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"text\":\"*bold* and `code`\""));
+}
+
+#[test]
+fn pills_of_virtual_users_are_forwarded_as_rocketchat_mentions() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_chat_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_formatted_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "Alice: hi".to_string(),
+        "<a href=\"https://matrix.to/#/@rocketchat_rcid_alice_id:localhost\">Alice</a>: hi".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"text\":\"@alice: hi\""));
+}
+
+#[test]
+fn matrix_user_ids_of_virtual_users_are_forwarded_as_rocketchat_mentions() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_chat_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "hi @rocketchat_rcid_alice_id:localhost and @unknown:localhost".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"text\":\"hi @alice and @unknown:localhost\""));
+}
+
 #[test]
 fn successfully_forwards_an_image_message_from_matrix_to_rocketchat() {
     let test = Test::new();
@@ -215,7 +305,8 @@ fn error_message_when_uploading_the_file_on_rocketchat_fails_when_forwarding_a_f
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard bridge message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -312,6 +403,11 @@ fn do_not_forward_messages_from_virtual_user_to_avoid_loops() {
         user_id: "virtual_spec_user_id".to_string(),
         user_name: "virtual_spec_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
     helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
@@ -392,7 +488,7 @@ fn ignore_messages_from_rooms_with_empty_room_canonical_alias() {
 }
 
 #[test]
-fn ignore_messages_with_a_message_type_that_is_not_supported() {
+fn successfully_forwards_an_emote_message_from_matrix_to_rocketchat() {
     let test = Test::new();
     let (message_forwarder, receiver) = MessageForwarder::new();
     let mut rocketchat_router = test.default_rocketchat_routes();
@@ -406,47 +502,56 @@ fn ignore_messages_with_a_message_type_that_is_not_supported() {
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
-    // create the virtual user by simulating a message from Rocket.Chat
-    let message = WebhookMessage {
-        message_id: "spec_id".to_string(),
-        token: Some(RS_TOKEN.to_string()),
-        channel_id: "spec_channel_id".to_string(),
-        channel_name: Some("spec_channel".to_string()),
-        user_id: "virtual_spec_user_id".to_string(),
-        user_name: "virtual_spec_user".to_string(),
-        text: "spec_message".to_string(),
-    };
-    let payload = to_string(&message).unwrap();
-    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
-
     helpers::send_emote_message_from_matrix(
         &test.config.as_url,
         RoomId::try_from("!spec_channel_id:localhost").unwrap(),
         UserId::try_from("@spec_user:localhost").unwrap(),
-        "emote message".to_string(),
+        "is testing emotes".to_string(),
     );
 
-    assert!(receiver.recv_timeout(default_timeout()).is_err());
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("_spec_user is testing emotes_"));
+    assert!(message_received_by_rocketchat.contains("spec_channel"));
 }
 
 #[test]
-fn the_user_gets_a_message_when_forwarding_a_message_failes() {
+fn successfully_forwards_a_notice_message_from_matrix_to_rocketchat() {
     let test = Test::new();
     let (message_forwarder, receiver) = MessageForwarder::new();
-    let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
     let mut rocketchat_router = test.default_rocketchat_routes();
-    rocketchat_router.post(
-        CHAT_POST_MESSAGE_PATH,
-        handlers::RocketchatErrorResponder {
-            message: "Rocketh.Chat chat.postMessage error".to_string(),
-            status: status::InternalServerError,
-        },
-        "post_text_message",
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_text_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_notice_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "spec notice".to_string(),
     );
 
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("spec notice"));
+    assert!(!message_received_by_rocketchat.contains("[Bot]"));
+}
+
+#[test]
+fn notices_are_flagged_as_bot_messages_when_the_option_is_enabled() {
+    let temp_dir = TempDir::new(TEMP_DIR_NAME).unwrap();
+    let mut config = build_test_config(&temp_dir);
+    config.flag_notices_as_bot_messages = true;
+    let test = Test::new().with_custom_config(config);
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_text_message");
+
     let test = test
-        .with_matrix_routes(matrix_router)
         .with_rocketchat_mock()
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
@@ -454,45 +559,112 @@ fn the_user_gets_a_message_when_forwarding_a_message_failes() {
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
-    helpers::send_room_message_from_matrix(
+    helpers::send_notice_message_from_matrix(
         &test.config.as_url,
         RoomId::try_from("!spec_channel_id:localhost").unwrap(),
         UserId::try_from("@spec_user:localhost").unwrap(),
-        "spec message".to_string(),
+        "spec notice".to_string(),
     );
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard bridge message
-    receiver.recv_timeout(default_timeout()).unwrap();
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("[Bot] spec notice"));
+}
 
-    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(message_received_by_matrix.contains("An internal error occurred"));
+#[test]
+fn successfully_forwards_a_location_message_from_matrix_to_rocketchat() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_text_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_location_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "Bern".to_string(),
+        "geo:46.948,7.447;u=35".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("Bern: https://www.openstreetmap.org/?mlat=46.948&mlon=7.447"));
 }
 
 #[test]
-fn the_user_gets_a_message_when_when_getting_the_canonical_room_alias_failes() {
+fn location_messages_with_an_invalid_geo_uri_are_forwarded_as_is() {
     let test = Test::new();
     let (message_forwarder, receiver) = MessageForwarder::new();
-    let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
-    let error_responder = handlers::MatrixErrorResponder {
-        status: status::InternalServerError,
-        message: "Could not get canonical room alias".to_string(),
-    };
-    matrix_router.get(
-        "/_matrix/client/r0/rooms/!spec_channel_id:localhost/state/m.room.canonical_alias",
-        error_responder,
-        "get_room_canonical_room_alias",
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_text_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_location_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "Somewhere".to_string(),
+        "geo:invalid".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("Somewhere: geo:invalid"));
+}
+
+#[test]
+fn location_messages_with_non_finite_coordinates_are_forwarded_as_is() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_text_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_location_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "Somewhere".to_string(),
+        "geo:NaN,NaN".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("Somewhere: geo:NaN,NaN"));
+    assert!(!message_received_by_rocketchat.contains("openstreetmap"));
+}
+
+#[test]
+fn the_rocketchat_message_id_of_a_forwarded_message_is_stored() {
+    let test = Test::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(
+        CHAT_POST_MESSAGE_PATH,
+        handlers::RocketchatPostMessage { message_id: "spec_message_id" },
+        "post_text_message",
     );
 
     let test = test
-        .with_matrix_routes(matrix_router)
         .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
@@ -505,55 +677,780 @@ fn the_user_gets_a_message_when_when_getting_the_canonical_room_alias_failes() {
         "spec message".to_string(),
     );
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard room bridged message
-    receiver.recv_timeout(default_timeout()).unwrap();
-
-    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(message_received_by_matrix.contains("An internal error occurred"));
+    let connection = test.connection_pool.get().unwrap();
+    let bridged_message =
+        BridgedMessage::find_by_rocketchat_message_id(&connection, "rcid", "spec_message_id").unwrap().unwrap();
+    assert_eq!(bridged_message.matrix_room_id, RoomId::try_from("!spec_channel_id:localhost").unwrap());
+    assert_eq!(bridged_message.rocketchat_room_id, "spec_channel_id");
 }
 
 #[test]
-fn the_user_gets_a_message_when_when_getting_the_canonical_room_alias_response_cannot_be_deserialized() {
+fn successfully_forwards_an_edited_message_from_matrix_to_rocketchat() {
     let test = Test::new();
     let (message_forwarder, receiver) = MessageForwarder::new();
-    let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
-    matrix_router.get(
-        "/_matrix/client/r0/rooms/!spec_channel_id:localhost/state/m.room.canonical_alias",
-        handlers::InvalidJsonResponse { status: status::Ok },
-        "get_room_canonical_room_alias",
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_UPDATE_PATH, message_forwarder, "update_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let edited_event_id = EventId::try_from("$edited_event_id:localhost").unwrap();
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &edited_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_edited_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        edited_event_id,
+        "corrected message".to_string(),
     );
 
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"msgId\":\"spec_message_id\""));
+    assert!(message_received_by_rocketchat.contains("\"roomId\":\"spec_channel_id\""));
+    assert!(message_received_by_rocketchat.contains("\"text\":\"corrected message\""));
+}
+
+#[test]
+fn an_edit_of_an_unknown_message_is_posted_as_new_message_without_the_edit_marker() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_text_message");
+
     let test = test
-        .with_matrix_routes(matrix_router)
         .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
-    helpers::send_room_message_from_matrix(
+    helpers::send_edited_message_from_matrix(
         &test.config.as_url,
         RoomId::try_from("!spec_channel_id:localhost").unwrap(),
         UserId::try_from("@spec_user:localhost").unwrap(),
-        "spec message".to_string(),
+        EventId::try_from("$unknown_event_id:localhost").unwrap(),
+        "corrected message".to_string(),
     );
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard room bridged message
-    receiver.recv_timeout(default_timeout()).unwrap();
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"text\":\"corrected message\""));
+}
 
-    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(message_received_by_matrix.contains("An internal error occurred"));
+#[test]
+fn successfully_deletes_a_redacted_message_on_rocketchat() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_DELETE_PATH, message_forwarder, "delete_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let redacted_event_id = EventId::try_from("$redacted_event_id:localhost").unwrap();
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &redacted_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_redaction_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        redacted_event_id.clone(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"msgId\":\"spec_message_id\""));
+    assert!(message_received_by_rocketchat.contains("\"roomId\":\"spec_channel_id\""));
+
+    assert!(BridgedMessage::find_by_matrix_event_id(&connection, &redacted_event_id).unwrap().is_none());
+}
+
+#[test]
+fn redactions_of_events_from_another_room_are_ignored() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_DELETE_PATH, message_forwarder, "delete_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let redacted_event_id = EventId::try_from("$redacted_event_id:localhost").unwrap();
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &redacted_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_redaction_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!other_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        redacted_event_id.clone(),
+    );
+
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+    assert!(BridgedMessage::find_by_matrix_event_id(&connection, &redacted_event_id).unwrap().is_some());
+}
+
+#[test]
+fn redactions_of_events_that_were_not_forwarded_are_ignored() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_DELETE_PATH, message_forwarder, "delete_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_redaction_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        EventId::try_from("$unknown_event_id:localhost").unwrap(),
+    );
+
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn the_user_gets_a_message_in_the_admin_room_when_deleting_a_redacted_message_fails() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("admin_room_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(
+        CHAT_DELETE_PATH,
+        handlers::RocketchatErrorResponder {
+            message: "You are not allowed to delete this message".to_string(),
+            status: status::BadRequest,
+        },
+        "delete_message",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let redacted_event_id = EventId::try_from("$redacted_event_id:localhost").unwrap();
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &redacted_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_redaction_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        redacted_event_id.clone(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard bridge message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Deleting a message you redacted"));
+    assert!(message_received_by_matrix.contains("You are not allowed to delete this message"));
+
+    // the message still exists on Rocket.Chat, so the mapping is kept
+    assert!(BridgedMessage::find_by_matrix_event_id(&connection, &redacted_event_id).unwrap().is_some());
+}
+
+#[test]
+fn successfully_forwards_a_reaction_from_matrix_to_rocketchat() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_REACT_PATH, message_forwarder, "react_to_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let reacted_event_id = EventId::try_from("$reacted_event_id:localhost").unwrap();
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &reacted_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_reaction_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        reacted_event_id,
+        "\u{1f44d}\u{fe0f}".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"messageId\":\"spec_message_id\""));
+    assert!(message_received_by_rocketchat.contains("\"emoji\":\":thumbsup:\""));
+    assert!(message_received_by_rocketchat.contains("\"shouldReact\":true"));
+
+    let reactions = BridgedReaction::find_by_rocketchat_message_id(&connection, "rcid", "spec_message_id").unwrap();
+    assert_eq!(reactions.len(), 1);
+    assert_eq!(reactions[0].emoji, ":thumbsup:");
+}
+
+#[test]
+fn reactions_with_an_emoji_that_rocketchat_does_not_know_are_ignored() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_REACT_PATH, message_forwarder, "react_to_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let reacted_event_id = EventId::try_from("$reacted_event_id:localhost").unwrap();
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &reacted_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_reaction_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        reacted_event_id,
+        "not an emoji".to_string(),
+    );
+
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn successfully_removes_a_redacted_reaction_on_rocketchat() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_REACT_PATH, message_forwarder, "react_to_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let reaction_event_id = EventId::try_from("$reaction_event_id:localhost").unwrap();
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_reaction = NewBridgedReaction {
+        matrix_event_id: &reaction_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_user_id: "spec_user_id",
+        emoji: ":thumbsup:",
+    };
+    BridgedReaction::insert(&connection, &new_bridged_reaction).unwrap();
+
+    helpers::send_redaction_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        reaction_event_id.clone(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"messageId\":\"spec_message_id\""));
+    assert!(message_received_by_rocketchat.contains("\"emoji\":\":thumbsup:\""));
+    assert!(message_received_by_rocketchat.contains("\"shouldReact\":false"));
+
+    assert!(BridgedReaction::find_by_matrix_event_id(&connection, &reaction_event_id).unwrap().is_none());
+}
+
+#[test]
+fn the_user_gets_a_message_when_forwarding_a_message_failes() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(
+        CHAT_POST_MESSAGE_PATH,
+        handlers::RocketchatErrorResponder {
+            message: "Rocketh.Chat chat.postMessage error".to_string(),
+            status: status::InternalServerError,
+        },
+        "post_text_message",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "spec message".to_string(),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard bridge message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("An internal error occurred"));
+}
+
+#[test]
+fn messages_are_forwarded_when_the_canonical_room_alias_was_changed() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_text_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    matrix_api.put_canonical_room_alias(RoomId::try_from("!spec_channel_id:localhost").unwrap(), None).unwrap();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "spec message".to_string(),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("spec message"));
+    assert!(message_received_by_rocketchat.contains("spec_channel_id"));
+}
+
+#[test]
+fn a_reply_to_a_message_in_a_thread_is_posted_in_the_rocketchat_thread() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_chat_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$threaded_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "threaded_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: Some("root_message_id"),
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_related_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "> <@rocketchat_rcid_alice_id:localhost> question\n\nanswer".to_string(),
+        json!({ "m.in_reply_to": { "event_id": "$threaded_event_id:localhost" } }),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"text\":\"answer\""));
+    assert!(message_received_by_rocketchat.contains("\"tmid\":\"root_message_id\""));
+    assert!(!message_received_by_rocketchat.contains("attachments"));
+}
+
+#[test]
+fn a_message_in_a_matrix_thread_is_posted_in_the_rocketchat_thread() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_chat_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$root_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "root_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_related_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "first answer in the thread".to_string(),
+        json!({
+            "rel_type": "m.thread",
+            "event_id": "$root_event_id:localhost",
+            "is_falling_back": true,
+            "m.in_reply_to": { "event_id": "$root_event_id:localhost" }
+        }),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"text\":\"first answer in the thread\""));
+    assert!(message_received_by_rocketchat.contains("\"tmid\":\"root_message_id\""));
+}
+
+#[test]
+fn a_reply_to_a_message_that_is_not_part_of_a_thread_quotes_the_message() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, message_forwarder, "post_chat_message");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$replied_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "replied_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    helpers::send_related_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "> <@rocketchat_rcid_alice_id:localhost> question\n> second line\n\nanswer".to_string(),
+        json!({ "m.in_reply_to": { "event_id": "$replied_event_id:localhost" } }),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"text\":\"answer\""));
+    assert!(message_received_by_rocketchat
+        .contains("\"attachments\":[{\"author_name\":\"alice\",\"text\":\"question\\nsecond line\"}]"));
+    assert!(!message_received_by_rocketchat.contains("tmid"));
+}
+
+#[test]
+fn successfully_renames_the_rocketchat_channel_when_the_room_is_renamed() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHANNELS_RENAME_PATH, message_forwarder, "channels_rename");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_state_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "m.room.name",
+        json!({ "name": "renamed_channel" }),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"roomId\":\"spec_channel_id\""));
+    assert!(message_received_by_rocketchat.contains("\"name\":\"renamed_channel\""));
+}
+
+#[test]
+fn the_rename_that_rocketchat_sends_back_is_not_forwarded_to_matrix_again() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHANNELS_RENAME_PATH, message_forwarder, "channels_rename");
+    let (state_forwarder, state_receiver) = handlers::SendRoomState::with_forwarder();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendStateEventForEmptyKeyEndpoint::router_path(), state_forwarder, "send_state_event_for_key");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // discard all room states that were set during the setup
+    while state_receiver.recv_timeout(default_timeout()).is_ok() {}
+
+    helpers::send_room_state_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "m.room.name",
+        json!({ "name": "renamed_channel" }),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"name\":\"renamed_channel\""));
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("renamed_channel".to_string()),
+        user_id: "spec_user_id".to_string(),
+        user_name: "spec_user".to_string(),
+        text: "renamed_channel".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: Some("r".to_string()),
+    };
+    let payload = to_string(&message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    assert!(state_receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn successfully_sets_the_rocketchat_channel_topic_when_the_room_topic_changes() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHANNELS_SET_TOPIC_PATH, message_forwarder, "channels_set_topic");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_state_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "m.room.topic",
+        json!({ "topic": "New topic" }),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"roomId\":\"spec_channel_id\""));
+    assert!(message_received_by_rocketchat.contains("\"topic\":\"New topic\""));
+}
+
+#[test]
+fn successfully_sets_the_rocketchat_group_topic_when_the_room_topic_changes() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(GROUPS_SET_TOPIC_PATH, message_forwarder, "groups_set_topic");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_group(("spec_group", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_state_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_group_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "m.room.topic",
+        json!({ "topic": "New topic" }),
+    );
+
+    let message_received_by_rocketchat = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("\"roomId\":\"spec_group_id\""));
+    assert!(message_received_by_rocketchat.contains("\"topic\":\"New topic\""));
+}
+
+#[test]
+fn the_user_gets_a_message_in_the_admin_room_when_renaming_the_rocketchat_channel_fails() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("admin_room_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(
+        CHANNELS_RENAME_PATH,
+        handlers::RocketchatErrorResponder {
+            message: "You are not allowed to rename this channel".to_string(),
+            status: status::BadRequest,
+        },
+        "channels_rename",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_state_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "m.room.name",
+        json!({ "name": "renamed_channel" }),
+    );
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard bridge message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Changing the name of room !spec_channel_id:localhost on Rocket.Chat failed"));
+    assert!(message_received_by_matrix.contains("You are not allowed to rename this channel"));
+}
+
+#[test]
+fn room_state_changes_of_the_application_service_are_not_forwarded() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.post(CHANNELS_RENAME_PATH, message_forwarder, "channels_rename");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_state_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@rocketchat_rcid_spec_user_id:localhost").unwrap(),
+        "m.room.name",
+        json!({ "name": "renamed_channel" }),
+    );
+
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
 }
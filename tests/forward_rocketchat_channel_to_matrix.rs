@@ -18,19 +18,26 @@ use std::sync::Mutex;
 
 use http::{Method, StatusCode};
 use iron::{status, Chain};
-use matrix_rocketchat::api::rocketchat::v1::{Attachment, File, Message, UserInfo, CHAT_GET_MESSAGE_PATH};
-use matrix_rocketchat::api::rocketchat::WebhookMessage;
+use matrix_rocketchat::api::rocketchat::v1::{Attachment, File, Message, UserInfo, CHAT_GET_MESSAGE_PATH, USERS_INFO_PATH};
+use matrix_rocketchat::api::rocketchat::{
+    MembershipEvent, WebhookAttachment, WebhookFile, WebhookMembership, WebhookMessage, WebhookReaction,
+};
 use matrix_rocketchat::api::{MatrixApi, RequestData, RestApi};
-use matrix_rocketchat::models::Room;
+use matrix_rocketchat::models::{
+    BridgedMessage, BridgedReaction, NewBridgedMessage, NewBridgedReaction, Room, VirtualUserAvatar,
+};
 use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, DEFAULT_LOGGER, RS_TOKEN};
 use ruma_client_api::r0::account::register::Endpoint as RegisterEndpoint;
 use ruma_client_api::r0::media::create_content::Endpoint as CreateContentEndpoint;
 use ruma_client_api::r0::membership::invite_user::Endpoint as InviteUserEndpoint;
 use ruma_client_api::r0::membership::join_room_by_id::Endpoint as JoinRoomByIdEndpoint;
+use ruma_client_api::r0::membership::leave_room::Endpoint as LeaveRoomEndpoint;
+use ruma_client_api::r0::profile::set_avatar_url::Endpoint as SetAvatarUrlEndpoint;
 use ruma_client_api::r0::profile::set_display_name::Endpoint as SetDisplayNameEndpoint;
 use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::r0::send::send_state_event_for_empty_key::Endpoint as SendStateEventForEmptyKeyEndpoint;
 use ruma_client_api::Endpoint;
-use ruma_identifiers::{RoomId, UserId};
+use ruma_identifiers::{EventId, RoomId, UserId};
 use serde_json::to_string;
 
 #[test]
@@ -64,6 +71,11 @@ fn successfully_forwards_a_message_from_rocketchat_to_matrix_when_the_user_is_no
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -102,7 +114,8 @@ fn successfully_forwards_a_message_from_rocketchat_to_matrix_when_the_user_is_no
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -126,6 +139,11 @@ fn successfully_forwards_a_message_from_rocketchat_to_matrix_when_the_user_is_no
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
         text: "spec_message 2".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let second_payload = to_string(&second_message).unwrap();
 
@@ -169,6 +187,11 @@ fn successfully_forwards_a_message_from_rocketchat_to_matrix_when_the_user_is_re
         user_id: "spec_user_id".to_string(),
         user_name: "spec_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -195,7 +218,8 @@ fn successfully_forwards_a_message_from_rocketchat_to_matrix_when_the_user_is_re
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -229,6 +253,11 @@ fn successfully_forwards_a_message_from_rocketchat_to_matrix_when_the_user_is_re
         user_id: "spec_user_id".to_string(),
         user_name: "spec_user".to_string(),
         text: "spec_message 2".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let second_payload = to_string(&second_message).unwrap();
 
@@ -293,7 +322,8 @@ fn successfully_forwards_an_image_from_rocketchat_to_matrix_when_the_user_is_not
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -305,7 +335,12 @@ fn successfully_forwards_an_image_from_rocketchat_to_matrix_when_the_user_is_not
         channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "Uploaded an image".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -375,7 +410,8 @@ fn successfully_forwards_an_audio_recording_from_rocketchat_to_matrix_when_the_u
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -387,7 +423,12 @@ fn successfully_forwards_an_audio_recording_from_rocketchat_to_matrix_when_the_u
         channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "Uploaded a file".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -457,7 +498,8 @@ fn successfully_forwards_a_video_recording_from_rocketchat_to_matrix_when_the_us
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -469,7 +511,12 @@ fn successfully_forwards_a_video_recording_from_rocketchat_to_matrix_when_the_us
         channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "Uploaded a file".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -530,7 +577,8 @@ fn do_not_forward_an_image_when_there_are_no_attachments() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -542,7 +590,12 @@ fn do_not_forward_an_image_when_there_are_no_attachments() {
         channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "Uploaded an image".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -581,7 +634,8 @@ fn do_not_forward_an_image_when_there_are_is_an_error_when_getting_the_message()
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -593,7 +647,12 @@ fn do_not_forward_an_image_when_there_are_is_an_error_when_getting_the_message()
         channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "Uploaded an image".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -659,7 +718,8 @@ fn do_not_forward_an_image_when_there_are_is_an_error_when_getting_the_file() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard room bridged message
     receiver.recv_timeout(default_timeout()).unwrap();
@@ -671,7 +731,12 @@ fn do_not_forward_an_image_when_there_are_is_an_error_when_getting_the_file() {
         channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "Uploaded an image".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -682,151 +747,247 @@ fn do_not_forward_an_image_when_there_are_is_an_error_when_getting_the_file() {
 }
 
 #[test]
-fn update_the_display_name_when_the_user_changed_it_on_the_rocketchat_server() {
+fn the_description_of_an_uploaded_file_is_forwarded_as_body() {
     let test = Test::new();
-    let (set_display_name_forwarder, set_display_name_receiver) = handlers::MatrixSetDisplayName::with_forwarder();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let uploaded_files = Arc::new(Mutex::new(Vec::new()));
+    let (create_content_forwarder, create_content_receiver) =
+        handlers::MatrixCreateContentHandler::with_forwarder(Arc::clone(&uploaded_files));
     let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SetDisplayNameEndpoint::router_path(), set_display_name_forwarder, "set_display_name");
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    matrix_router.post(CreateContentEndpoint::router_path(), create_content_forwarder, "create_content");
+
+    let attachments = vec![Attachment {
+        description: "Spec image".to_string(),
+        image_size: Some(100),
+        image_type: Some("image/png".to_string()),
+        image_url: Some("/file-upload/image.png".to_string()),
+        mimetype: "image/png".to_string(),
+        title: "Spec titel".to_string(),
+        title_link: "/file-upload/image.png".to_string(),
+    }];
+    let rocketchat_message = Arc::new(Mutex::new(Some(Message {
+        id: "spec_id".to_string(),
+        rid: "spec_rid".to_string(),
+        msg: "".to_string(),
+        ts: "2017-12-12 11:11".to_string(),
+        attachments: Some(attachments),
+        file: Some(File { mimetype: "image/png".to_string() }),
+        u: UserInfo { id: "spec_user_id".to_string(), username: "spec_sender".to_string(), name: "spec sender".to_string() },
+        mentions: Vec::new(),
+        channels: Vec::new(),
+        updated_at: "2017-12-12 11:11".to_string(),
+    })));
+    let rocketchat_message_responder = handlers::RocketchatMessageResponder { message: rocketchat_message };
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(CHAT_GET_MESSAGE_PATH, rocketchat_message_responder, "get_chat_message");
+    let mut files = HashMap::new();
+    files.insert("image.png".to_string(), b"image".to_vec());
+    rocketchat_router.get("/file-upload/:filename", handlers::RocketchatFileResponder { files: files }, "get_file");
 
     let test = test
         .with_matrix_routes(matrix_router)
         .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room bridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
         token: Some(RS_TOKEN.to_string()),
         channel_id: "spec_channel_id".to_string(),
         channel_name: Some("spec_channel".to_string()),
-        user_id: "other_virtual_user_id".to_string(),
-        user_name: "other virtual user".to_string(),
-        text: "spec_message".to_string(),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: Some(vec![WebhookAttachment {
+            title: Some("Spec titel".to_string()),
+            title_link: Some("/file-upload/image.png".to_string()),
+            description: Some("Look at this".to_string()),
+        }]),
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
     helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    let spec_user_display_name_message = set_display_name_receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(spec_user_display_name_message.contains("spec_user"));
-    let other_virtual_user_display_name_message = set_display_name_receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(other_virtual_user_display_name_message.contains("other virtual user"));
-
-    let second_message_with_new_username = WebhookMessage {
-        message_id: "spec_id_2".to_string(),
-        token: Some(RS_TOKEN.to_string()),
-        channel_id: "spec_channel_id".to_string(),
-        channel_name: Some("spec_channel".to_string()),
-        user_id: "other_virtual_user_id".to_string(),
-        user_name: "other virtual user new".to_string(),
-        text: "spec_message 2".to_string(),
-    };
-    let second_payload_with_new_username = to_string(&second_message_with_new_username).unwrap();
-
-    helpers::simulate_message_from_rocketchat(&test.config.as_url, &second_payload_with_new_username);
-
-    let new_display_name_message = set_display_name_receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(new_display_name_message.contains("other virtual user new"));
-
-    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
-    let virtual_spec_user_id = UserId::try_from("@rocketchat_rcid_other_virtual_user_id:localhost").unwrap();
-    let displayname = matrix_api.get_display_name(virtual_spec_user_id).unwrap().unwrap();
+    let file = create_content_receiver.recv_timeout(default_timeout()).unwrap();
+    // this would contain the image data, but for the test this was just a string converted to bytes.
+    assert_eq!(file, "image");
 
-    assert_eq!(displayname, "other virtual user new".to_string());
+    let message = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message.contains("\"body\":\"Look at this\""));
+    assert!(message.contains("m.image"));
+    let files = uploaded_files.lock().unwrap();
+    let file_id = files.first().unwrap();
+    assert!(message.contains(&format!("mxc://localhost/{}", file_id)));
 }
 
 #[test]
-fn message_is_forwarded_even_if_setting_the_display_name_failes() {
+fn a_file_upload_is_detected_by_its_attachments_and_the_attachment_description_is_forwarded_as_body() {
     let test = Test::new();
     let (message_forwarder, receiver) = MessageForwarder::new();
-    let error_responder_active = Arc::new(AtomicBool::new(false));
+    let uploaded_files = Arc::new(Mutex::new(Vec::new()));
+    let (create_content_forwarder, create_content_receiver) =
+        handlers::MatrixCreateContentHandler::with_forwarder(Arc::clone(&uploaded_files));
     let mut matrix_router = test.default_matrix_routes();
     matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
-    let set_display_name = handlers::MatrixSetDisplayName {};
-    let error_responder = handlers::MatrixActivatableErrorResponder {
-        status: status::InternalServerError,
-        message: "Could not set display name".to_string(),
-        active: Arc::clone(&error_responder_active),
-    };
-    let mut set_display_name_with_error = Chain::new(set_display_name);
-    set_display_name_with_error.link_before(error_responder);
-    matrix_router.put(SetDisplayNameEndpoint::router_path(), set_display_name_with_error, "set_display_name");
+    matrix_router.post(CreateContentEndpoint::router_path(), create_content_forwarder, "create_content");
+
+    let attachments = vec![Attachment {
+        description: "Spec image".to_string(),
+        image_size: Some(100),
+        image_type: Some("image/png".to_string()),
+        image_url: Some("/file-upload/image.png".to_string()),
+        mimetype: "image/png".to_string(),
+        title: "Spec titel".to_string(),
+        title_link: "/file-upload/image.png".to_string(),
+    }];
+    let rocketchat_message = Arc::new(Mutex::new(Some(Message {
+        id: "spec_id".to_string(),
+        rid: "spec_rid".to_string(),
+        msg: "".to_string(),
+        ts: "2017-12-12 11:11".to_string(),
+        attachments: Some(attachments),
+        file: Some(File { mimetype: "image/png".to_string() }),
+        u: UserInfo { id: "spec_user_id".to_string(), username: "spec_sender".to_string(), name: "spec sender".to_string() },
+        mentions: Vec::new(),
+        channels: Vec::new(),
+        updated_at: "2017-12-12 11:11".to_string(),
+    })));
+    let rocketchat_message_responder = handlers::RocketchatMessageResponder { message: rocketchat_message };
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(CHAT_GET_MESSAGE_PATH, rocketchat_message_responder, "get_chat_message");
+    let mut files = HashMap::new();
+    files.insert("image.png".to_string(), b"image".to_vec());
+    rocketchat_router.get("/file-upload/:filename", handlers::RocketchatFileResponder { files: files }, "get_file");
 
     let test = test
         .with_matrix_routes(matrix_router)
         .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
-    error_responder_active.store(true, Ordering::Relaxed);
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room bridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
 
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
         token: Some(RS_TOKEN.to_string()),
         channel_id: "spec_channel_id".to_string(),
         channel_name: Some("spec_channel".to_string()),
-        user_id: "virtual_spec_user_id".to_string(),
-        user_name: "virtual_spec_user".to_string(),
-        text: "spec_message".to_string(),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "Hat ein Bild hochgeladen".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: Some(vec![WebhookAttachment {
+            title: Some("Spec titel".to_string()),
+            title_link: Some("/file-upload/image.png".to_string()),
+            description: Some("Schau dir das an".to_string()),
+        }]),
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
     helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard room bridged message
-    receiver.recv_timeout(default_timeout()).unwrap();
-
-    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
-    assert!(message_received_by_matrix.contains("spec_message"));
-}
-
-#[test]
-fn rocketchat_sends_mal_formatted_json() {
-    let test = Test::new().run();
-    let payload = "bad_json";
-
-    let url = format!("{}/rocketchat", &test.config.as_url);
-
-    let params = HashMap::new();
-    let (_, status_code) = RestApi::call(&Method::POST, &url, RequestData::Body(payload), &params, None).unwrap();
+    let file = create_content_receiver.recv_timeout(default_timeout()).unwrap();
+    // this would contain the image data, but for the test this was just a string converted to bytes.
+    assert_eq!(file, "image");
 
-    assert_eq!(status_code, StatusCode::UNPROCESSABLE_ENTITY)
+    let message = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message.contains("\"body\":\"Schau dir das an\""));
+    assert!(message.contains("m.image"));
+    let files = uploaded_files.lock().unwrap();
+    let file_id = files.first().unwrap();
+    assert!(message.contains(&format!("mxc://localhost/{}", file_id)));
 }
 
 #[test]
-fn no_message_is_forwarded_when_inviting_the_user_failes() {
+fn the_file_title_is_forwarded_as_body_when_the_file_has_no_description() {
     let test = Test::new();
     let (message_forwarder, receiver) = MessageForwarder::new();
+    let uploaded_files = Arc::new(Mutex::new(Vec::new()));
+    let (create_content_forwarder, create_content_receiver) =
+        handlers::MatrixCreateContentHandler::with_forwarder(Arc::clone(&uploaded_files));
     let mut matrix_router = test.default_matrix_routes();
     matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
-    let invite_handler = handlers::MatrixInviteUser { as_url: test.config.as_url.clone() };
-    let conditional_error = handlers::MatrixConditionalErrorResponder {
-        status: status::InternalServerError,
-        message: "Could not invite user".to_string(),
-        conditional_content: "new_user",
-    };
-    let mut invite_with_error = Chain::new(invite_handler);
-    invite_with_error.link_before(conditional_error);
-    matrix_router.post(InviteUserEndpoint::router_path(), invite_with_error, "invite_user_spec_channel");
+    matrix_router.post(CreateContentEndpoint::router_path(), create_content_forwarder, "create_content");
+
+    let attachments = vec![Attachment {
+        description: "Spec image".to_string(),
+        image_size: Some(100),
+        image_type: Some("image/png".to_string()),
+        image_url: Some("/file-upload/image.png".to_string()),
+        mimetype: "image/png".to_string(),
+        title: "Spec titel".to_string(),
+        title_link: "/file-upload/image.png".to_string(),
+    }];
+    let rocketchat_message = Arc::new(Mutex::new(Some(Message {
+        id: "spec_id".to_string(),
+        rid: "spec_rid".to_string(),
+        msg: "".to_string(),
+        ts: "2017-12-12 11:11".to_string(),
+        attachments: Some(attachments),
+        file: Some(File { mimetype: "image/png".to_string() }),
+        u: UserInfo { id: "spec_user_id".to_string(), username: "spec_sender".to_string(), name: "spec sender".to_string() },
+        mentions: Vec::new(),
+        channels: Vec::new(),
+        updated_at: "2017-12-12 11:11".to_string(),
+    })));
+    let rocketchat_message_responder = handlers::RocketchatMessageResponder { message: rocketchat_message };
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(CHAT_GET_MESSAGE_PATH, rocketchat_message_responder, "get_chat_message");
+    let mut files = HashMap::new();
+    files.insert("image.png".to_string(), b"image".to_vec());
+    rocketchat_router.get("/file-upload/:filename", handlers::RocketchatFileResponder { files: files }, "get_file");
 
     let test = test
         .with_matrix_routes(matrix_router)
         .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room bridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
         token: Some(RS_TOKEN.to_string()),
@@ -834,65 +995,77 @@ fn no_message_is_forwarded_when_inviting_the_user_failes() {
         channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "spec_message".to_string(),
+        text: "Uploaded an image".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: Some(vec![WebhookAttachment {
+            title: Some("Spec titel".to_string()),
+            title_link: Some("/file-upload/image.png".to_string()),
+            description: None,
+        }]),
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard room bridged message
-    receiver.recv_timeout(default_timeout()).unwrap();
-
     helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    // no message is forwarded
-    assert!(receiver.recv_timeout(default_timeout()).is_err());
+    let file = create_content_receiver.recv_timeout(default_timeout()).unwrap();
+    // this would contain the image data, but for the test this was just a string converted to bytes.
+    assert_eq!(file, "image");
+
+    let message = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message.contains("\"body\":\"Spec titel\""));
+    assert!(message.contains("m.image"));
+    let files = uploaded_files.lock().unwrap();
+    let file_id = files.first().unwrap();
+    assert!(message.contains(&format!("mxc://localhost/{}", file_id)));
 }
 
 #[test]
-fn ignore_messages_to_a_room_that_is_not_bridged() {
+fn a_text_message_that_looks_like_a_file_upload_is_forwarded_as_text() {
     let test = Test::new();
-    let (message_forwarder, receiver) = MessageForwarder::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
     let mut matrix_router = test.default_matrix_routes();
     matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
 
-    let test =
-        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
 
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
         token: Some(RS_TOKEN.to_string()),
-        channel_id: "not_bridged_channel_id".to_string(),
-        channel_name: Some("not_bridged_channel".to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
         user_id: "new_user_id".to_string(),
         user_name: "new_spec_user".to_string(),
-        text: "spec_message".to_string(),
+        text: "Uploaded an image".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-
     helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    // no message is forwarded
-    assert!(receiver.recv_timeout(default_timeout()).is_err());
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"body\":\"Uploaded an image\""));
+    assert!(message_received_by_matrix.contains("m.text"));
 }
 
 #[test]
-fn ignore_messages_forwarded_from_rocketchat_if_the_non_virtual_user_just_sent_a_message_on_matrix_to_avoid_loops() {
+fn update_the_display_name_when_the_user_changed_it_on_the_rocketchat_server() {
     let test = Test::new();
-    let (message_forwarder, receiver) = MessageForwarder::new();
+    let (set_display_name_forwarder, set_display_name_receiver) = handlers::MatrixSetDisplayName::with_forwarder();
     let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    matrix_router.put(SetDisplayNameEndpoint::router_path(), set_display_name_forwarder, "set_display_name");
 
     let test = test
         .with_matrix_routes(matrix_router)
@@ -902,133 +1075,1464 @@ fn ignore_messages_forwarded_from_rocketchat_if_the_non_virtual_user_just_sent_a
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
-    helpers::send_room_message_from_matrix(
-        &test.config.as_url,
-        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
-        UserId::try_from("@spec_user:localhost").unwrap(),
-        "message from Matrix".to_string(),
-    );
-
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
         token: Some(RS_TOKEN.to_string()),
         channel_id: "spec_channel_id".to_string(),
         channel_name: Some("spec_channel".to_string()),
-        user_id: "spec_user_id".to_string(),
-        user_name: "spec_user".to_string(),
+        user_id: "other_virtual_user_id".to_string(),
+        user_name: "other virtual user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
     helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard room bridged message
-    receiver.recv_timeout(default_timeout()).unwrap();
+    let spec_user_display_name_message = set_display_name_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(spec_user_display_name_message.contains("spec_user"));
+    let other_virtual_user_display_name_message = set_display_name_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(other_virtual_user_display_name_message.contains("other virtual user"));
 
-    assert!(receiver.recv_timeout(default_timeout()).is_err());
+    let second_message_with_new_username = WebhookMessage {
+        message_id: "spec_id_2".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "other_virtual_user_id".to_string(),
+        user_name: "other virtual user new".to_string(),
+        text: "spec_message 2".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let second_payload_with_new_username = to_string(&second_message_with_new_username).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &second_payload_with_new_username);
+
+    let new_display_name_message = set_display_name_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(new_display_name_message.contains("other virtual user new"));
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let virtual_spec_user_id = UserId::try_from("@rocketchat_rcid_other_virtual_user_id:localhost").unwrap();
+    let displayname = matrix_api.get_display_name(virtual_spec_user_id).unwrap().unwrap();
+
+    assert_eq!(displayname, "other virtual user new".to_string());
 }
 
 #[test]
-fn do_not_forward_messages_when_the_channel_was_bridged_but_is_unbridged_now() {
+fn the_avatar_of_the_rocketchat_user_is_set_for_the_virtual_user() {
     let test = Test::new();
-    let (message_forwarder, receiver) = MessageForwarder::new();
+    let (set_avatar_url_forwarder, set_avatar_url_receiver) = MessageForwarder::new();
+    let uploaded_files = Arc::new(Mutex::new(Vec::new()));
+    let (create_content_forwarder, create_content_receiver) =
+        handlers::MatrixCreateContentHandler::with_forwarder(Arc::clone(&uploaded_files));
     let mut matrix_router = test.default_matrix_routes();
-    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    matrix_router.put(SetAvatarUrlEndpoint::router_path(), set_avatar_url_forwarder, "set_avatar_url");
+    matrix_router.post(CreateContentEndpoint::router_path(), create_content_forwarder, "create_content");
+
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        "/avatar/:username",
+        handlers::RocketchatAvatarResponder { avatar: b"avatar".to_vec(), etag: "\"spec_etag\"" },
+        "avatar",
+    );
 
     let test = test
         .with_matrix_routes(matrix_router)
         .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
         .with_bridged_room(("spec_channel", vec!["spec_user"]))
         .run();
 
-    helpers::leave_room(
-        &test.config,
-        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
-        UserId::try_from("@spec_user:localhost").unwrap(),
-    );
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
 
-    helpers::send_room_message_from_matrix(
-        &test.config.as_url,
-        RoomId::try_from("!admin_room_id:localhost").unwrap(),
-        UserId::try_from("@spec_user:localhost").unwrap(),
-        "unbridge spec_channel".to_string(),
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let file = create_content_receiver.recv_timeout(default_timeout()).unwrap();
+    assert_eq!(file, "avatar");
+
+    let set_avatar_url_message = set_avatar_url_receiver.recv_timeout(default_timeout()).unwrap();
+    let files = uploaded_files.lock().unwrap();
+    let file_id = files.first().unwrap();
+    assert!(set_avatar_url_message.contains(&format!("mxc://localhost/{}", file_id)));
+
+    let connection = test.connection_pool.get().unwrap();
+    let user_id = UserId::try_from("@rocketchat_rcid_new_user_id:localhost").unwrap();
+    let virtual_user_avatar = VirtualUserAvatar::find_by_matrix_user_id(&connection, &user_id).unwrap().unwrap();
+    assert_eq!(virtual_user_avatar.avatar_etag, "\"spec_etag\"");
+    assert_eq!(virtual_user_avatar.avatar_url, format!("mxc://localhost/{}", file_id));
+}
+
+#[test]
+fn a_stable_hash_of_the_avatar_is_stored_when_rocketchat_does_not_send_an_etag() {
+    let test = Test::new();
+    let (set_avatar_url_forwarder, set_avatar_url_receiver) = MessageForwarder::new();
+    let uploaded_files = Arc::new(Mutex::new(Vec::new()));
+    let (create_content_forwarder, create_content_receiver) =
+        handlers::MatrixCreateContentHandler::with_forwarder(Arc::clone(&uploaded_files));
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SetAvatarUrlEndpoint::router_path(), set_avatar_url_forwarder, "set_avatar_url");
+    matrix_router.post(CreateContentEndpoint::router_path(), create_content_forwarder, "create_content");
+
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        "/avatar/:username",
+        handlers::RocketchatAvatarResponder { avatar: b"avatar".to_vec(), etag: "" },
+        "avatar",
     );
 
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
         token: Some(RS_TOKEN.to_string()),
         channel_id: "spec_channel_id".to_string(),
         channel_name: Some("spec_channel".to_string()),
-        user_id: "virtual_spec_user_id".to_string(),
-        user_name: "virtual_spec_user".to_string(),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
     helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    // discard welcome message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard connect message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard room bridged message
-    receiver.recv_timeout(default_timeout()).unwrap();
-    // discard room unbridged message
-    receiver.recv_timeout(default_timeout()).unwrap();
+    let file = create_content_receiver.recv_timeout(default_timeout()).unwrap();
+    assert_eq!(file, "avatar");
 
-    assert!(receiver.recv_timeout(default_timeout()).is_err());
+    let set_avatar_url_message = set_avatar_url_receiver.recv_timeout(default_timeout()).unwrap();
+    let files = uploaded_files.lock().unwrap();
+    let file_id = files.first().unwrap();
+    assert!(set_avatar_url_message.contains(&format!("mxc://localhost/{}", file_id)));
+
+    let connection = test.connection_pool.get().unwrap();
+    let user_id = UserId::try_from("@rocketchat_rcid_new_user_id:localhost").unwrap();
+    let virtual_user_avatar = VirtualUserAvatar::find_by_matrix_user_id(&connection, &user_id).unwrap().unwrap();
+    // FNV-1a hash of the avatar content
+    assert_eq!(virtual_user_avatar.avatar_etag, "dfe2526362bc3224");
+    assert_eq!(virtual_user_avatar.avatar_url, format!("mxc://localhost/{}", file_id));
 }
 
 #[test]
-fn returns_unauthorized_when_the_rs_token_is_missing() {
-    let test = Test::new().run();
+fn the_avatar_is_not_uploaded_again_if_it_did_not_change() {
+    let test = Test::new();
+    let (set_avatar_url_forwarder, set_avatar_url_receiver) = MessageForwarder::new();
+    let uploaded_files = Arc::new(Mutex::new(Vec::new()));
+    let (create_content_forwarder, create_content_receiver) =
+        handlers::MatrixCreateContentHandler::with_forwarder(Arc::clone(&uploaded_files));
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SetAvatarUrlEndpoint::router_path(), set_avatar_url_forwarder, "set_avatar_url");
+    matrix_router.post(CreateContentEndpoint::router_path(), create_content_forwarder, "create_content");
+
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        "/avatar/:username",
+        handlers::RocketchatAvatarResponder { avatar: b"avatar".to_vec(), etag: "\"spec_etag\"" },
+        "avatar",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
-        token: None,
+        token: Some(RS_TOKEN.to_string()),
         channel_id: "spec_channel_id".to_string(),
         channel_name: Some("spec_channel".to_string()),
-        user_id: "virtual_spec_user_id".to_string(),
-        user_name: "virtual_spec_user".to_string(),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    let url = format!("{}/rocketchat", &test.config.as_url);
+    create_content_receiver.recv_timeout(default_timeout()).unwrap();
+    set_avatar_url_receiver.recv_timeout(default_timeout()).unwrap();
 
-    let params = HashMap::new();
-    let (_, status_code) = RestApi::call(&Method::POST, &url, RequestData::Body(payload), &params, None).unwrap();
+    let second_message = WebhookMessage {
+        message_id: "spec_id_2".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "spec_message 2".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let second_payload = to_string(&second_message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &second_payload);
 
-    assert_eq!(status_code, StatusCode::UNAUTHORIZED)
+    assert!(create_content_receiver.recv_timeout(default_timeout()).is_err());
+    assert!(set_avatar_url_receiver.recv_timeout(default_timeout()).is_err());
 }
 
 #[test]
-fn returns_forbidden_when_the_rs_token_does_not_match_a_server() {
-    let test = Test::new().run();
+fn message_is_forwarded_even_if_setting_the_display_name_failes() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let error_responder_active = Arc::new(AtomicBool::new(false));
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let set_display_name = handlers::MatrixSetDisplayName {};
+    let error_responder = handlers::MatrixActivatableErrorResponder {
+        status: status::InternalServerError,
+        message: "Could not set display name".to_string(),
+        active: Arc::clone(&error_responder_active),
+    };
+    let mut set_display_name_with_error = Chain::new(set_display_name);
+    set_display_name_with_error.link_before(error_responder);
+    matrix_router.put(SetDisplayNameEndpoint::router_path(), set_display_name_with_error, "set_display_name");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    error_responder_active.store(true, Ordering::Relaxed);
+
     let message = WebhookMessage {
         message_id: "spec_id".to_string(),
-        token: Some("wrong_token".to_string()),
+        token: Some(RS_TOKEN.to_string()),
         channel_id: "spec_channel_id".to_string(),
         channel_name: Some("spec_channel".to_string()),
         user_id: "virtual_spec_user_id".to_string(),
         user_name: "virtual_spec_user".to_string(),
         text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
-    let url = format!("{}/rocketchat", &test.config.as_url);
-
-    let params = HashMap::new();
-    let (_, status_code) = RestApi::call(&Method::POST, &url, RequestData::Body(payload), &params, None).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
 
-    assert_eq!(status_code, StatusCode::FORBIDDEN)
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room bridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("spec_message"));
+}
+
+#[test]
+fn rocketchat_sends_mal_formatted_json() {
+    let test = Test::new().run();
+    let payload = "bad_json";
+
+    let url = format!("{}/rocketchat", &test.config.as_url);
+
+    let params = HashMap::new();
+    let (_, status_code) = RestApi::call(&Method::POST, &url, RequestData::Body(payload), &params, None).unwrap();
+
+    assert_eq!(status_code, StatusCode::UNPROCESSABLE_ENTITY)
+}
+
+#[test]
+fn no_message_is_forwarded_when_inviting_the_user_failes() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let invite_handler = handlers::MatrixInviteUser { as_url: test.config.as_url.clone() };
+    let conditional_error = handlers::MatrixConditionalErrorResponder {
+        status: status::InternalServerError,
+        message: "Could not invite user".to_string(),
+        conditional_content: "new_user",
+    };
+    let mut invite_with_error = Chain::new(invite_handler);
+    invite_with_error.link_before(conditional_error);
+    matrix_router.post(InviteUserEndpoint::router_path(), invite_with_error, "invite_user_spec_channel");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room bridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    // no message is forwarded
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn ignore_messages_to_a_room_that_is_not_bridged() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test =
+        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "not_bridged_channel_id".to_string(),
+        channel_name: Some("not_bridged_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    // no message is forwarded
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn ignore_messages_forwarded_from_rocketchat_if_the_non_virtual_user_just_sent_a_message_on_matrix_to_avoid_loops() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "message from Matrix".to_string(),
+    );
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "spec_user_id".to_string(),
+        user_name: "spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room bridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn do_not_forward_messages_when_the_channel_was_bridged_but_is_unbridged_now() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    helpers::leave_room(
+        &test.config,
+        RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!admin_room_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "unbridge spec_channel".to_string(),
+    );
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "virtual_spec_user_id".to_string(),
+        user_name: "virtual_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    // discard welcome message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard connect message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room bridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
+    // discard room unbridged message
+    receiver.recv_timeout(default_timeout()).unwrap();
+
+    assert!(receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn successfully_forwards_a_formatted_message_from_rocketchat_to_matrix() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "*bold* and ~struck~".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"body\":\"*bold* and ~struck~\""));
+    assert!(message_received_by_matrix.contains("\"format\":\"org.matrix.custom.html\""));
+    assert!(message_received_by_matrix.contains("\"formatted_body\":\"<p><strong>bold</strong> and <del>struck</del></p>\""));
+}
+
+#[test]
+fn rocketchat_mentions_are_forwarded_as_pills_and_room_mentions() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "hello @spec_user, @alice and @here".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"body\":\"hello spec_user, alice and @room\""));
+    assert!(message_received_by_matrix.contains("<a href=\\\"https://matrix.to/#/@spec_user:localhost\\\">spec_user</a>"));
+    assert!(message_received_by_matrix
+        .contains("<a href=\\\"https://matrix.to/#/@rocketchat_rcid_alice_id:localhost\\\">alice</a>"));
+}
+
+#[test]
+fn rocketchat_mentions_in_code_are_not_replaced_and_display_names_are_escaped() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    matrix_api.set_display_name(UserId::try_from("@spec_user:localhost").unwrap(), "Spec *User* [1]".to_string()).unwrap();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "hello @spec_user and `@alice`\n```\n@here\n```".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"body\":\"hello Spec *User* [1] and `@alice`\\n```\\n@here\\n```\""));
+    assert!(message_received_by_matrix
+        .contains("<a href=\\\"https://matrix.to/#/@spec_user:localhost\\\">Spec *User* [1]</a> and <code>@alice</code>"));
+    assert!(!message_received_by_matrix.contains("@room"));
+}
+
+#[test]
+fn successfully_forwards_a_reaction_from_rocketchat_to_matrix() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("m.reaction");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$reacted_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    let mut reactions = HashMap::new();
+    reactions.insert(":thumbsup:".to_string(), WebhookReaction { usernames: vec!["new_spec_user".to_string()] });
+    let message = WebhookMessage {
+        message_id: "spec_message_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "spec_user_id".to_string(),
+        user_name: "spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: Some(reactions),
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let reaction_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(reaction_received_by_matrix.contains("\"rel_type\":\"m.annotation\""));
+    assert!(reaction_received_by_matrix.contains("\"event_id\":\"$reacted_event_id:localhost\""));
+    assert!(reaction_received_by_matrix.contains("\"key\":\"\u{1f44d}\""));
+}
+
+#[test]
+fn successfully_removes_a_reaction_in_matrix_that_was_removed_on_rocketchat() {
+    let test = Test::new();
+    let (redact_forwarder, redact_receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put("/_matrix/client/r0/rooms/:room_id/redact/:event_id/:txn_id", redact_forwarder, "redact");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // make sure the virtual user of the reacting Rocket.Chat user joined the room
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$reacted_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+    let reaction_event_id = EventId::try_from("$reaction_event_id:localhost").unwrap();
+    let new_bridged_reaction = NewBridgedReaction {
+        matrix_event_id: &reaction_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_user_id: "new_user_id",
+        emoji: ":thumbsup:",
+    };
+    BridgedReaction::insert(&connection, &new_bridged_reaction).unwrap();
+
+    let message = WebhookMessage {
+        message_id: "spec_message_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "spec_user_id".to_string(),
+        user_name: "spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: Some(HashMap::new()),
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    assert!(redact_receiver.recv_timeout(default_timeout()).is_ok());
+    assert!(BridgedReaction::find_by_matrix_event_id(&connection, &reaction_event_id).unwrap().is_none());
+}
+
+#[test]
+fn reactions_of_a_new_message_are_forwarded_after_the_message() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut send_message_chain = Chain::new(handlers::SendMessageEvent {});
+    send_message_chain.link_before(message_forwarder);
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), send_message_chain, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let mut reactions = HashMap::new();
+    reactions.insert(":thumbsup:".to_string(), WebhookReaction { usernames: vec!["other_user".to_string()] });
+    let message = WebhookMessage {
+        message_id: "spec_message_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: Some(reactions),
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let messages: Vec<String> = receiver.try_iter().collect();
+    let message_index = messages.iter().position(|message| message.contains("\"body\":\"spec_message\"")).unwrap();
+    let reaction_index = messages.iter().position(|message| message.contains("\"rel_type\":\"m.annotation\"")).unwrap();
+    assert!(message_index < reaction_index);
+    assert!(messages[reaction_index].contains("\"key\":\"\u{1f44d}\""));
+}
+
+#[test]
+fn a_message_that_is_received_again_with_new_reactions_is_only_forwarded_once() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::new();
+    let mut send_message_chain = Chain::new(handlers::SendMessageEvent {});
+    send_message_chain.link_before(message_forwarder);
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), send_message_chain, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    for emoji in &[":thumbsup:", ":smile:"] {
+        let mut reactions = HashMap::new();
+        reactions.insert(emoji.to_string(), WebhookReaction { usernames: vec!["other_user".to_string()] });
+        let message = WebhookMessage {
+            message_id: "spec_message_id".to_string(),
+            token: Some(RS_TOKEN.to_string()),
+            channel_id: "spec_channel_id".to_string(),
+            channel_name: Some("spec_channel".to_string()),
+            user_id: "new_user_id".to_string(),
+            user_name: "new_spec_user".to_string(),
+            text: "spec_message".to_string(),
+            reactions: Some(reactions),
+            tmid: None,
+            file: None,
+            attachments: None,
+            message_type: None,
+        };
+        let payload = to_string(&message).unwrap();
+        helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+    }
+
+    let messages: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(messages.iter().filter(|message| message.contains("\"body\":\"spec_message\"")).count(), 1);
+    assert!(messages.iter().any(|message| message.contains("\"key\":\"\u{1f604}\"")));
+}
+
+#[test]
+fn reactions_are_kept_when_the_reacting_users_cannot_be_looked_up() {
+    let test = Test::new();
+    let (redact_forwarder, redact_receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put("/_matrix/client/r0/rooms/:room_id/redact/:event_id/:txn_id", redact_forwarder, "redact");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        USERS_INFO_PATH,
+        handlers::RocketchatErrorResponder { message: "User not found".to_string(), status: status::BadRequest },
+        "users_info",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$reacted_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+    let reaction_event_id = EventId::try_from("$reaction_event_id:localhost").unwrap();
+    let new_bridged_reaction = NewBridgedReaction {
+        matrix_event_id: &reaction_event_id,
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "spec_message_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_user_id: "new_user_id",
+        emoji: ":thumbsup:",
+    };
+    BridgedReaction::insert(&connection, &new_bridged_reaction).unwrap();
+
+    let mut reactions = HashMap::new();
+    reactions.insert(":thumbsup:".to_string(), WebhookReaction { usernames: vec!["new_spec_user".to_string()] });
+    let message = WebhookMessage {
+        message_id: "spec_message_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "spec_user_id".to_string(),
+        user_name: "spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: Some(reactions),
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+    let (_, status_code) = helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    assert_eq!(status_code, StatusCode::OK);
+    assert!(redact_receiver.recv_timeout(default_timeout()).is_err());
+    assert!(BridgedReaction::find_by_matrix_event_id(&connection, &reaction_event_id).unwrap().is_some());
+}
+
+#[test]
+fn returns_unauthorized_when_the_rs_token_is_missing() {
+    let test = Test::new().run();
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: None,
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "virtual_spec_user_id".to_string(),
+        user_name: "virtual_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    let url = format!("{}/rocketchat", &test.config.as_url);
+
+    let params = HashMap::new();
+    let (_, status_code) = RestApi::call(&Method::POST, &url, RequestData::Body(payload), &params, None).unwrap();
+
+    assert_eq!(status_code, StatusCode::UNAUTHORIZED)
+}
+
+#[test]
+fn returns_forbidden_when_the_rs_token_does_not_match_a_server() {
+    let test = Test::new().run();
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some("wrong_token".to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "virtual_spec_user_id".to_string(),
+        user_name: "virtual_spec_user".to_string(),
+        text: "spec_message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    let url = format!("{}/rocketchat", &test.config.as_url);
+
+    let params = HashMap::new();
+    let (_, status_code) = RestApi::call(&Method::POST, &url, RequestData::Body(payload), &params, None).unwrap();
+
+    assert_eq!(status_code, StatusCode::FORBIDDEN)
+}
+
+#[test]
+fn a_message_in_a_rocketchat_thread_is_sent_as_part_of_the_matrix_thread() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$root_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "root_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "answer in the thread".to_string(),
+        reactions: None,
+        tmid: Some("root_message_id".to_string()),
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"body\":\"answer in the thread\""));
+    assert!(message_received_by_matrix.contains("\"rel_type\":\"m.thread\""));
+    assert!(message_received_by_matrix.contains("\"event_id\":\"$root_event_id:localhost\""));
+    assert!(message_received_by_matrix.contains("\"is_falling_back\":true"));
+    assert!(message_received_by_matrix.contains("\"m.in_reply_to\":{\"event_id\":\"$root_event_id:localhost\"}"));
+}
+
+#[test]
+fn a_message_in_a_rocketchat_thread_replies_to_the_last_bridged_message_of_the_thread() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // the messages are stored within the same second, so that their timestamps are equal
+    let connection = test.connection_pool.get().unwrap();
+    for &(event_id, message_id, thread_id) in &[
+        ("$root_event_id:localhost", "root_message_id", None),
+        ("$first_answer_event_id:localhost", "first_answer_id", Some("root_message_id")),
+        ("$second_answer_event_id:localhost", "second_answer_id", Some("root_message_id")),
+    ] {
+        let new_bridged_message = NewBridgedMessage {
+            matrix_event_id: &EventId::try_from(event_id).unwrap(),
+            matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+            rocketchat_message_id: message_id,
+            rocketchat_room_id: "spec_channel_id",
+            rocketchat_server_id: "rcid",
+            rocketchat_thread_id: thread_id,
+        };
+        BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+    }
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "third answer in the thread".to_string(),
+        reactions: None,
+        tmid: Some("root_message_id".to_string()),
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"event_id\":\"$root_event_id:localhost\""));
+    assert!(message_received_by_matrix.contains("\"m.in_reply_to\":{\"event_id\":\"$second_answer_event_id:localhost\"}"));
+}
+
+#[test]
+fn a_message_in_a_rocketchat_thread_without_a_bridged_root_is_sent_as_regular_message() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "answer in the thread".to_string(),
+        reactions: None,
+        tmid: Some("unknown_message_id".to_string()),
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"body\":\"answer in the thread\""));
+    assert!(!message_received_by_matrix.contains("m.relates_to"));
+}
+
+#[test]
+fn a_quoted_rocketchat_message_is_sent_as_matrix_reply() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_channel_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    let new_bridged_message = NewBridgedMessage {
+        matrix_event_id: &EventId::try_from("$quoted_event_id:localhost").unwrap(),
+        matrix_room_id: &RoomId::try_from("!spec_channel_id:localhost").unwrap(),
+        rocketchat_message_id: "quoted_message_id",
+        rocketchat_room_id: "spec_channel_id",
+        rocketchat_server_id: "rcid",
+        rocketchat_thread_id: None,
+    };
+    BridgedMessage::insert(&connection, &new_bridged_message).unwrap();
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "[ ](http://localhost/channel/spec_channel?msg=quoted_message_id) the answer".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("\"body\":\"the answer\""));
+    assert!(message_received_by_matrix
+        .contains("\"m.relates_to\":{\"m.in_reply_to\":{\"event_id\":\"$quoted_event_id:localhost\"}}"));
+}
+
+#[test]
+fn the_virtual_user_joins_the_room_when_the_rocketchat_user_joins_the_channel() {
+    let test = Test::new();
+    let (invite_forwarder, invite_receiver) = handlers::MatrixInviteUser::with_forwarder(test.config.as_url.clone());
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.post(InviteUserEndpoint::router_path(), invite_forwarder, "invite_user");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // discard admin room invite
+    invite_receiver.recv_timeout(default_timeout()).unwrap();
+    // discard invites during bridging
+    invite_receiver.recv_timeout(default_timeout()).unwrap();
+    invite_receiver.recv_timeout(default_timeout()).unwrap();
+
+    let membership = WebhookMembership {
+        event: MembershipEvent::RoomJoined,
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+    };
+    let payload = to_string(&membership).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let invite_message = invite_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(invite_message.contains("@rocketchat_rcid_new_user_id:localhost"));
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room_id = RoomId::try_from("!spec_channel_id:localhost").unwrap();
+    let room = Room::new(&test.config, &DEFAULT_LOGGER, &(*matrix_api), room_id);
+    let user_ids = room.user_ids(None).unwrap();
+    assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_new_user_id:localhost").unwrap()));
+}
+
+#[test]
+fn the_virtual_user_leaves_the_room_when_the_rocketchat_user_leaves_the_channel() {
+    let test = Test::new();
+    let (leave_room, leave_receiver) = handlers::MatrixLeaveRoom::with_forwarder(test.config.as_url.clone());
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.post(LeaveRoomEndpoint::router_path(), leave_room, "leave_room");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user", "other_user"]))
+        .run();
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room_id = RoomId::try_from("!spec_channel_id:localhost").unwrap();
+    let room = Room::new(&test.config, &DEFAULT_LOGGER, &(*matrix_api), room_id);
+    let virtual_user_id = UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap();
+    assert!(room.user_ids(None).unwrap().iter().any(|id| id == &virtual_user_id));
+
+    let membership = WebhookMembership {
+        event: MembershipEvent::RoomLeft,
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+    };
+    let payload = to_string(&membership).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    leave_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(!room.user_ids(None).unwrap().iter().any(|id| id == &virtual_user_id));
+}
+
+#[test]
+fn membership_changes_in_channels_that_are_not_bridged_are_ignored() {
+    let test = Test::new();
+    let (invite_forwarder, invite_receiver) = handlers::MatrixInviteUser::with_forwarder(test.config.as_url.clone());
+    let (leave_room, leave_receiver) = handlers::MatrixLeaveRoom::with_forwarder(test.config.as_url.clone());
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.post(InviteUserEndpoint::router_path(), invite_forwarder, "invite_user");
+    matrix_router.post(LeaveRoomEndpoint::router_path(), leave_room, "leave_room");
+
+    let test =
+        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    // discard admin room invite
+    invite_receiver.recv_timeout(default_timeout()).unwrap();
+
+    let joined = WebhookMembership {
+        event: MembershipEvent::RoomJoined,
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "not_bridged_channel_id".to_string(),
+        channel_name: Some("not_bridged_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+    };
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &to_string(&joined).unwrap());
+
+    let left = WebhookMembership {
+        event: MembershipEvent::RoomLeft,
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "not_bridged_channel_id".to_string(),
+        channel_name: Some("not_bridged_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+    };
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &to_string(&left).unwrap());
+
+    assert!(invite_receiver.recv_timeout(default_timeout()).is_err());
+    assert!(leave_receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn the_room_name_is_updated_when_the_rocketchat_channel_is_renamed() {
+    let test = Test::new();
+    let (state_forwarder, state_receiver) = handlers::SendRoomState::with_forwarder();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendStateEventForEmptyKeyEndpoint::router_path(), state_forwarder, "send_state_event_for_key");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // discard all room states that were set during the setup
+    while state_receiver.recv_timeout(default_timeout()).is_ok() {}
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "renamed_channel".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: Some("r".to_string()),
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let state_message = state_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(state_message.contains("\"name\":\"renamed_channel\""));
+}
+
+#[test]
+fn the_room_topic_is_updated_when_the_rocketchat_channel_topic_changes() {
+    let test = Test::new();
+    let (state_forwarder, state_receiver) = handlers::SendRoomState::with_forwarder();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendStateEventForEmptyKeyEndpoint::router_path(), state_forwarder, "send_state_event_for_key");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // discard all room states that were set during the setup
+    while state_receiver.recv_timeout(default_timeout()).is_ok() {}
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "New topic".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: Some("room_changed_topic".to_string()),
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let state_message = state_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(state_message.contains("\"topic\":\"New topic\""));
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room_id = RoomId::try_from("!spec_channel_id:localhost").unwrap();
+    assert_eq!(matrix_api.get_room_topic(room_id).unwrap(), Some("New topic".to_string()));
+}
+
+#[test]
+fn the_room_topic_is_not_updated_when_it_did_not_change() {
+    let test = Test::new();
+    let (state_forwarder, state_receiver) = handlers::SendRoomState::with_forwarder();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendStateEventForEmptyKeyEndpoint::router_path(), state_forwarder, "send_state_event_for_key");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // discard all room states that were set during the setup
+    while state_receiver.recv_timeout(default_timeout()).is_ok() {}
+
+    let mut message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "New topic".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: Some("room_changed_topic".to_string()),
+    };
+    let payload = to_string(&message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let state_message = state_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(state_message.contains("\"topic\":\"New topic\""));
+
+    message.message_id = "other_spec_id".to_string();
+    let payload = to_string(&message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    assert!(state_receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn the_room_topic_is_updated_when_the_rocketchat_channel_announcement_changes() {
+    let test = Test::new();
+    let (state_forwarder, state_receiver) = handlers::SendRoomState::with_forwarder();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendStateEventForEmptyKeyEndpoint::router_path(), state_forwarder, "send_state_event_for_key");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // discard all room states that were set during the setup
+    while state_receiver.recv_timeout(default_timeout()).is_ok() {}
+
+    let message = WebhookMessage {
+        message_id: "spec_id".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_channel_id".to_string(),
+        channel_name: Some("spec_channel".to_string()),
+        user_id: "new_user_id".to_string(),
+        user_name: "new_spec_user".to_string(),
+        text: "New announcement".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: Some("room_changed_announcement".to_string()),
+    };
+    let payload = to_string(&message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+
+    let state_message = state_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(state_message.contains("\"topic\":\"New announcement\""));
 }
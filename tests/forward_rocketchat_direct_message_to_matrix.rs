@@ -1,5 +1,6 @@
 #![feature(try_from)]
 
+extern crate diesel;
 extern crate iron;
 extern crate matrix_rocketchat;
 extern crate matrix_rocketchat_test;
@@ -15,13 +16,16 @@ use std::convert::TryFrom;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
+use diesel::{sql_query, RunQueryDsl};
 use iron::{status, Chain};
 use matrix_rocketchat::api::rocketchat::v1::{
-    Attachment, File, Message, UserInfo, CHAT_GET_MESSAGE_PATH, DM_LIST_PATH, LOGIN_PATH, ME_PATH,
+    Attachment, File, Message, UserInfo, CHAT_GET_MESSAGE_PATH, DM_LIST_PATH, IM_MEMBERS_PATH, LOGIN_PATH, ME_PATH,
 };
-use matrix_rocketchat::api::rocketchat::WebhookMessage;
+use matrix_rocketchat::api::rocketchat::{WebhookFile, WebhookMessage};
 use matrix_rocketchat::api::MatrixApi;
-use matrix_rocketchat::models::{Room, UserOnRocketchatServer};
+use matrix_rocketchat::models::{
+    DirectMessageMember, DirectMessageReceiver, DirectMessageRoom, NonDirectMessageChannel, Room, UserOnRocketchatServer,
+};
 use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, DEFAULT_LOGGER, RS_TOKEN};
 use ruma_client_api::r0::account::register::Endpoint as RegisterEndpoint;
 use ruma_client_api::r0::media::create_content::Endpoint as CreateContentEndpoint;
@@ -63,6 +67,7 @@ fn successfully_forwards_a_direct_message_to_matrix() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let first_direct_message = WebhookMessage {
@@ -73,6 +78,11 @@ fn successfully_forwards_a_direct_message_to_matrix() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let first_direct_message_payload = to_string(&first_direct_message).unwrap();
 
@@ -126,6 +136,11 @@ fn successfully_forwards_a_direct_message_to_matrix() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Yay".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let second_direct_message_payload = to_string(&second_direct_message).unwrap();
 
@@ -158,6 +173,7 @@ fn successfully_forwards_a_direct_message_to_an_existing_dm_room_on_matrix() {
         .with_connected_admin_room()
         .with_bridge_dm((room_id, vec![spec_user_id, other_user_id]))
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let first_direct_message = WebhookMessage {
@@ -168,6 +184,11 @@ fn successfully_forwards_a_direct_message_to_an_existing_dm_room_on_matrix() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let first_direct_message_payload = to_string(&first_direct_message).unwrap();
 
@@ -227,13 +248,15 @@ fn successfully_forwards_an_image_in_a_direct_message_to_matrix() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     // discard welcome message
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     // trigger room creation
@@ -245,6 +268,11 @@ fn successfully_forwards_an_image_in_a_direct_message_to_matrix() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "first message".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -264,7 +292,12 @@ fn successfully_forwards_an_image_in_a_direct_message_to_matrix() {
         channel_name: Some("spec_channel".to_string()),
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
-        text: "Uploaded an image".to_string(),
+        text: "".to_string(),
+        reactions: None,
+        tmid: None,
+        file: Some(WebhookFile { id: "spec_file_id".to_string(), name: "spec_file".to_string(), mimetype: None }),
+        attachments: None,
+        message_type: None,
     };
     let payload = to_string(&message).unwrap();
 
@@ -304,6 +337,7 @@ fn the_virtual_user_stays_in_the_direct_message_room_if_the_user_leaves() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let direct_message = WebhookMessage {
@@ -314,6 +348,11 @@ fn the_virtual_user_stays_in_the_direct_message_room_if_the_user_leaves() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -350,6 +389,125 @@ fn the_virtual_user_stays_in_the_direct_message_room_if_the_user_leaves() {
     assert!(user_ids.iter().any(|id| id == &other_user_id));
 }
 
+#[test]
+fn the_cached_direct_message_room_is_removed_when_the_user_leaves() {
+    let test = Test::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let mut direct_messages = HashMap::new();
+    direct_messages.insert("spec_user_id_other_user_id", vec!["spec_user", "other_user"]);
+    let direct_messages_list_handler =
+        handlers::RocketchatDirectMessagesList { direct_messages: direct_messages, status: status::Ok };
+    rocketchat_router.get(DM_LIST_PATH, direct_messages_list_handler, "direct_messages_list");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
+        .run();
+
+    let direct_message = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_payload = to_string(&direct_message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_payload);
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let connection = test.connection_pool.get().unwrap();
+    let spec_user_id = UserId::try_from("@spec_user:localhost").unwrap();
+    let direct_message_receiver =
+        DirectMessageReceiver::find(&connection, "rcid", "spec_user_id_other_user_id", &spec_user_id).unwrap().unwrap();
+    assert_eq!(direct_message_receiver.matrix_room_id, RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap());
+
+    helpers::leave_room(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let direct_message_receiver =
+        DirectMessageReceiver::find(&connection, "rcid", "spec_user_id_other_user_id", &spec_user_id).unwrap();
+    assert!(direct_message_receiver.is_none());
+}
+
+#[test]
+fn the_cached_direct_message_room_is_removed_when_the_user_is_banned() {
+    let test = Test::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let mut direct_messages = HashMap::new();
+    direct_messages.insert("spec_user_id_other_user_id", vec!["spec_user", "other_user"]);
+    let direct_messages_list_handler =
+        handlers::RocketchatDirectMessagesList { direct_messages: direct_messages, status: status::Ok };
+    rocketchat_router.get(DM_LIST_PATH, direct_messages_list_handler, "direct_messages_list");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
+        .run();
+
+    let direct_message = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_payload = to_string(&direct_message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_payload);
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let connection = test.connection_pool.get().unwrap();
+    let spec_user_id = UserId::try_from("@spec_user:localhost").unwrap();
+    let direct_message_receiver =
+        DirectMessageReceiver::find(&connection, "rcid", "spec_user_id_other_user_id", &spec_user_id).unwrap().unwrap();
+    assert_eq!(direct_message_receiver.matrix_room_id, RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap());
+
+    helpers::send_ban_event_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap(),
+    );
+
+    let direct_message_receiver =
+        DirectMessageReceiver::find(&connection, "rcid", "spec_user_id_other_user_id", &spec_user_id).unwrap();
+    assert!(direct_message_receiver.is_none());
+}
+
 #[test]
 fn successfully_forwards_a_direct_message_to_a_matrix_room_that_was_bridged_before() {
     let test = Test::new();
@@ -373,6 +531,7 @@ fn successfully_forwards_a_direct_message_to_a_matrix_room_that_was_bridged_befo
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let direct_message = WebhookMessage {
@@ -383,6 +542,11 @@ fn successfully_forwards_a_direct_message_to_a_matrix_room_that_was_bridged_befo
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -392,7 +556,8 @@ fn successfully_forwards_a_direct_message_to_a_matrix_room_that_was_bridged_befo
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
@@ -424,6 +589,11 @@ fn successfully_forwards_a_direct_message_to_a_matrix_room_that_was_bridged_befo
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey again".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -485,6 +655,7 @@ fn do_use_two_different_dm_rooms_when_both_users_are_on_matrix() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let other_user_sender_direct_message = WebhookMessage {
@@ -495,6 +666,11 @@ fn do_use_two_different_dm_rooms_when_both_users_are_on_matrix() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let other_user_sender_direct_message_payload = to_string(&other_user_sender_direct_message).unwrap();
 
@@ -516,7 +692,8 @@ fn do_use_two_different_dm_rooms_when_both_users_are_on_matrix() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     let other_user_sender_direct_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
@@ -568,6 +745,11 @@ fn do_use_two_different_dm_rooms_when_both_users_are_on_matrix() {
         user_id: "spec_user_id".to_string(),
         user_name: "spec_user".to_string(),
         text: "Hey you".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let spec_user_sender_direct_message_payload = to_string(&spec_user_sender_direct_message).unwrap();
 
@@ -589,13 +771,365 @@ fn do_use_two_different_dm_rooms_when_both_users_are_on_matrix() {
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard other connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard other login message
+    // discard other login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     let spec_user_sender_direct_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
     assert!(spec_user_sender_direct_message_received_by_matrix.contains("Hey you"));
 }
 
+#[test]
+fn successfully_forwards_a_group_direct_message_to_matrix() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_userother_userthird_userDMRocketChat_id");
+    let (create_room_forwarder, create_room_receiver) = handlers::MatrixCreateRoom::with_forwarder(test.config.as_url.clone());
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    matrix_router.post(CreateRoomEndpoint::router_path(), create_room_forwarder, "create_room");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("bA3xK9mQ2wLp7RtZc", vec!["spec_user", "other_user", "third_user"]))
+        .run();
+
+    let direct_message_from_rocketchat = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "bA3xK9mQ2wLp7RtZc".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_from_rocketchat_payload = to_string(&direct_message_from_rocketchat).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_from_rocketchat_payload);
+
+    // discard admin room creation message
+    create_room_receiver.recv_timeout(default_timeout()).unwrap();
+
+    let create_room_message = create_room_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(create_room_message.contains("\"name\":\"spec_user, other_user, third_user (DM Rocket.Chat)\""));
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Hey there"));
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!spec_userother_userthird_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room = Room::new(
+        &test.config,
+        &DEFAULT_LOGGER,
+        &(*matrix_api),
+        RoomId::try_from("!spec_userother_userthird_userDMRocketChat_id:localhost").unwrap(),
+    );
+    let user_ids = room.user_ids(Some(UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap())).unwrap();
+    assert!(user_ids.iter().any(|id| id == &UserId::try_from("@spec_user:localhost").unwrap()));
+    assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap()));
+    assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_third_user_id:localhost").unwrap()));
+}
+
+#[test]
+fn group_direct_messages_from_different_participants_are_forwarded_to_the_same_room() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_userother_userthird_userDMRocketChat_id");
+    let (create_room_forwarder, create_room_receiver) = handlers::MatrixCreateRoom::with_forwarder(test.config.as_url.clone());
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    matrix_router.post(CreateRoomEndpoint::router_path(), create_room_forwarder, "create_room");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("bA3xK9mQ2wLp7RtZc", vec!["spec_user", "other_user", "third_user"]))
+        .run();
+
+    let other_user_direct_message = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "bA3xK9mQ2wLp7RtZc".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let other_user_direct_message_payload = to_string(&other_user_direct_message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &other_user_direct_message_payload);
+
+    let third_user_direct_message = WebhookMessage {
+        message_id: "spec_id_2".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "bA3xK9mQ2wLp7RtZc".to_string(),
+        channel_name: None,
+        user_id: "third_user_id".to_string(),
+        user_name: "third_user".to_string(),
+        text: "Hey you".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let third_user_direct_message_payload = to_string(&third_user_direct_message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &third_user_direct_message_payload);
+
+    let other_user_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(other_user_message_received_by_matrix.contains("Hey there"));
+    let third_user_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(third_user_message_received_by_matrix.contains("Hey you"));
+
+    // discard admin room creation message
+    create_room_receiver.recv_timeout(default_timeout()).unwrap();
+    // discard group direct message room creation message
+    create_room_receiver.recv_timeout(default_timeout()).unwrap();
+
+    assert!(create_room_receiver.recv_timeout(default_timeout()).is_err());
+}
+
+#[test]
+fn the_virtual_user_of_a_participant_that_left_a_group_direct_message_leaves_the_room() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_userother_userthird_userDMRocketChat_id");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("bA3xK9mQ2wLp7RtZc", vec!["spec_user", "other_user", "third_user"]))
+        .run();
+
+    let first_direct_message = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "bA3xK9mQ2wLp7RtZc".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let first_direct_message_payload = to_string(&first_direct_message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &first_direct_message_payload);
+
+    let first_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(first_message_received_by_matrix.contains("Hey there"));
+
+    // the third user left the direct message and the stored participants are outdated
+    test.direct_message_list()
+        .lock()
+        .unwrap()
+        .insert("bA3xK9mQ2wLp7RtZc".to_string(), vec!["spec_user".to_string(), "other_user".to_string()]);
+    let connection = test.connection_pool.get().unwrap();
+    sql_query("UPDATE direct_message_members SET created_at = datetime('now', '-1 day')").execute(&*connection).unwrap();
+
+    let second_direct_message = WebhookMessage {
+        message_id: "spec_id_2".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "bA3xK9mQ2wLp7RtZc".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Yay".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let second_direct_message_payload = to_string(&second_direct_message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &second_direct_message_payload);
+
+    let second_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(second_message_received_by_matrix.contains("Yay"));
+
+    let members = DirectMessageMember::find_by_rocketchat_channel_id(&connection, "rcid", "bA3xK9mQ2wLp7RtZc").unwrap();
+    assert_eq!(members.len(), 2);
+    assert!(!members.iter().any(|member| member.rocketchat_user_id == "third_user_id"));
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room = Room::new(
+        &test.config,
+        &DEFAULT_LOGGER,
+        &(*matrix_api),
+        RoomId::try_from("!spec_userother_userthird_userDMRocketChat_id:localhost").unwrap(),
+    );
+    let user_ids = room.user_ids(Some(UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap())).unwrap();
+    assert!(user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap()));
+    assert!(!user_ids.iter().any(|id| id == &UserId::try_from("@rocketchat_rcid_third_user_id:localhost").unwrap()));
+}
+
+#[test]
+fn a_direct_message_is_recognized_by_its_participants_independent_of_the_channel_id() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("other_userDMRocketChat_id:localhost");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let mut direct_messages = HashMap::new();
+    direct_messages.insert("Kq2Lr8TyX5vWn3PsD", vec!["spec_user", "other_user"]);
+    let direct_messages_list_handler =
+        handlers::RocketchatDirectMessagesList { direct_messages: direct_messages, status: status::Ok };
+    rocketchat_router.get(DM_LIST_PATH, direct_messages_list_handler, "direct_messages_list");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("Kq2Lr8TyX5vWn3PsD", vec!["spec_user", "other_user"]))
+        .run();
+
+    let direct_message = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "Kq2Lr8TyX5vWn3PsD".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_payload = to_string(&direct_message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_payload);
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Hey there"));
+
+    let connection = test.connection_pool.get().unwrap();
+    let room_id = RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap();
+    let direct_message_room = DirectMessageRoom::find_by_matrix_room_id(&connection, &room_id).unwrap().unwrap();
+    assert_eq!(direct_message_room.rocketchat_channel_id, "Kq2Lr8TyX5vWn3PsD");
+}
+
+#[test]
+fn the_direct_message_participants_are_only_looked_up_once() {
+    let test = Test::new();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("other_userDMRocketChat_id:localhost");
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let mut direct_messages = HashMap::new();
+    direct_messages.insert("spec_user_id_other_user_id", vec!["spec_user", "other_user"]);
+    let direct_messages_list_handler =
+        handlers::RocketchatDirectMessagesList { direct_messages: direct_messages, status: status::Ok };
+    rocketchat_router.get(DM_LIST_PATH, direct_messages_list_handler, "direct_messages_list");
+    let (im_members_forwarder, im_members_receiver) = MessageForwarder::new();
+    let mut im_members_chain = Chain::new(handlers::RocketchatImMembers { direct_messages: test.direct_message_list() });
+    im_members_chain.link_before(im_members_forwarder);
+    rocketchat_router.get(IM_MEMBERS_PATH, im_members_chain, "im_members");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
+        .run();
+
+    let first_direct_message = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let first_direct_message_payload = to_string(&first_direct_message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &first_direct_message_payload);
+
+    helpers::join(
+        &test.config,
+        RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+    );
+
+    let first_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(first_message_received_by_matrix.contains("Hey there"));
+
+    let second_direct_message = WebhookMessage {
+        message_id: "spec_id_2".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Yay".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let second_direct_message_payload = to_string(&second_direct_message).unwrap();
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &second_direct_message_payload);
+
+    let second_message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(second_message_received_by_matrix.contains("Yay"));
+
+    // the participants are only looked up for the first message
+    im_members_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(im_members_receiver.recv_timeout(default_timeout()).is_err());
+
+    let connection = test.connection_pool.get().unwrap();
+    let members =
+        DirectMessageMember::find_by_rocketchat_channel_id(&connection, "rcid", "spec_user_id_other_user_id").unwrap();
+    assert_eq!(members.len(), 2);
+    assert!(members.iter().any(|member| member.rocketchat_user_id == "spec_user_id"));
+    assert!(members.iter().any(|member| member.rocketchat_user_id == "other_user_id"));
+
+    let room_id = RoomId::try_from("!other_userDMRocketChat_id:localhost").unwrap();
+    let direct_message_room = DirectMessageRoom::find_by_matrix_room_id(&connection, &room_id).unwrap().unwrap();
+    assert_eq!(direct_message_room.rocketchat_server_id, "rcid");
+    assert_eq!(direct_message_room.rocketchat_channel_id, "spec_user_id_other_user_id");
+    assert_eq!(direct_message_room.matrix_query_user_id, UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap());
+}
+
 #[test]
 fn do_not_forward_a_direct_message_if_the_receiver_is_the_senders_virtual_user() {
     let test = Test::new();
@@ -615,6 +1149,7 @@ fn do_not_forward_a_direct_message_if_the_receiver_is_the_senders_virtual_user()
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let first_direct_message = WebhookMessage {
@@ -625,6 +1160,11 @@ fn do_not_forward_a_direct_message_if_the_receiver_is_the_senders_virtual_user()
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let first_direct_message_payload = to_string(&first_direct_message).unwrap();
 
@@ -647,6 +1187,11 @@ fn do_not_forward_a_direct_message_if_the_receiver_is_the_senders_virtual_user()
         user_id: "spec_user_id".to_string(),
         user_name: "spec_user".to_string(),
         text: "This will not be forwarded".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let second_direct_message_payload = to_string(&message_from_receiver_virtual_user).unwrap();
 
@@ -679,6 +1224,7 @@ fn do_not_forwards_a_direct_message_to_a_room_if_the_user_is_no_longer_logged_in
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let direct_message = WebhookMessage {
@@ -689,6 +1235,11 @@ fn do_not_forwards_a_direct_message_to_a_room_if_the_user_is_no_longer_logged_in
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -698,7 +1249,8 @@ fn do_not_forwards_a_direct_message_to_a_room_if_the_user_is_no_longer_logged_in
     receiver.recv_timeout(default_timeout()).unwrap();
     // discard connect message
     receiver.recv_timeout(default_timeout()).unwrap();
-    // discard login message
+    // discard login messages
+    receiver.recv_timeout(default_timeout()).unwrap();
     receiver.recv_timeout(default_timeout()).unwrap();
 
     let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
@@ -735,6 +1287,11 @@ fn do_not_forwards_a_direct_message_to_a_room_if_the_user_is_no_longer_logged_in
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey again".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -760,6 +1317,7 @@ fn no_room_is_created_when_the_user_doesn_not_have_access_to_the_matching_direct
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let direct_message = WebhookMessage {
@@ -770,6 +1328,11 @@ fn no_room_is_created_when_the_user_doesn_not_have_access_to_the_matching_direct
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -799,6 +1362,11 @@ fn no_room_is_created_when_no_matching_user_for_the_room_name_is_found() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -810,6 +1378,99 @@ fn no_room_is_created_when_no_matching_user_for_the_room_name_is_found() {
     assert!(create_room_receiver.recv_timeout(default_timeout()).is_err());
 }
 
+#[test]
+fn channels_that_are_not_direct_messages_are_only_looked_up_once() {
+    let test = Test::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let (im_members_forwarder, im_members_receiver) = MessageForwarder::new();
+    let mut im_members_chain = Chain::new(handlers::RocketchatImMembers { direct_messages: test.direct_message_list() });
+    im_members_chain.link_before(im_members_forwarder);
+    rocketchat_router.get(IM_MEMBERS_PATH, im_members_chain, "im_members");
+
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    for message_id in &["spec_id_1", "spec_id_2", "spec_id_3"] {
+        let message = WebhookMessage {
+            message_id: message_id.to_string(),
+            token: Some(RS_TOKEN.to_string()),
+            channel_id: "unbridged_channel_id".to_string(),
+            channel_name: Some("unbridged_channel".to_string()),
+            user_id: "other_user_id".to_string(),
+            user_name: "other_user".to_string(),
+            text: "Hey there".to_string(),
+            reactions: None,
+            tmid: None,
+            file: None,
+            attachments: None,
+            message_type: None,
+        };
+        let payload = to_string(&message).unwrap();
+        helpers::simulate_message_from_rocketchat(&test.config.as_url, &payload);
+    }
+
+    // the channel is only looked up for the first message
+    im_members_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(im_members_receiver.recv_timeout(default_timeout()).is_err());
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(NonDirectMessageChannel::is_known(&connection, "rcid", "unbridged_channel_id", 60).unwrap());
+}
+
+#[test]
+fn no_room_is_created_when_no_logged_in_user_can_look_up_the_direct_message_participants() {
+    let test = Test::new();
+    let (create_room_forwarder, create_room_receiver) = handlers::MatrixCreateRoom::with_forwarder(test.config.as_url.clone());
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.post(CreateRoomEndpoint::router_path(), create_room_forwarder, "create_room");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        IM_MEMBERS_PATH,
+        handlers::RocketchatErrorResponder { status: status::BadRequest, message: "Room not found".to_string() },
+        "im_members",
+    );
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    let direct_message = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_payload = to_string(&direct_message).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_payload);
+
+    // discard admin room creation
+    create_room_receiver.recv_timeout(default_timeout()).unwrap();
+    // no room is created on the Matrix server
+    assert!(create_room_receiver.recv_timeout(default_timeout()).is_err());
+
+    let connection = test.connection_pool.get().unwrap();
+    let members =
+        DirectMessageMember::find_by_rocketchat_channel_id(&connection, "rcid", "spec_user_id_other_user_id").unwrap();
+    assert!(members.is_empty());
+}
+
 #[test]
 fn no_room_is_created_when_getting_the_direct_message_list_failes() {
     let test = Test::new();
@@ -829,6 +1490,7 @@ fn no_room_is_created_when_getting_the_direct_message_list_failes() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let direct_message = WebhookMessage {
@@ -839,6 +1501,11 @@ fn no_room_is_created_when_getting_the_direct_message_list_failes() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -873,6 +1540,7 @@ fn no_additional_room_is_created_when_getting_the_initial_sync_failes() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let first_direct_message = WebhookMessage {
@@ -883,6 +1551,11 @@ fn no_additional_room_is_created_when_getting_the_initial_sync_failes() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
 
     let second_direct_message_payload = to_string(&first_direct_message).unwrap();
@@ -897,6 +1570,11 @@ fn no_additional_room_is_created_when_getting_the_initial_sync_failes() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let first_direct_message_payload = to_string(&second_direct_message).unwrap();
 
@@ -940,6 +1618,7 @@ fn no_room_is_created_when_getting_the_displayname_failes() {
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     error_responder_active.store(true, Ordering::Relaxed);
@@ -952,6 +1631,11 @@ fn no_room_is_created_when_getting_the_displayname_failes() {
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -978,6 +1662,7 @@ fn no_room_is_created_when_the_direct_message_list_response_cannot_be_deserializ
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let direct_message = WebhookMessage {
@@ -988,6 +1673,11 @@ fn no_room_is_created_when_the_direct_message_list_response_cannot_be_deserializ
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey again".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
@@ -1020,6 +1710,7 @@ fn no_additional_room_is_created_when_getting_the_initial_sync_response_cannot_b
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let first_direct_message = WebhookMessage {
@@ -1030,6 +1721,11 @@ fn no_additional_room_is_created_when_getting_the_initial_sync_response_cannot_b
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
 
     let second_direct_message_payload = to_string(&first_direct_message).unwrap();
@@ -1044,6 +1740,11 @@ fn no_additional_room_is_created_when_getting_the_initial_sync_response_cannot_b
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey again".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let first_direct_message_payload = to_string(&second_direct_message).unwrap();
 
@@ -1082,6 +1783,7 @@ fn no_room_is_created_when_getting_the_displayname_respones_cannot_be_deserializ
         .with_custom_rocketchat_routes(rocketchat_router)
         .with_connected_admin_room()
         .with_logged_in_user()
+        .with_direct_message(("spec_user_id_other_user_id", vec!["spec_user", "other_user"]))
         .run();
 
     let direct_message = WebhookMessage {
@@ -1092,6 +1794,11 @@ fn no_room_is_created_when_getting_the_displayname_respones_cannot_be_deserializ
         user_id: "other_user_id".to_string(),
         user_name: "other_user".to_string(),
         text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
     };
     let direct_message_payload = to_string(&direct_message).unwrap();
 
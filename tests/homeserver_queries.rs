@@ -0,0 +1,202 @@
+#![feature(try_from)]
+
+extern crate http;
+extern crate iron;
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate router;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use http::{Method, StatusCode};
+use iron::status;
+use matrix_rocketchat::api::rocketchat::v1::USERS_INFO_PATH;
+use matrix_rocketchat::api::{MatrixApi, RequestData, RestApi};
+use matrix_rocketchat::models::BridgedRoom;
+use matrix_rocketchat_test::{handlers, Test, DEFAULT_LOGGER, HS_TOKEN};
+use ruma_client_api::r0::room::create_room::Endpoint as CreateRoomEndpoint;
+use ruma_client_api::r0::send::send_state_event_for_empty_key::Endpoint as SendStateEventForEmptyKeyEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomAliasId, RoomId, UserId};
+
+#[test]
+fn successfully_register_a_virtual_user_when_the_homeserver_queries_it() {
+    let test = Test::new().with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "users", "@rocketchat_rcid_other_user_id:localhost");
+    assert_eq!(status_code, StatusCode::OK);
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let user_id = UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap();
+    let display_name = matrix_api.get_display_name(user_id).unwrap();
+    assert_eq!(display_name, Some("other_user".to_string()));
+}
+
+#[test]
+fn the_homeserver_gets_not_found_when_the_queried_rocketchat_user_does_not_exist() {
+    let test = Test::new();
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    rocketchat_router.get(
+        USERS_INFO_PATH,
+        handlers::RocketchatErrorResponder { message: "User not found".to_string(), status: status::BadRequest },
+        "users_info",
+    );
+    let test = test
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    let status_code = query(&test.config.as_url, "users", "@rocketchat_rcid_unknown_user_id:localhost");
+    assert_eq!(status_code, StatusCode::NOT_FOUND);
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let user_id = UserId::try_from("@rocketchat_rcid_unknown_user_id:localhost").unwrap();
+    assert!(matrix_api.get_display_name(user_id).unwrap().is_none());
+}
+
+#[test]
+fn the_homeserver_gets_not_found_when_the_queried_user_is_not_a_virtual_user() {
+    let test = Test::new().with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "users", "@other_user:localhost");
+    assert_eq!(status_code, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn the_homeserver_gets_not_found_when_the_server_of_the_queried_user_is_not_connected() {
+    let test = Test::new().with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "users", "@rocketchat_unknownid_other_user_id:localhost");
+    assert_eq!(status_code, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn successfully_bridge_a_channel_when_the_homeserver_queries_its_room_alias() {
+    let test = Test::new();
+    let (create_room_forwarder, create_room_receiver) = handlers::MatrixCreateRoom::with_forwarder(test.config.as_url.clone());
+    let (state_forwarder, state_receiver) = handlers::SendRoomState::with_forwarder();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.post(CreateRoomEndpoint::router_path(), create_room_forwarder, "create_room");
+    matrix_router.put(SendStateEventForEmptyKeyEndpoint::router_path(), state_forwarder, "send_state_event_for_key");
+
+    let channels = test.channel_list();
+    channels.lock().unwrap().insert("joined_channel", vec!["spec_user", "other_user"]);
+
+    let test =
+        test.with_matrix_routes(matrix_router).with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "rooms", "%23rocketchat%23rcid%23joined_channel_id:localhost");
+    assert_eq!(status_code, StatusCode::OK);
+
+    let create_room_messages: Vec<String> = create_room_receiver.try_iter().collect();
+    let create_room_message =
+        create_room_messages.iter().find(|message| message.contains("\"name\":\"joined_channel\"")).unwrap();
+    assert!(create_room_message.contains("\"room_alias_name\":\"rocketchat#rcid#joined_channel_id\""));
+
+    // the room is public, so that any user can join it by alias
+    let state_messages: Vec<String> = state_receiver.try_iter().collect();
+    assert!(state_messages.iter().any(|message| message.contains("\"join_rule\":\"public\"")));
+
+    let connection = test.connection_pool.get().unwrap();
+    let bridged_room = BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "joined_channel_id").unwrap().unwrap();
+    assert_eq!(bridged_room.matrix_room_id, RoomId::try_from("!joined_channel_id:localhost").unwrap());
+    assert_eq!(bridged_room.kind, Some("channel".to_string()));
+    assert_eq!(bridged_room.created_by, Some(UserId::try_from("@spec_user:localhost").unwrap()));
+}
+
+#[test]
+fn the_alias_of_an_already_bridged_channel_is_added_again_when_the_homeserver_queries_it() {
+    let test = Test::new();
+    let (state_forwarder, state_receiver) = handlers::SendRoomState::with_forwarder();
+    let mut matrix_router = test.default_matrix_routes();
+    matrix_router.put(SendStateEventForEmptyKeyEndpoint::router_path(), state_forwarder, "send_state_event_for_key");
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let matrix_api = MatrixApi::new(&test.config, DEFAULT_LOGGER.clone()).unwrap();
+    let room_alias_id = RoomAliasId::try_from("#rocketchat#rcid#spec_channel_id:localhost").unwrap();
+    matrix_api.delete_room_alias(room_alias_id.clone()).unwrap();
+    assert!(matrix_api.get_room_alias(room_alias_id.clone()).unwrap().is_none());
+
+    let status_code = query(&test.config.as_url, "rooms", "%23rocketchat%23rcid%23spec_channel_id:localhost");
+    assert_eq!(status_code, StatusCode::OK);
+
+    let room_id = matrix_api.get_room_alias(room_alias_id).unwrap().unwrap();
+    assert_eq!(room_id, RoomId::try_from("!spec_channel_id:localhost").unwrap());
+
+    let state_messages: Vec<String> = state_receiver.try_iter().collect();
+    assert!(state_messages.iter().any(|message| message.contains("\"alias\":\"#rocketchat#rcid#spec_channel_id:localhost\"")));
+}
+
+#[test]
+fn private_groups_are_not_bridged_when_the_homeserver_queries_their_room_alias() {
+    let test = Test::new();
+    let groups = test.group_list();
+    groups.lock().unwrap().insert("spec_group", vec!["spec_user"]);
+    let test = test.with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "rooms", "%23rocketchat%23rcid%23spec_group_id:localhost");
+    assert_eq!(status_code, StatusCode::NOT_FOUND);
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "spec_group_id").unwrap().is_none());
+}
+
+#[test]
+fn channels_are_not_bridged_when_no_logged_in_user_is_a_member() {
+    let test = Test::new();
+    let channels = test.channel_list();
+    channels.lock().unwrap().insert("other_channel", vec!["other_user"]);
+    let test = test.with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "rooms", "%23rocketchat%23rcid%23other_channel_id:localhost");
+    assert_eq!(status_code, StatusCode::NOT_FOUND);
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(BridgedRoom::find_by_rocketchat_channel_id(&connection, "rcid", "other_channel_id").unwrap().is_none());
+}
+
+#[test]
+fn the_homeserver_gets_not_found_when_the_queried_channel_does_not_exist() {
+    let test = Test::new().with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "rooms", "%23rocketchat%23rcid%23unknown_channel_id:localhost");
+    assert_eq!(status_code, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn the_homeserver_gets_not_found_when_the_queried_room_alias_is_not_in_the_namespace() {
+    let test = Test::new().with_rocketchat_mock().with_connected_admin_room().with_logged_in_user().run();
+
+    let status_code = query(&test.config.as_url, "rooms", "%23other%23rcid%23joined_channel_id:localhost");
+    assert_eq!(status_code, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn returns_unauthorized_when_the_hs_access_token_is_missing_for_a_query() {
+    let test = Test::new().run();
+    let url = format!("{}/rooms/{}", test.config.as_url, "%23rocketchat%23rcid%23joined_channel_id:localhost");
+    let params = HashMap::new();
+
+    let (_, status_code) = RestApi::call(&Method::GET, &url, RequestData::Body("".to_string()), &params, None).unwrap();
+
+    assert_eq!(status_code, StatusCode::UNAUTHORIZED);
+}
+
+fn query(as_url: &str, kind: &str, id: &str) -> StatusCode {
+    let url = format!("{}/{}/{}", as_url, kind, id);
+    let mut params = HashMap::new();
+    params.insert("access_token", HS_TOKEN);
+    let (_, status_code) = RestApi::call(&Method::GET, &url, RequestData::Body("".to_string()), &params, None).unwrap();
+    status_code
+}
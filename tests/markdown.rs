@@ -0,0 +1,140 @@
+extern crate matrix_rocketchat;
+
+use matrix_rocketchat::markdown::{escape_markdown, html_to_rocketchat, rocketchat_to_html};
+
+#[test]
+fn single_asterisks_are_rendered_as_bold_text() {
+    assert_eq!(rocketchat_to_html("*bold*"), "<p><strong>bold</strong></p>");
+    assert_eq!(rocketchat_to_html("*bold* and *more*"), "<p><strong>bold</strong> and <strong>more</strong></p>");
+}
+
+#[test]
+fn double_asterisks_are_rendered_as_bold_text() {
+    assert_eq!(rocketchat_to_html("**bold**"), "<p><strong>bold</strong></p>");
+}
+
+#[test]
+fn asterisks_that_are_surrounded_by_whitespace_are_kept() {
+    assert_eq!(rocketchat_to_html("a * b * c"), "<p>a * b * c</p>");
+}
+
+#[test]
+fn escaped_text_is_rendered_as_it_is() {
+    assert_eq!(
+        rocketchat_to_html(&escape_markdown("*not bold* ~not struck~ [no](link)")),
+        "<p>*not bold* ~not struck~ [no](link)</p>"
+    );
+}
+
+#[test]
+fn underscores_are_rendered_as_italic_text() {
+    assert_eq!(rocketchat_to_html("_italic_"), "<p><em>italic</em></p>");
+}
+
+#[test]
+fn underscores_within_words_are_kept() {
+    assert_eq!(rocketchat_to_html("snake_case_name"), "<p>snake_case_name</p>");
+}
+
+#[test]
+fn single_tildes_are_rendered_as_strikethrough_text() {
+    assert_eq!(rocketchat_to_html("~strike~"), "<p><del>strike</del></p>");
+}
+
+#[test]
+fn tildes_that_are_followed_by_whitespace_are_kept() {
+    assert_eq!(rocketchat_to_html("~not struck ~"), "<p>~not struck ~</p>");
+}
+
+#[test]
+fn markup_in_inline_code_is_not_rendered() {
+    assert_eq!(rocketchat_to_html("`*not bold*`"), "<p><code>*not bold*</code></p>");
+}
+
+#[test]
+fn markup_in_code_blocks_is_not_rendered() {
+    assert_eq!(rocketchat_to_html("```\n*not bold*\n```"), "<pre><code>*not bold*\n</code></pre>");
+}
+
+#[test]
+fn quotes_are_rendered_as_blockquotes() {
+    assert_eq!(rocketchat_to_html("> quote"), "<blockquote>\n<p>quote</p>\n</blockquote>");
+}
+
+#[test]
+fn line_breaks_are_kept() {
+    assert_eq!(rocketchat_to_html("first line\nsecond line"), "<p>first line<br />\nsecond line</p>");
+}
+
+#[test]
+fn markdown_links_are_rendered() {
+    assert_eq!(rocketchat_to_html("[link](https://example.com)"), "<p><a href=\"https://example.com\">link</a></p>");
+}
+
+#[test]
+fn rocketchat_links_are_rendered() {
+    assert_eq!(rocketchat_to_html("<https://example.com|link>"), "<p><a href=\"https://example.com\">link</a></p>");
+}
+
+#[test]
+fn links_with_a_not_allowed_scheme_are_rendered_as_text() {
+    assert_eq!(rocketchat_to_html("[click](javascript:alert(1))"), "<p>click</p>");
+}
+
+#[test]
+fn html_is_escaped() {
+    let html = rocketchat_to_html("<script>alert(1)</script>");
+    assert!(html.contains("&lt;script&gt;"));
+    assert!(!html.contains("<script>"));
+
+    let html = rocketchat_to_html("some <b>inline</b> html");
+    assert!(html.contains("&lt;b&gt;inline&lt;/b&gt;"));
+}
+
+#[test]
+fn bold_italic_and_strikethrough_html_is_converted_to_rocketchat_markdown() {
+    assert_eq!(html_to_rocketchat("<strong>bold</strong> and <em>italic</em>"), "*bold* and _italic_");
+    assert_eq!(html_to_rocketchat("<b>bold</b> and <i>italic</i>"), "*bold* and _italic_");
+    assert_eq!(html_to_rocketchat("<del>gone</del>"), "~gone~");
+}
+
+#[test]
+fn inline_code_html_is_converted_to_rocketchat_markdown() {
+    assert_eq!(html_to_rocketchat("use <code>cargo build</code>"), "use `cargo build`");
+}
+
+#[test]
+fn code_block_html_is_converted_to_rocketchat_markdown() {
+    let html = "<pre><code class=\"language-rust\">fn main() {\n    println!(&quot;hi&quot;);\n}\n</code></pre>";
+    assert_eq!(html_to_rocketchat(html), "```\nfn main() {\n    println!(\"hi\");\n}\n```");
+}
+
+#[test]
+fn link_html_is_converted_to_rocketchat_markdown() {
+    assert_eq!(html_to_rocketchat("<a href=\"https://example.com\">example</a>"), "[example](https://example.com)");
+    assert_eq!(html_to_rocketchat("<a href=\"https://example.com\">https://example.com</a>"), "https://example.com");
+}
+
+#[test]
+fn blockquote_html_is_converted_to_rocketchat_markdown() {
+    let html = "<blockquote>\n<p>quoted</p>\n<p>more</p>\n</blockquote>\n<p>reply</p>";
+    assert_eq!(html_to_rocketchat(html), "> quoted\n> more\nreply");
+}
+
+#[test]
+fn list_html_is_converted_to_rocketchat_markdown() {
+    assert_eq!(html_to_rocketchat("<ul>\n<li>one</li>\n<li>two</li>\n</ul>"), "- one\n- two");
+    assert_eq!(html_to_rocketchat("<ol><li>one</li><li>two</li></ol>"), "1. one\n2. two");
+    assert_eq!(html_to_rocketchat("<ul><li>one<ul><li>nested</li></ul></li></ul>"), "- one\n  - nested");
+}
+
+#[test]
+fn line_breaks_and_paragraphs_are_converted_to_newlines() {
+    assert_eq!(html_to_rocketchat("line one<br />line two"), "line one\nline two");
+    assert_eq!(html_to_rocketchat("<p>first</p>\n<p>second</p>"), "first\nsecond");
+}
+
+#[test]
+fn html_entities_are_decoded() {
+    assert_eq!(html_to_rocketchat("&lt;b&gt; &amp; &quot;x&quot; &#39;y&#39; &#x1F44D;"), "<b> & \"x\" 'y' \u{1f44d}");
+}
@@ -21,7 +21,7 @@ use matrix_rocketchat::errors::{MatrixErrorResponse, RocketchatErrorResponse};
 use persistent::Write;
 use router::Router;
 use ruma_client_api::r0::account::register;
-use ruma_client_api::r0::alias::get_alias;
+use ruma_client_api::r0::alias::{create_alias, get_alias};
 use ruma_client_api::r0::media::create_content;
 use ruma_client_api::r0::membership::invite_user;
 use ruma_client_api::r0::profile::{get_display_name, set_display_name};
@@ -149,6 +149,44 @@ impl Handler for RocketchatChannelsList {
     }
 }
 
+pub struct RocketchatChannelsInfo {
+    pub channels: Arc<Mutex<HashMap<&'static str, Vec<&'static str>>>>,
+}
+
+impl Handler for RocketchatChannelsInfo {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Rocket.Chat mock server got channel info request");
+
+        let url: Url = request.url.clone().into();
+        let mut query_pairs = url.query_pairs();
+        let (_, room_id) = query_pairs.find(|&(ref key, _)| key == "roomId").unwrap_or_default();
+        // the IDs of the channels on the mock server are built by appending `_id` to the channel name
+        let channel_name = room_id.replace("_id", "");
+
+        if !self.channels.lock().unwrap().contains_key(channel_name.as_str()) {
+            let error_response = RocketchatErrorResponse {
+                status: Some("error".to_string()),
+                message: Some("The required \"roomId\" or \"roomName\" param provided does not match any channel".to_string()),
+                error: None,
+            };
+            let payload = serde_json::to_string(&error_response).unwrap();
+            return Ok(Response::with((status::BadRequest, payload)));
+        }
+
+        let payload = r#"{
+            "channel": {
+                "_id": "CHANNEL_NAME_id",
+                "name": "CHANNEL_NAME",
+                "t": "c"
+            },
+            "success": true
+        }"#
+        .replace("CHANNEL_NAME", &channel_name);
+
+        Ok(Response::with((status::Ok, payload)))
+    }
+}
+
 pub struct RocketchatRoomMembers {
     pub channels: Arc<Mutex<HashMap<&'static str, Vec<&'static str>>>>,
     pub status: status::Status,
@@ -296,6 +334,76 @@ impl Handler for RocketchatDirectMessagesList {
     }
 }
 
+pub struct RocketchatImCreate {
+    pub direct_messages: Arc<Mutex<HashMap<String, Vec<String>>>>,
+}
+
+impl Handler for RocketchatImCreate {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Rocket.Chat mock server got im create request");
+
+        let user_id_raw = request.headers.get_raw("X-User-Id").unwrap();
+        let user_id_header = (*user_id_raw).first().unwrap();
+        let user_id = String::from_utf8(user_id_header.to_vec()).unwrap();
+        let request_payload = extract_payload(request);
+        let im_create_payload: serde_json::Value = serde_json::from_str(&request_payload).unwrap();
+        let username = im_create_payload["username"].as_str().unwrap();
+
+        // the IDs of the users on the mock server are built by appending `_id` to the username
+        let channel = Channel { id: format!("{}_{}_id", user_id, username), name: None };
+        let participants = vec![user_id.trim_right_matches("_id").to_string(), username.to_string()];
+        self.direct_messages.lock().unwrap().insert(channel.id.clone(), participants);
+        let payload = format!("{{\"room\": {}, \"success\": true}}", serde_json::to_string(&channel).unwrap());
+
+        Ok(Response::with((status::Ok, payload)))
+    }
+}
+
+pub struct RocketchatImMembers {
+    pub direct_messages: Arc<Mutex<HashMap<String, Vec<String>>>>,
+}
+
+impl Handler for RocketchatImMembers {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Rocket.Chat mock server got im members request");
+
+        let user_id_raw = request.headers.get_raw("X-User-Id").unwrap();
+        let user_id_header = (*user_id_raw).first().unwrap();
+        let user_id = String::from_utf8(user_id_header.to_vec()).unwrap();
+        let url: Url = request.url.clone().into();
+        let mut query_pairs = url.query_pairs();
+        let (_, room_id) = query_pairs.find(|&(ref key, _)| key == "roomId").unwrap_or_default();
+
+        // only participants of a direct message channel can get its members, the IDs of the users
+        // on the mock server are built by appending `_id` to the username
+        let members: Vec<User> = match self.direct_messages.lock().unwrap().get(&*room_id) {
+            Some(usernames) => {
+                usernames.iter().map(|username| User { id: format!("{}_id", username), username: username.clone() }).collect()
+            }
+            None => Vec::new(),
+        };
+
+        if !members.iter().any(|member| member.id == user_id) {
+            let error_response = RocketchatErrorResponse {
+                status: Some("error".to_string()),
+                message: Some("Room not found".to_string()),
+                error: None,
+            };
+            let payload = serde_json::to_string(&error_response).unwrap();
+            return Ok(Response::with((status::BadRequest, payload)));
+        }
+
+        let payload = format!(
+            "{{\"members\": {}, \"count\": {}, \"offset\": 0, \"total\": {}, \"success\": true}}",
+            serde_json::to_string(&members).unwrap(),
+            members.len(),
+            members.len()
+        );
+
+        Ok(Response::with((status::Ok, payload)))
+    }
+}
+
 pub struct RocketchatMessageResponder {
     pub message: Arc<Mutex<Option<RocketchatMessage>>>,
 }
@@ -325,6 +433,26 @@ impl Handler for RocketchatMessageResponder {
     }
 }
 
+pub struct RocketchatPostMessage {
+    pub message_id: &'static str,
+}
+
+impl Handler for RocketchatPostMessage {
+    fn handle(&self, _request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Rocket.Chat mock server got post message request");
+
+        let payload = r#"{
+            "success": true,
+            "message": {
+                "_id": "MESSAGE_ID"
+            }
+        }"#
+        .replace("MESSAGE_ID", self.message_id);
+
+        Ok(Response::with((status::Ok, payload)))
+    }
+}
+
 pub struct RocketchatUsersInfo {}
 
 impl Handler for RocketchatUsersInfo {
@@ -334,8 +462,17 @@ impl Handler for RocketchatUsersInfo {
         let url: Url = request.url.clone().into();
         let mut query_pairs = url.query_pairs();
 
-        let (status, payload) = match query_pairs.find(|&(ref key, _)| key == "username") {
-            Some((_, ref username)) => (
+        // the IDs of the users on the mock server are built by appending `_id` to the username
+        let username = query_pairs.find(|&(ref key, _)| key == "username" || key == "userId").map(|(key, value)| {
+            if key == "userId" {
+                value.trim_right_matches("_id").to_string()
+            } else {
+                value.to_string()
+            }
+        });
+
+        let (status, payload) = match username {
+            Some(ref username) => (
                 status::Ok,
                 r#"{
                     "user": {
@@ -401,6 +538,30 @@ impl Handler for RocketchatFileResponder {
     }
 }
 
+pub struct RocketchatAvatarResponder {
+    pub avatar: Vec<u8>,
+    pub etag: &'static str,
+}
+
+impl Handler for RocketchatAvatarResponder {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Rocket.Chat mock server got avatar request");
+
+        let if_none_match = request.headers.get_raw("If-None-Match").and_then(|values| values.first().cloned());
+        if if_none_match == Some(self.etag.as_bytes().to_vec()) {
+            return Ok(Response::with(status::NotModified));
+        }
+
+        let mut response = Response::with((status::Ok, self.avatar.clone()));
+        // older Rocket.Chat versions don't send an ETag
+        if !self.etag.is_empty() {
+            response.headers.set_raw("ETag", vec![self.etag.as_bytes().to_vec()]);
+        }
+        response.headers.set_raw("Content-Type", vec![b"image/png".to_vec()]);
+        Ok(response)
+    }
+}
+
 #[derive(Serialize)]
 pub struct MatrixVersion {
     pub versions: Vec<&'static str>,
@@ -974,6 +1135,34 @@ impl Handler for DeleteRoomAlias {
     }
 }
 
+pub struct PutRoomAlias {}
+
+impl Handler for PutRoomAlias {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Matrix mock server got put room alias request");
+
+        let params = request.extensions.get::<Router>().unwrap().clone();
+        let url_room_alias = params.find("room_alias").unwrap();
+        let decoded_room_alias = percent_decode(url_room_alias.as_bytes()).decode_utf8().unwrap();
+        let room_alias = RoomAliasId::try_from(decoded_room_alias.as_ref()).unwrap();
+
+        let request_payload = extract_payload(request);
+        let create_alias_payload: create_alias::BodyParams = serde_json::from_str(&request_payload).unwrap();
+
+        if let Err(err) = add_alias_to_room(request, create_alias_payload.room_id, room_alias) {
+            debug!(DEFAULT_LOGGER, "{}", err);
+            let payload = r#"{
+                    "errcode":"M_UNKNOWN",
+                    "error":"ERR_MSG"
+                }"#
+            .replace("ERR_MSG", err);
+            return Ok(Response::with((status::Conflict, payload)));
+        }
+
+        Ok(Response::with((status::Ok, "{}".to_string())))
+    }
+}
+
 pub struct GetRoomState {}
 
 impl Handler for GetRoomState {
@@ -992,7 +1181,9 @@ impl Handler for GetRoomState {
         let state_result = match serde_json::from_value::<EventType>(event_type_value).unwrap() {
             EventType::RoomCreate => get_state_from_room(request, room_id, user_id.clone(), "creator".to_string()),
             EventType::RoomCanonicalAlias => get_state_from_room(request, room_id, user_id.clone(), "alias".to_string()),
+            EventType::RoomName => get_state_from_room(request, room_id, user_id.clone(), "name".to_string()),
             EventType::RoomTopic => get_state_from_room(request, room_id, user_id.clone(), "topic".to_string()),
+            EventType::RoomPowerLevels => return RoomStatePowerLevels { bot_power_level: 100 }.handle(request),
             _ => panic!("Event type {} not covered", event_type),
         };
 
@@ -1042,6 +1233,26 @@ impl Handler for RoomStateCreate {
     }
 }
 
+pub struct RoomStatePowerLevels {
+    pub bot_power_level: i64,
+}
+
+impl Handler for RoomStatePowerLevels {
+    fn handle(&self, _request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Matrix mock server got room state power levels request");
+        let payload = r#"{
+            "redact": 50,
+            "users": {
+                "@rocketchat:localhost": BOT_POWER_LEVEL
+            },
+            "users_default": 0
+        }"#
+        .replace("BOT_POWER_LEVEL", &self.bot_power_level.to_string());
+
+        Ok(Response::with((status::Ok, payload)))
+    }
+}
+
 pub struct MatrixJoinRoom {
     pub as_url: String,
     pub send_inviter: bool,
@@ -1211,6 +1422,21 @@ impl Handler for EmptyJson {
     }
 }
 
+pub struct SendMessageEvent {}
+
+impl Handler for SendMessageEvent {
+    fn handle(&self, request: &mut Request) -> IronResult<Response> {
+        debug!(DEFAULT_LOGGER, "Matrix mock server got send message event request for URL {}", request.url);
+
+        let mut values = serde_json::Map::new();
+        let event_id = EventId::new("localhost").unwrap();
+        values.insert("event_id".to_string(), serde_json::Value::String(event_id.to_string()));
+        let payload = serde_json::to_string(&values).unwrap();
+
+        Ok(Response::with((status::Ok, payload)))
+    }
+}
+
 pub struct MatrixErrorResponder {
     pub status: status::Status,
     pub message: String,
@@ -13,9 +13,11 @@ use ruma_client_api::Endpoint;
 use ruma_events::collections::all::Event;
 use ruma_events::room::member::{MemberEvent, MemberEventContent, MembershipState};
 use ruma_events::room::message::{
-    AudioInfo, AudioMessageEventContent, FileInfo, FileMessageEventContent, ImageMessageEventContent, MessageEvent,
-    MessageEventContent, MessageType, TextMessageEventContent, VideoInfo, VideoMessageEventContent,
+    AudioInfo, AudioMessageEventContent, FileInfo, FileMessageEventContent, ImageMessageEventContent,
+    LocationMessageEventContent, MessageEvent, MessageEventContent, MessageType, NoticeMessageEventContent,
+    TextMessageEventContent, VideoInfo, VideoMessageEventContent,
 };
+use ruma_events::room::redaction::{RedactionEvent, RedactionEventContent};
 use ruma_events::room::ImageInfo;
 use ruma_events::EventType;
 use ruma_identifiers::{EventId, RoomAliasId, RoomId, UserId};
@@ -123,6 +125,29 @@ pub fn send_leave_event_from_matrix(as_url: &str, room_id: RoomId, user_id: User
     simulate_message_from_matrix(as_url, &leave_payload);
 }
 
+pub fn send_ban_event_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, sender_id: UserId) {
+    let ban_event = MemberEvent {
+        content: MemberEventContent {
+            avatar_url: None,
+            displayname: None,
+            membership: MembershipState::Ban,
+            third_party_invite: None,
+        },
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomMember,
+        invite_room_state: None,
+        prev_content: None,
+        room_id: room_id,
+        state_key: format!("{}", user_id),
+        unsigned: None,
+        user_id: sender_id,
+    };
+
+    let events = Events { events: vec![Box::new(Event::RoomMember(ban_event))] };
+    let ban_payload = to_string(&events).unwrap();
+    simulate_message_from_matrix(as_url, &ban_payload);
+}
+
 pub fn send_room_message_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, body: String) {
     let message_event = MessageEvent {
         content: MessageEventContent::Text(TextMessageEventContent { body: body, msgtype: MessageType::Text }),
@@ -139,6 +164,89 @@ pub fn send_room_message_from_matrix(as_url: &str, room_id: RoomId, user_id: Use
     simulate_message_from_matrix(as_url, &payload);
 }
 
+pub fn send_related_message_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, body: String, relates_to: Value) {
+    let message_event = MessageEvent {
+        content: MessageEventContent::Text(TextMessageEventContent { body: body, msgtype: MessageType::Text }),
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomMessage,
+        room_id: room_id,
+        unsigned: None,
+        user_id: user_id,
+    };
+
+    let mut event = serde_json::to_value(Event::RoomMessage(message_event)).unwrap();
+    event["content"].as_object_mut().unwrap().insert("m.relates_to".to_string(), relates_to);
+    let payload = to_string(&json!({ "events": [event] })).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
+pub fn send_edited_message_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, edited_event_id: EventId, body: String) {
+    let message_event = MessageEvent {
+        content: MessageEventContent::Text(TextMessageEventContent { body: format!("* {}", body), msgtype: MessageType::Text }),
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomMessage,
+        room_id: room_id,
+        unsigned: None,
+        user_id: user_id,
+    };
+
+    let mut event = serde_json::to_value(Event::RoomMessage(message_event)).unwrap();
+    {
+        let content = event["content"].as_object_mut().unwrap();
+        content.insert("m.new_content".to_string(), json!({ "body": body, "msgtype": "m.text" }));
+        content.insert("m.relates_to".to_string(), json!({ "event_id": edited_event_id, "rel_type": "m.replace" }));
+    }
+    let payload = to_string(&json!({ "events": [event] })).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
+pub fn send_reaction_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, reacted_event_id: EventId, key: String) {
+    let message_event = MessageEvent {
+        content: MessageEventContent::Text(TextMessageEventContent { body: key.clone(), msgtype: MessageType::Text }),
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomMessage,
+        room_id: room_id,
+        unsigned: None,
+        user_id: user_id,
+    };
+
+    let mut event = serde_json::to_value(Event::RoomMessage(message_event)).unwrap();
+    event["type"] = json!("m.reaction");
+    event["content"] = json!({ "m.relates_to": { "event_id": reacted_event_id, "key": key, "rel_type": "m.annotation" } });
+    let payload = to_string(&json!({ "events": [event] })).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
+pub fn send_formatted_message_from_matrix(
+    as_url: &str,
+    room_id: RoomId,
+    user_id: UserId,
+    body: String,
+    formatted_body: String,
+) {
+    let message_event = MessageEvent {
+        content: MessageEventContent::Text(TextMessageEventContent { body: body, msgtype: MessageType::Text }),
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomMessage,
+        room_id: room_id,
+        unsigned: None,
+        user_id: user_id,
+    };
+
+    let mut event = serde_json::to_value(Event::RoomMessage(message_event)).unwrap();
+    {
+        let content = event["content"].as_object_mut().unwrap();
+        content.insert("format".to_string(), json!("org.matrix.custom.html"));
+        content.insert("formatted_body".to_string(), json!(formatted_body));
+    }
+    let payload = to_string(&json!({ "events": [event] })).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
 pub fn send_image_message_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, body: String, url: String) {
     let message_event = MessageEvent {
         content: MessageEventContent::Image(ImageMessageEventContent {
@@ -256,6 +364,75 @@ pub fn send_emote_message_from_matrix(as_url: &str, room_id: RoomId, user_id: Us
     simulate_message_from_matrix(as_url, &payload);
 }
 
+pub fn send_notice_message_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, body: String) {
+    let message_event = MessageEvent {
+        content: MessageEventContent::Notice(NoticeMessageEventContent { body: body, msgtype: MessageType::Notice }),
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomMessage,
+        room_id: room_id,
+        unsigned: None,
+        user_id: user_id,
+    };
+
+    let events = Events { events: vec![Box::new(Event::RoomMessage(message_event))] };
+    let payload = to_string(&events).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
+pub fn send_location_message_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, body: String, geo_uri: String) {
+    let message_event = MessageEvent {
+        content: MessageEventContent::Location(LocationMessageEventContent {
+            body: body,
+            geo_uri: geo_uri,
+            msgtype: MessageType::Location,
+            thumbnail_info: None,
+            thumbnail_url: None,
+        }),
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomMessage,
+        room_id: room_id,
+        unsigned: None,
+        user_id: user_id,
+    };
+
+    let events = Events { events: vec![Box::new(Event::RoomMessage(message_event))] };
+    let payload = to_string(&events).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
+pub fn send_redaction_event_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, redacts: EventId) {
+    let redaction_event = RedactionEvent {
+        content: RedactionEventContent { reason: None },
+        event_id: EventId::new("localhost").unwrap(),
+        event_type: EventType::RoomRedaction,
+        redacts: redacts,
+        room_id: room_id,
+        unsigned: None,
+        user_id: user_id,
+    };
+
+    let events = Events { events: vec![Box::new(Event::RoomRedaction(redaction_event))] };
+    let payload = to_string(&events).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
+pub fn send_room_state_event_from_matrix(as_url: &str, room_id: RoomId, user_id: UserId, event_type: &str, content: Value) {
+    let event = json!({
+        "content": content,
+        "event_id": EventId::new("localhost").unwrap(),
+        "room_id": room_id,
+        "sender": user_id,
+        "state_key": "",
+        "type": event_type,
+    });
+    let payload = to_string(&json!({ "events": [event] })).unwrap();
+
+    simulate_message_from_matrix(as_url, &payload);
+}
+
 pub fn simulate_message_from_matrix(as_url: &str, payload: &str) -> (String, StatusCode) {
     let url = format!("{}/transactions/{}", as_url, "specid");
     let mut params = HashMap::new();
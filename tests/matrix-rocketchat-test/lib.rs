@@ -49,7 +49,8 @@ use iron::prelude::*;
 use iron::typemap::Key;
 use iron::{status, Chain, Iron, Listening};
 use matrix_rocketchat::api::rocketchat::v1::{
-    CHANNELS_LIST_PATH, CHANNELS_MEMBERS_PATH, GROUPS_LIST_PATH, GROUPS_MEMBERS_PATH, LOGIN_PATH, ME_PATH, USERS_INFO_PATH,
+    CHANNELS_INFO_PATH, CHANNELS_LIST_PATH, CHANNELS_MEMBERS_PATH, GROUPS_LIST_PATH, GROUPS_MEMBERS_PATH, IM_CREATE_PATH,
+    IM_MEMBERS_PATH, LOGIN_PATH, ME_PATH, USERS_INFO_PATH,
 };
 use matrix_rocketchat::api::MatrixApi;
 use matrix_rocketchat::models::ConnectionPool;
@@ -59,6 +60,7 @@ use r2d2::Pool;
 use r2d2_diesel::ConnectionManager;
 use router::Router;
 use ruma_client_api::r0::account::register::Endpoint as RegisterEndpoint;
+use ruma_client_api::r0::alias::create_alias::Endpoint as CreateAliasEndpoint;
 use ruma_client_api::r0::alias::delete_alias::Endpoint as DeleteAliasEndpoint;
 use ruma_client_api::r0::alias::get_alias::Endpoint as GetAliasEndpoint;
 use ruma_client_api::r0::membership::invite_user::Endpoint as InviteUserEndpoint;
@@ -192,6 +194,9 @@ pub struct Test {
     pub channels: Arc<Mutex<HashMap<&'static str, Vec<&'static str>>>>,
     /// Configuration that is used during the test
     pub config: Config,
+    /// A list of Rocket.Chat direct messages and the usernames of their participants that are
+    /// returned when querying the Rocket.Chat mock im.members endpoint
+    pub direct_messages: Arc<Mutex<HashMap<String, Vec<String>>>>,
     /// Connection pool to get connection to the test database
     pub connection_pool: Pool<ConnectionManager<SqliteConnection>>,
     /// A list of Rocket.Chat groups that are returned when querying the Rocket.Chat mock
@@ -234,6 +239,7 @@ impl Test {
             channels: Arc::new(Mutex::new(HashMap::new())),
             config: config,
             connection_pool: connection_pool,
+            direct_messages: Arc::new(Mutex::new(HashMap::new())),
             groups: Arc::new(Mutex::new(HashMap::new())),
             hs_listening: None,
             with_logged_in_user: false,
@@ -306,6 +312,14 @@ impl Test {
         self
     }
 
+    /// Direct message channel on the Rocket.Chat server and the usernames of its participants.
+    pub fn with_direct_message(self, direct_message: (&'static str, Vec<&'static str>)) -> Test {
+        let (channel_id, usernames) = direct_message;
+        let usernames = usernames.iter().map(|username| username.to_string()).collect();
+        self.direct_messages.lock().unwrap().insert(channel_id.to_string(), usernames);
+        self
+    }
+
     /// Run the application service so that a test can interact with it.
     pub fn run(mut self) -> Test {
         self.run_matrix_homeserver_mock();
@@ -574,6 +588,7 @@ impl Test {
         router.get(GetAliasEndpoint::router_path(), get_room_alias, "get_room_alias");
 
         router.delete(DeleteAliasEndpoint::router_path(), handlers::DeleteRoomAlias {}, "delete_room_alias");
+        router.put(CreateAliasEndpoint::router_path(), handlers::PutRoomAlias {}, "put_room_alias");
 
         router.post("*", handlers::EmptyJson {}, "default_post");
         router.put("*", handlers::EmptyJson {}, "default_put");
@@ -605,6 +620,12 @@ impl Test {
             "channels_list",
         );
 
+        router.get(
+            CHANNELS_INFO_PATH,
+            handlers::RocketchatChannelsInfo { channels: Arc::clone(&self.channels) },
+            "channels_info",
+        );
+
         router.get(
             CHANNELS_MEMBERS_PATH,
             handlers::RocketchatRoomMembers { status: status::Ok, channels: Arc::clone(&self.channels) },
@@ -623,6 +644,17 @@ impl Test {
             "get_group_members",
         );
 
+        router.post(
+            IM_CREATE_PATH,
+            handlers::RocketchatImCreate { direct_messages: Arc::clone(&self.direct_messages) },
+            "im_create",
+        );
+        router.get(
+            IM_MEMBERS_PATH,
+            handlers::RocketchatImMembers { direct_messages: Arc::clone(&self.direct_messages) },
+            "im_members",
+        );
+
         router.post("*", handlers::EmptyJson {}, "default_post");
         router.put("*", handlers::EmptyJson {}, "default_put");
 
@@ -638,6 +670,11 @@ impl Test {
     pub fn group_list(&self) -> Arc<Mutex<HashMap<&'static str, Vec<&'static str>>>> {
         Arc::clone(&self.groups)
     }
+
+    /// Get a list of direct messages that is used by the Rocket.Chat mock.
+    pub fn direct_message_list(&self) -> Arc<Mutex<HashMap<String, Vec<String>>>> {
+        Arc::clone(&self.direct_messages)
+    }
 }
 
 impl Drop for Test {
@@ -674,6 +711,7 @@ pub fn build_test_config(temp_dir: &TempDir) -> Config {
         sender_localpart: "rocketchat".to_string(),
         database_url: database_url.to_string(),
         accept_remote_invites: false,
+        flag_notices_as_bot_messages: false,
         log_level: "info".to_string(),
         log_to_console: true,
         log_to_file: false,
@@ -0,0 +1,142 @@
+#![feature(try_from)]
+
+extern crate iron;
+extern crate matrix_rocketchat;
+extern crate matrix_rocketchat_test;
+extern crate router;
+extern crate ruma_client_api;
+extern crate ruma_identifiers;
+extern crate serde_json;
+
+use std::convert::TryFrom;
+
+use iron::Chain;
+use matrix_rocketchat::api::rocketchat::v1::{CHAT_POST_MESSAGE_PATH, IM_CREATE_PATH};
+use matrix_rocketchat::api::rocketchat::WebhookMessage;
+use matrix_rocketchat_test::{default_timeout, handlers, helpers, MessageForwarder, Test, RS_TOKEN};
+use ruma_client_api::r0::membership::join_room_by_id::Endpoint as JoinRoomByIdEndpoint;
+use ruma_client_api::r0::send::send_message_event::Endpoint as SendMessageEventEndpoint;
+use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomId, UserId};
+use serde_json::to_string;
+
+#[test]
+fn the_virtual_user_joins_when_it_is_invited_into_a_room_by_a_logged_in_user() {
+    let test = Test::new();
+    let mut matrix_router = test.default_matrix_routes();
+    let (join_forwarder, join_receiver) = MessageForwarder::with_path_filter("spec_dm_id");
+    let mut join_chain = Chain::new(handlers::MatrixJoinRoom { as_url: test.config.as_url.clone(), send_inviter: true });
+    join_chain.link_before(join_forwarder);
+    matrix_router.post(JoinRoomByIdEndpoint::router_path(), join_chain, "join_room");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let (im_create_forwarder, im_create_receiver) = MessageForwarder::new();
+    let mut im_create_chain = Chain::new(handlers::RocketchatImCreate { direct_messages: test.direct_message_list() });
+    im_create_chain.link_before(im_create_forwarder);
+    rocketchat_router.post(IM_CREATE_PATH, im_create_chain, "im_create");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    helpers::create_room(
+        &test.config,
+        "spec_dm",
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap(),
+    );
+
+    let im_create_message = im_create_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(im_create_message.contains("other_user"));
+    assert!(join_receiver.recv_timeout(default_timeout()).is_ok());
+}
+
+#[test]
+fn rocketchat_messages_are_forwarded_to_the_room_the_virtual_user_was_invited_into() {
+    let test = Test::new();
+    let mut matrix_router = test.default_matrix_routes();
+    let (message_forwarder, receiver) = MessageForwarder::with_path_filter("spec_dm_id");
+    matrix_router.put(SendMessageEventEndpoint::router_path(), message_forwarder, "send_message_event");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let (rocketchat_message_forwarder, rocketchat_receiver) = MessageForwarder::new();
+    rocketchat_router.post(CHAT_POST_MESSAGE_PATH, rocketchat_message_forwarder, "post_text_message");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .run();
+
+    helpers::create_room(
+        &test.config,
+        "spec_dm",
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap(),
+    );
+
+    let direct_message_from_rocketchat = WebhookMessage {
+        message_id: "spec_id_1".to_string(),
+        token: Some(RS_TOKEN.to_string()),
+        channel_id: "spec_user_id_other_user_id".to_string(),
+        channel_name: None,
+        user_id: "other_user_id".to_string(),
+        user_name: "other_user".to_string(),
+        text: "Hey there".to_string(),
+        reactions: None,
+        tmid: None,
+        file: None,
+        attachments: None,
+        message_type: None,
+    };
+    let direct_message_from_rocketchat_payload = to_string(&direct_message_from_rocketchat).unwrap();
+
+    helpers::simulate_message_from_rocketchat(&test.config.as_url, &direct_message_from_rocketchat_payload);
+
+    let message_received_by_matrix = receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_matrix.contains("Hey there"));
+
+    helpers::send_room_message_from_matrix(
+        &test.config.as_url,
+        RoomId::try_from("!spec_dm_id:localhost").unwrap(),
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        "Nice to hear from you".to_string(),
+    );
+
+    let message_received_by_rocketchat = rocketchat_receiver.recv_timeout(default_timeout()).unwrap();
+    assert!(message_received_by_rocketchat.contains("Nice to hear from you"));
+}
+
+#[test]
+fn the_virtual_user_does_not_join_when_the_inviting_user_is_not_logged_in() {
+    let test = Test::new();
+    let mut matrix_router = test.default_matrix_routes();
+    let (join_forwarder, join_receiver) = MessageForwarder::with_path_filter("spec_dm_id");
+    let mut join_chain = Chain::new(handlers::MatrixJoinRoom { as_url: test.config.as_url.clone(), send_inviter: true });
+    join_chain.link_before(join_forwarder);
+    matrix_router.post(JoinRoomByIdEndpoint::router_path(), join_chain, "join_room");
+    let mut rocketchat_router = test.default_rocketchat_routes();
+    let (im_create_forwarder, im_create_receiver) = MessageForwarder::new();
+    rocketchat_router.post(IM_CREATE_PATH, im_create_forwarder, "im_create");
+
+    let test = test
+        .with_matrix_routes(matrix_router)
+        .with_rocketchat_mock()
+        .with_custom_rocketchat_routes(rocketchat_router)
+        .with_connected_admin_room()
+        .run();
+
+    helpers::create_room(
+        &test.config,
+        "spec_dm",
+        UserId::try_from("@spec_user:localhost").unwrap(),
+        UserId::try_from("@rocketchat_rcid_other_user_id:localhost").unwrap(),
+    );
+
+    assert!(im_create_receiver.recv_timeout(default_timeout()).is_err());
+    assert!(join_receiver.recv_timeout(default_timeout()).is_err());
+}
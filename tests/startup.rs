@@ -1,25 +1,33 @@
+#![feature(try_from)]
+
+extern crate diesel;
 extern crate iron;
 extern crate matrix_rocketchat;
 #[macro_use]
 extern crate matrix_rocketchat_test;
 extern crate router;
 extern crate ruma_client_api;
+extern crate ruma_identifiers;
 extern crate slog;
 extern crate slog_json;
 extern crate slog_stream;
 extern crate slog_term;
 extern crate tempdir;
 
+use std::convert::TryFrom;
 use std::sync::mpsc::channel;
 use std::thread;
 
+use diesel::{sql_query, RunQueryDsl};
 use iron::{status, Iron, Listening};
 use matrix_rocketchat::errors::*;
+use matrix_rocketchat::models::{AdminRoom, BridgedRoom, CompletedBackfill};
 use matrix_rocketchat::Server;
-use matrix_rocketchat_test::{default_matrix_api_versions, handlers, DEFAULT_LOGGER, IRON_THREADS, TEMP_DIR_NAME};
+use matrix_rocketchat_test::{default_matrix_api_versions, handlers, Test, DEFAULT_LOGGER, IRON_THREADS, TEMP_DIR_NAME};
 use router::Router;
 use ruma_client_api::r0::account::register::Endpoint as RegisterEndpoint;
 use ruma_client_api::Endpoint;
+use ruma_identifiers::{RoomId, UserId};
 use tempdir::TempDir;
 
 #[test]
@@ -156,6 +164,68 @@ fn startup_failes_when_the_bot_user_registration_returns_invalid_json() {
     assert_error_kind!(err, ErrorKind::InvalidJSON(ref _msg));
 }
 
+#[test]
+fn rooms_that_were_bridged_and_connected_before_they_were_stored_are_backfilled_on_startup() {
+    let test = Test::new()
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    // remove the stored rooms and the completed backfills, so that the rooms are only identified
+    // by their alias and topic like the rooms that were bridged and connected by older versions of
+    // the application service
+    let connection = test.connection_pool.get().unwrap();
+    let admin_room_id = RoomId::try_from("!admin_room_id:localhost").unwrap();
+    let bridged_room_id = RoomId::try_from("!spec_channel_id:localhost").unwrap();
+    AdminRoom::find_by_matrix_room_id(&connection, &admin_room_id).unwrap().unwrap().delete(&connection).unwrap();
+    BridgedRoom::find_by_matrix_room_id(&connection, &bridged_room_id).unwrap().unwrap().delete(&connection).unwrap();
+    sql_query("DELETE FROM completed_backfills").execute(&*connection).unwrap();
+
+    restart_server(&test);
+
+    let admin_room = AdminRoom::find_by_matrix_room_id(&connection, &admin_room_id).unwrap().unwrap();
+    assert_eq!(admin_room.matrix_user_id, UserId::try_from("@spec_user:localhost").unwrap());
+    assert_eq!(admin_room.rocketchat_server_id, "rcid");
+    let bridged_room = BridgedRoom::find_by_matrix_room_id(&connection, &bridged_room_id).unwrap().unwrap();
+    assert_eq!(bridged_room.rocketchat_server_id, "rcid");
+    assert_eq!(bridged_room.rocketchat_channel_id, "spec_channel_id");
+}
+
+#[test]
+fn rooms_are_only_backfilled_once() {
+    let test = Test::new()
+        .with_rocketchat_mock()
+        .with_connected_admin_room()
+        .with_logged_in_user()
+        .with_bridged_room(("spec_channel", vec!["spec_user"]))
+        .run();
+
+    let connection = test.connection_pool.get().unwrap();
+    assert!(CompletedBackfill::is_completed(&connection, "admin_rooms").unwrap());
+    assert!(CompletedBackfill::is_completed(&connection, "bridged_rooms").unwrap());
+
+    let bridged_room_id = RoomId::try_from("!spec_channel_id:localhost").unwrap();
+    BridgedRoom::find_by_matrix_room_id(&connection, &bridged_room_id).unwrap().unwrap().delete(&connection).unwrap();
+
+    restart_server(&test);
+
+    assert!(BridgedRoom::find_by_matrix_room_id(&connection, &bridged_room_id).unwrap().is_none());
+}
+
+fn restart_server(test: &Test) {
+    let mut config = test.config.clone();
+    config.as_address = matrix_rocketchat_test::get_free_socket_addr();
+    let (server_tx, server_rx) = channel::<Result<Listening>>();
+    thread::spawn(move || {
+        let server_result = Server::new(&config, DEFAULT_LOGGER.clone()).run(IRON_THREADS);
+        server_tx.send(server_result).unwrap();
+    });
+    let server_result = server_rx.recv_timeout(matrix_rocketchat_test::default_timeout() * 2).unwrap();
+    server_result.unwrap().close().unwrap();
+}
+
 fn start_servers(matrix_router: Router) -> Result<Listening> {
     let homeserver_mock_socket_addr = matrix_rocketchat_test::get_free_socket_addr();
 